@@ -0,0 +1,65 @@
+#![no_std]
+
+//! What other contracts need to call the access-control contract: its client,
+//! the `Action` type it takes, and the resource ids it knows about patients by.
+
+use soroban_sdk::{contractclient, contracttype, Address, Bytes, Env, String, Symbol};
+
+mod test;
+
+/// Access action, mirrors `Action` in the access-control contract
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Action {
+    Read,
+    Write,
+    Amend,
+    Share,
+    Export,
+}
+
+/// Client for the shared access-control contract
+#[contractclient(name = "AccessControlClient")]
+pub trait AccessControlInterface {
+    fn check_access_for(env: Env, entity: Address, resource_id: String, action: Action) -> bool;
+
+    fn check_exact_access_for(
+        env: Env,
+        entity: Address,
+        resource_id: String,
+        action: Action,
+    ) -> bool;
+
+    fn audit_access(
+        env: Env,
+        entity: Address,
+        resource_id: String,
+        action: Action,
+        purpose: Option<Symbol>,
+    ) -> bool;
+
+    fn audit_exact_access(
+        env: Env,
+        entity: Address,
+        resource_id: String,
+        action: Action,
+        purpose: Option<Symbol>,
+    ) -> bool;
+
+    fn break_glass_access(
+        env: Env,
+        clinician: Address,
+        patient: Address,
+        resource_id: String,
+        justification: String,
+    ) -> u64;
+}
+
+/// Resource id for part of a patient's data, e.g. `patient:G.../allergies`
+pub fn patient_resource_id(env: &Env, patient: &Address, suffix: &str) -> String {
+    let mut id = Bytes::from_slice(env, b"patient:");
+    id.append(&patient.to_string().to_bytes());
+    id.push_back(b'/');
+    id.append(&Bytes::from_slice(env, suffix.as_bytes()));
+    String::from(id)
+}
@@ -0,0 +1,19 @@
+#![cfg(test)]
+
+use super::*;
+use soroban_sdk::{testutils::Address as _, Env};
+
+#[test]
+fn test_patient_resource_id() {
+    let env = Env::default();
+    let patient = Address::generate(&env);
+
+    let mut expected = Bytes::from_slice(&env, b"patient:");
+    expected.append(&patient.to_string().to_bytes());
+    expected.append(&Bytes::from_slice(&env, b"/records/labs"));
+
+    assert_eq!(
+        patient_resource_id(&env, &patient, "records/labs"),
+        String::from(expected)
+    );
+}
@@ -0,0 +1,144 @@
+//! Paginated storage indexes.
+//!
+//! Items are kept in fixed-size pages plus a slot entry per item recording its
+//! position, so adding or removing an item touches a bounded number of ledger
+//! entries no matter how large the index grows. Removal swaps the last item
+//! into the freed slot, so pages stay dense but ordering is not preserved.
+
+use crate::DataKey;
+use soroban_sdk::{Address, Env, IntoVal, String, TryFromVal, Val, Vec};
+
+/// Number of items stored per index page
+pub const PAGE_SIZE: u32 = 50;
+
+/// Storage keys describing one index
+pub struct Index<P, S>
+where
+    P: Fn(u32) -> DataKey,
+{
+    pub count: DataKey,
+    pub page: P,
+    pub slot: S,
+}
+
+impl<P, S> Index<P, S>
+where
+    P: Fn(u32) -> DataKey,
+{
+    pub fn len(&self, env: &Env) -> u32 {
+        env.storage().persistent().get(&self.count).unwrap_or(0)
+    }
+
+    pub fn page<T>(&self, env: &Env, page: u32) -> Vec<T>
+    where
+        T: IntoVal<Env, Val> + TryFromVal<Env, Val>,
+    {
+        env.storage()
+            .persistent()
+            .get(&(self.page)(page))
+            .unwrap_or(Vec::new(env))
+    }
+
+    /// Append an item; returns `false` if it is already indexed
+    pub fn insert<T>(&self, env: &Env, item: &T) -> bool
+    where
+        T: IntoVal<Env, Val> + TryFromVal<Env, Val> + Clone,
+        S: Fn(&T) -> DataKey,
+    {
+        let slot_key = (self.slot)(item);
+        if env.storage().persistent().has(&slot_key) {
+            return false;
+        }
+
+        let position = self.len(env);
+        let page_key = (self.page)(position / PAGE_SIZE);
+        let mut page: Vec<T> = env
+            .storage()
+            .persistent()
+            .get(&page_key)
+            .unwrap_or(Vec::new(env));
+        page.push_back(item.clone());
+        env.storage().persistent().set(&page_key, &page);
+        env.storage().persistent().set(&slot_key, &position);
+        env.storage().persistent().set(&self.count, &(position + 1));
+        true
+    }
+
+    /// Remove an item; returns `false` if it was not indexed
+    pub fn remove<T>(&self, env: &Env, item: &T) -> bool
+    where
+        T: IntoVal<Env, Val> + TryFromVal<Env, Val> + Clone,
+        S: Fn(&T) -> DataKey,
+    {
+        let slot_key = (self.slot)(item);
+        let position: u32 = match env.storage().persistent().get(&slot_key) {
+            Some(position) => position,
+            None => return false,
+        };
+        env.storage().persistent().remove(&slot_key);
+
+        // Detach the last item, then move it into the freed slot
+        let last = self.len(env) - 1;
+        let last_key = (self.page)(last / PAGE_SIZE);
+        let mut last_page: Vec<T> = env
+            .storage()
+            .persistent()
+            .get(&last_key)
+            .expect("Index page missing");
+        let moved = last_page.pop_back().expect("Index page missing");
+        if last_page.is_empty() {
+            env.storage().persistent().remove(&last_key);
+        } else {
+            env.storage().persistent().set(&last_key, &last_page);
+        }
+
+        if position != last {
+            let page_key = (self.page)(position / PAGE_SIZE);
+            let mut page: Vec<T> = env
+                .storage()
+                .persistent()
+                .get(&page_key)
+                .expect("Index page missing");
+            page.set(position % PAGE_SIZE, moved.clone());
+            env.storage().persistent().set(&page_key, &page);
+            env.storage()
+                .persistent()
+                .set(&(self.slot)(&moved), &position);
+        }
+
+        if last == 0 {
+            env.storage().persistent().remove(&self.count);
+        } else {
+            env.storage().persistent().set(&self.count, &last);
+        }
+        true
+    }
+}
+
+/// Resources an entity holds at least one grant on
+pub fn entity_resources(
+    entity: &Address,
+) -> Index<impl Fn(u32) -> DataKey, impl Fn(&String) -> DataKey> {
+    let (page_entity, slot_entity) = (entity.clone(), entity.clone());
+    Index {
+        count: DataKey::EntityResourceCount(entity.clone()),
+        page: move |page| DataKey::EntityResources(page_entity.clone(), page),
+        slot: move |resource_id: &String| {
+            DataKey::EntityResourceSlot(slot_entity.clone(), resource_id.clone())
+        },
+    }
+}
+
+/// Entities holding at least one grant on a resource
+pub fn resource_parties(
+    resource_id: &String,
+) -> Index<impl Fn(u32) -> DataKey, impl Fn(&Address) -> DataKey> {
+    let (page_resource, slot_resource) = (resource_id.clone(), resource_id.clone());
+    Index {
+        count: DataKey::ResourcePartyCount(resource_id.clone()),
+        page: move |page| DataKey::ResourceParties(page_resource.clone(), page),
+        slot: move |party: &Address| {
+            DataKey::ResourcePartySlot(slot_resource.clone(), party.clone())
+        },
+    }
+}
@@ -1,7 +1,11 @@
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, contracttype, symbol_short, Address, Env, String, Vec};
+use soroban_sdk::{
+    contract, contractimpl, contracttype, symbol_short, xdr::ToXdr, Address, Bytes, BytesN, Env,
+    String, Symbol, Vec,
+};
 
+mod index;
 mod test;
 
 /// --------------------
@@ -17,6 +21,43 @@ pub enum EntityType {
     Admin,
 }
 
+/// --------------------
+/// Roles
+/// --------------------
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Role {
+    Admin,
+    ComplianceOfficer,
+    RecordsClerk,
+    Verifier,
+}
+
+/// --------------------
+/// Capabilities
+/// --------------------
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Capability {
+    ManageEntities,   // Deactivate entities
+    RevokeAnyAccess,  // Revoke grants made by other entities
+    ReviewBreakGlass, // Approve or flag emergency access
+    ViewAuditLog,     // Read any entity's or resource's audit trail
+}
+
+/// --------------------
+/// Access Actions
+/// --------------------
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Action {
+    Read,
+    Write,
+    Amend,
+    Share,
+    Export,
+}
+
 /// --------------------
 /// Entity Data
 /// --------------------
@@ -29,18 +70,170 @@ pub struct EntityData {
     pub active: bool,
 }
 
+/// --------------------
+/// Entity Status
+/// --------------------
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EntityStatus {
+    Active,
+    Suspended,
+    Deactivated,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StatusChange {
+    pub status: EntityStatus,
+    pub changed_by: Address,
+    pub changed_at: u64,
+    pub until: u64, // End of a suspension, 0 otherwise
+    pub reason: Option<String>,
+}
+
 /// --------------------
 /// Access Permission
 /// --------------------
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct AccessPermission {
+    pub resource_id: String,
+    pub actions: Vec<Action>,
+    pub granted_by: Address,
+    pub granted_at: u64,
+    pub expires_at: u64, // 0 means no expiration
+}
+
+/// Grant layout held in `DataKey::AccessList` before grants were scoped to
+/// actions; such grants covered every action
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LegacyAccessPermission {
     pub resource_id: String,
     pub granted_by: Address,
     pub granted_at: u64,
+    pub expires_at: u64,
+}
+
+/// --------------------
+/// Break-Glass Access
+/// --------------------
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BreakGlassStatus {
+    PendingReview,
+    Approved,
+    Flagged,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BreakGlassRecord {
+    pub id: u64,
+    pub clinician: Address,
+    pub patient: Address,
+    pub resource_id: String,
+    pub justification: String,
+    pub granted_at: u64,
+    pub expires_at: u64,
+    pub status: BreakGlassStatus,
+    pub reviewed_by: Option<Address>,
+    pub review_note: Option<String>,
+    pub reviewed_at: u64, // 0 until reviewed
+}
+
+/// --------------------
+/// Signed Consent
+/// --------------------
+/// A grant the patient signed off-chain with their registered consent key.
+///
+/// The signed message is the XDR encoding of `(access_control_contract, payload)`,
+/// so a consent cannot be replayed against another deployment.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConsentPayload {
+    pub patient: Address,
+    pub grantee: Address,
+    pub resource_id: String,
+    pub actions: Vec<Action>,
     pub expires_at: u64, // 0 means no expiration
+    pub nonce: u64,
+}
+
+/// --------------------
+/// Delegation
+/// --------------------
+/// Identifies a delegable grant: the grant `holder` received on `resource_id`
+/// from `granted_by`, pinned to its delegation id so a later re-grant does not
+/// match.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GrantRef {
+    pub holder: Address,
+    pub resource_id: String,
+    pub granted_by: Address,
+    pub delegation_id: u64,
 }
 
+/// Where a grant came from
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum GrantOrigin {
+    Direct,              // Made by the grantor on their own authority
+    Delegated(GrantRef), // Passed on from the referenced grant
+}
+
+/// Delegation terms attached to a grant
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Delegation {
+    pub id: u64, // Issued fresh for every delegable grant, 0 for plain grants
+    pub can_delegate: bool,
+    pub max_depth: u32, // Further levels of re-delegation allowed below this grant
+    pub origin: GrantOrigin,
+}
+
+/// One step of a delegation chain
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DelegationLink {
+    pub holder: Address,
+    pub permission: AccessPermission,
+    pub delegation: Delegation,
+}
+
+/// Longest delegation chain allowed below a root grant
+const MAX_DELEGATION_DEPTH: u32 = 5;
+
+/// How long an emergency grant stays valid (4 hours)
+const BREAK_GLASS_DURATION: u64 = 4 * 60 * 60;
+
+/// --------------------
+/// Access Audit Log
+/// --------------------
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AccessOutcome {
+    Granted,
+    Denied,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuditEntry {
+    pub id: u64,
+    pub accessor: Address,
+    pub resource_id: String,
+    pub action: Action,
+    pub outcome: AccessOutcome,
+    pub timestamp: u64,
+    pub purpose: Option<Symbol>, // Purpose-of-use code, e.g. TREAT, HPAYMT, ETREAT
+}
+
+/// Largest page returned by the audit log queries
+const MAX_AUDIT_PAGE: u32 = 50;
+
 /// --------------------
 /// Storage Keys
 /// --------------------
@@ -48,8 +241,35 @@ pub struct AccessPermission {
 pub enum DataKey {
     Admin,
     Entity(Address),
-    AccessList(Address),    // Entity -> Vec<AccessPermission>
-    ResourceAccess(String), // Resource -> Vec<Address> (authorized parties)
+    AccessList(Address),        // Legacy: Entity -> Vec<LegacyAccessPermission>
+    ResourceAccess(String),     // Legacy: Resource -> Vec<Address> (authorized parties)
+    RoleMembers(Role),          // Role -> Vec<Address>
+    AccountRoles(Address),      // Account -> Vec<Role>
+    RoleCapabilities(Role),     // Role -> Vec<Capability>
+    BreakGlass(u64),            // Id -> BreakGlassRecord
+    BreakGlassCounter,          // Last issued break-glass id
+    PendingBreakGlass,          // Vec<u64> awaiting review
+    PatientBreakGlass(Address), // Patient -> Vec<u64> (full history)
+    AuditEntry(u64),            // Id -> AuditEntry
+    AuditCounter,               // Last issued audit entry id
+    EntityAudit(Address, u32),  // (Accessor, index) -> audit entry id
+    EntityAuditCount(Address),  // Accessor -> number of audit entries
+    ResourceAudit(String, u32), // (Resource, index) -> audit entry id
+    ResourceAuditCount(String), // Resource -> number of audit entries
+    Grants(Address, String),    // (Entity, resource) -> Vec<AccessPermission>, one per grantor
+    EntityResources(Address, u32), // (Entity, page) -> Vec<String> of granted resources
+    EntityResourceCount(Address), // Entity -> number of granted resources
+    EntityResourceSlot(Address, String), // (Entity, resource) -> position in the entity index
+    ResourceParties(String, u32), // (Resource, page) -> Vec<Address> of grantees
+    ResourcePartyCount(String), // Resource -> number of grantees
+    ResourcePartySlot(String, Address), // (Resource, grantee) -> position in the resource index
+    StatusHistory(Address),     // Entity -> Vec<StatusChange>
+    Suspension(Address),        // Entity -> timestamp its suspension ends
+    ConsentKey(Address),        // Patient -> ed25519 public key
+    ConsentNonce(Address, u64), // (Patient, nonce) -> used
+    Delegation(Address, String, Address), // (Holder, resource, grantor) -> Delegation
+    DelegationCounter,          // Last issued delegation id
+    ClinicianVerifier(Address), // Clinician -> admin or verifier who vouched for them
 }
 
 #[contract]
@@ -57,6 +277,186 @@ pub struct AccessControl;
 
 #[contractimpl]
 impl AccessControl {
+    fn roles_of(env: &Env, account: &Address) -> Vec<Role> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::AccountRoles(account.clone()))
+            .unwrap_or(Vec::new(env))
+    }
+
+    fn require_admin(env: &Env, account: &Address) {
+        if !Self::roles_of(env, account).contains(Role::Admin) {
+            panic!("Only admin can manage roles");
+        }
+    }
+
+    fn add_role(env: &Env, account: &Address, role: &Role) {
+        let mut roles = Self::roles_of(env, account);
+        if roles.contains(role) {
+            panic!("Role already granted");
+        }
+        roles.push_back(role.clone());
+        env.storage()
+            .persistent()
+            .set(&DataKey::AccountRoles(account.clone()), &roles);
+
+        let members_key = DataKey::RoleMembers(role.clone());
+        let mut members: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&members_key)
+            .unwrap_or(Vec::new(env));
+        members.push_back(account.clone());
+        env.storage().persistent().set(&members_key, &members);
+    }
+
+    fn account_has_capability(env: &Env, account: &Address, capability: &Capability) -> bool {
+        let roles = Self::roles_of(env, account);
+        for role in roles.iter() {
+            // Admins hold every capability
+            if role == Role::Admin {
+                return true;
+            }
+            let capabilities: Vec<Capability> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::RoleCapabilities(role))
+                .unwrap_or(Vec::new(env));
+            if capabilities.contains(capability) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Add a permission to the grantee's grants on a resource, replacing any
+    /// existing grant from the same grantor
+    fn store_permission(
+        env: &Env,
+        grantee: &Address,
+        permission: AccessPermission,
+        delegation: Option<Delegation>,
+    ) {
+        Self::migrate_legacy_grants(env, grantee);
+
+        let delegation_key = DataKey::Delegation(
+            grantee.clone(),
+            permission.resource_id.clone(),
+            permission.granted_by.clone(),
+        );
+        match delegation {
+            Some(delegation) => env.storage().persistent().set(&delegation_key, &delegation),
+            None => env.storage().persistent().remove(&delegation_key),
+        }
+
+        let resource_id = permission.resource_id.clone();
+        let mut grants = Self::load_grants(env, grantee, &resource_id);
+        let mut replaced = false;
+        for i in 0..grants.len() {
+            if grants.get_unchecked(i).granted_by == permission.granted_by {
+                grants.set(i, permission.clone());
+                replaced = true;
+                break;
+            }
+        }
+        if !replaced {
+            grants.push_back(permission);
+        }
+        Self::save_grants(env, grantee, &resource_id, &grants);
+    }
+
+    fn load_grants(env: &Env, entity: &Address, resource_id: &String) -> Vec<AccessPermission> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Grants(entity.clone(), resource_id.clone()))
+            .unwrap_or(Vec::new(env))
+    }
+
+    /// Persist an entity's grants on a resource and keep both indexes in step
+    fn save_grants(
+        env: &Env,
+        entity: &Address,
+        resource_id: &String,
+        grants: &Vec<AccessPermission>,
+    ) {
+        let key = DataKey::Grants(entity.clone(), resource_id.clone());
+        if grants.is_empty() {
+            env.storage().persistent().remove(&key);
+            index::entity_resources(entity).remove(env, resource_id);
+            index::resource_parties(resource_id).remove(env, entity);
+        } else {
+            env.storage().persistent().set(&key, grants);
+            index::entity_resources(entity).insert(env, resource_id);
+            index::resource_parties(resource_id).insert(env, entity);
+        }
+    }
+
+    /// Move an entity's legacy access list into per-resource grants
+    fn migrate_legacy_grants(env: &Env, entity: &Address) -> u32 {
+        let legacy = match Self::legacy_grants(env, entity) {
+            Some(legacy) => legacy,
+            None => return 0,
+        };
+        env.storage()
+            .persistent()
+            .remove(&DataKey::AccessList(entity.clone()));
+
+        for permission in legacy.iter() {
+            Self::remove_legacy_party(env, &permission.resource_id, entity);
+            Self::store_permission(env, entity, permission, None);
+        }
+        legacy.len()
+    }
+
+    /// An entity's legacy access list, upgraded to grants covering every action
+    fn legacy_grants(env: &Env, entity: &Address) -> Option<Vec<AccessPermission>> {
+        let legacy: Vec<LegacyAccessPermission> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::AccessList(entity.clone()))?;
+
+        let mut grants = Vec::new(env);
+        for permission in legacy.iter() {
+            grants.push_back(AccessPermission {
+                resource_id: permission.resource_id,
+                actions: Vec::from_array(
+                    env,
+                    [
+                        Action::Read,
+                        Action::Write,
+                        Action::Amend,
+                        Action::Share,
+                        Action::Export,
+                    ],
+                ),
+                granted_by: permission.granted_by,
+                granted_at: permission.granted_at,
+                expires_at: permission.expires_at,
+            });
+        }
+        Some(grants)
+    }
+
+    fn remove_legacy_party(env: &Env, resource_id: &String, party: &Address) {
+        let resource_key = DataKey::ResourceAccess(resource_id.clone());
+        let authorized: Vec<Address> = match env.storage().persistent().get(&resource_key) {
+            Some(authorized) => authorized,
+            None => return,
+        };
+
+        let mut remaining: Vec<Address> = Vec::new(env);
+        for addr in authorized.iter() {
+            if addr != *party {
+                remaining.push_back(addr);
+            }
+        }
+        if remaining.is_empty() {
+            env.storage().persistent().remove(&resource_key);
+        } else {
+            env.storage().persistent().set(&resource_key, &remaining);
+        }
+    }
+
     /// Initialize the contract with an admin
     ///
     /// # Arguments
@@ -67,6 +467,7 @@ impl AccessControl {
         }
         admin.require_auth();
         env.storage().persistent().set(&DataKey::Admin, &admin);
+        Self::add_role(&env, &admin, &Role::Admin);
 
         env.events()
             .publish((symbol_short!("init"), admin), symbol_short!("success"));
@@ -101,12 +502,7 @@ impl AccessControl {
         };
 
         env.storage().persistent().set(&key, &entity);
-
-        // Initialize empty access list for the entity
-        let empty_access: Vec<AccessPermission> = Vec::new(&env);
-        env.storage()
-            .persistent()
-            .set(&DataKey::AccessList(wallet.clone()), &empty_access);
+        Self::record_status(&env, &wallet, EntityStatus::Active, &wallet, 0, None);
 
         env.events()
             .publish((symbol_short!("reg_ent"), wallet), symbol_short!("success"));
@@ -114,202 +510,893 @@ impl AccessControl {
 
     /// Grant access permission to an entity for a specific resource
     ///
+    /// Granting the same resource again from the same grantor replaces the
+    /// previous actions and expiry. Resources under `patient:<address>` can
+    /// only be granted by that patient, or by a holder of a delegable grant
+    /// from them that covers the new grant; such a grant is then part of the
+    /// delegation chain and lapses with its parent.
+    ///
     /// # Arguments
     /// * `grantor` - The address granting access (must be authorized)
     /// * `grantee` - The address receiving access
-    /// * `resource_id` - The identifier of the resource
+    /// * `resource_id` - The identifier of the resource; a trailing `/*` grants
+    ///   access to every resource below that path
+    /// * `actions` - The actions the grantee may perform on the resource
     /// * `expires_at` - Expiration timestamp (0 for no expiration)
     pub fn grant_access(
         env: Env,
         grantor: Address,
         grantee: Address,
         resource_id: String,
+        actions: Vec<Action>,
         expires_at: u64,
     ) {
         grantor.require_auth();
+        Self::grant(
+            &env,
+            &grantor,
+            grantee,
+            resource_id,
+            actions,
+            expires_at,
+            None,
+        );
+    }
 
-        // Verify grantor is a registered entity
-        let grantor_key = DataKey::Entity(grantor.clone());
-        if !env.storage().persistent().has(&grantor_key) {
-            panic!("Grantor not registered");
-        }
+    /// Grant access that the grantee may re-delegate
+    ///
+    /// # Arguments
+    /// * `grantor` - The address granting access
+    /// * `grantee` - The address receiving access
+    /// * `resource_id` - The identifier of the resource
+    /// * `actions` - The actions the grantee may perform on the resource
+    /// * `expires_at` - Expiration timestamp (0 for no expiration)
+    /// * `max_depth` - How many levels of re-delegation are allowed (1 to 5)
+    pub fn grant_delegable_access(
+        env: Env,
+        grantor: Address,
+        grantee: Address,
+        resource_id: String,
+        actions: Vec<Action>,
+        expires_at: u64,
+        max_depth: u32,
+    ) {
+        grantor.require_auth();
 
-        // Verify grantee is a registered entity
-        let grantee_key = DataKey::Entity(grantee.clone());
-        if !env.storage().persistent().has(&grantee_key) {
-            panic!("Grantee not registered");
+        if max_depth == 0 || max_depth > MAX_DELEGATION_DEPTH {
+            panic!("Invalid delegation depth");
         }
 
-        let permission = AccessPermission {
-            resource_id: resource_id.clone(),
-            granted_by: grantor.clone(),
-            granted_at: env.ledger().timestamp(),
-            expires_at,
+        let delegation = Delegation {
+            id: Self::next_delegation_id(&env),
+            can_delegate: true,
+            max_depth,
+            origin: GrantOrigin::Direct,
         };
+        Self::grant(
+            &env,
+            &grantor,
+            grantee,
+            resource_id,
+            actions,
+            expires_at,
+            Some(delegation),
+        );
+    }
 
-        // Add permission to grantee's access list
-        let access_key = DataKey::AccessList(grantee.clone());
-        let mut access_list: Vec<AccessPermission> = env
-            .storage()
-            .persistent()
-            .get(&access_key)
-            .unwrap_or(Vec::new(&env));
+    /// Pass on part of a delegable grant to another entity
+    ///
+    /// The sub-grant must fall within a delegable grant the delegator holds:
+    /// same or narrower resource, a subset of its actions, an expiry no later
+    /// than its own and a smaller `max_depth`. It stays valid only while every
+    /// grant above it in the chain does.
+    ///
+    /// # Arguments
+    /// * `delegator` - The holder of the delegable grant
+    /// * `delegatee` - The address receiving access
+    /// * `resource_id` - The identifier of the resource
+    /// * `actions` - The actions the delegatee may perform on the resource
+    /// * `expires_at` - Expiration timestamp (0 only if the parent never expires)
+    /// * `max_depth` - How many further levels of re-delegation are allowed
+    pub fn delegate_access(
+        env: Env,
+        delegator: Address,
+        delegatee: Address,
+        resource_id: String,
+        actions: Vec<Action>,
+        expires_at: u64,
+        max_depth: u32,
+    ) {
+        delegator.require_auth();
 
-        // Check if permission already exists for this resource
-        let mut exists = false;
-        for i in 0..access_list.len() {
-            if let Some(existing) = access_list.get(i) {
-                if existing.resource_id == resource_id {
-                    exists = true;
-                    break;
-                }
-            }
+        if delegator == delegatee {
+            panic!("Cannot delegate to self");
         }
-        if exists {
-            panic!("Access already granted for this resource");
-        }
-
-        access_list.push_back(permission);
-        env.storage().persistent().set(&access_key, &access_list);
+        Self::validate_resource_id(&resource_id);
 
-        // Add grantee to resource's authorized parties
-        let resource_key = DataKey::ResourceAccess(resource_id.clone());
-        let mut authorized: Vec<Address> = env
-            .storage()
-            .persistent()
-            .get(&resource_key)
-            .unwrap_or(Vec::new(&env));
+        let (parent, parent_delegation) = Self::find_delegable_parent(
+            &env,
+            &delegator,
+            &resource_id,
+            &actions,
+            expires_at,
+            max_depth,
+        )
+        .expect("No delegable grant covers this delegation");
 
-        authorized.push_back(grantee.clone());
-        env.storage().persistent().set(&resource_key, &authorized);
+        let delegation = Delegation {
+            id: Self::next_delegation_id(&env),
+            can_delegate: max_depth > 0,
+            max_depth,
+            origin: GrantOrigin::Delegated(GrantRef {
+                holder: delegator.clone(),
+                resource_id: parent.resource_id,
+                granted_by: parent.granted_by,
+                delegation_id: parent_delegation.id,
+            }),
+        };
+        Self::grant(
+            &env,
+            &delegator,
+            delegatee.clone(),
+            resource_id.clone(),
+            actions,
+            expires_at,
+            Some(delegation),
+        );
 
         env.events().publish(
-            (symbol_short!("grant"), grantee, resource_id),
-            symbol_short!("success"),
+            (symbol_short!("delegate"), delegatee, resource_id),
+            delegator,
         );
     }
 
-    /// Revoke access permission from an entity for a specific resource
+    /// Get the delegation chain behind a grant, starting from the grant itself
+    /// and ending at the root grant
     ///
     /// # Arguments
-    /// * `revoker` - The address revoking access (must be the original grantor or admin)
-    /// * `revokee` - The address losing access
-    /// * `resource_id` - The identifier of the resource
-    pub fn revoke_access(env: Env, revoker: Address, revokee: Address, resource_id: String) {
-        revoker.require_auth();
-
-        // Get admin for authorization check
-        let admin: Address = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Admin)
-            .expect("Contract not initialized");
-
-        // Remove from grantee's access list
-        let access_key = DataKey::AccessList(revokee.clone());
-        let access_list: Vec<AccessPermission> = env
-            .storage()
-            .persistent()
-            .get(&access_key)
-            .unwrap_or(Vec::new(&env));
-
-        let mut new_access_list: Vec<AccessPermission> = Vec::new(&env);
-        let mut found = false;
+    /// * `holder` - The entity holding the grant
+    /// * `resource_id` - The resource id the grant was made on
+    /// * `granted_by` - The grantor of the grant
+    pub fn get_delegation_chain(
+        env: Env,
+        holder: Address,
+        resource_id: String,
+        granted_by: Address,
+    ) -> Vec<DelegationLink> {
+        let permission = Self::find_grant(&env, &holder, &resource_id, &granted_by)
+            .expect("Access permission not found");
 
-        for i in 0..access_list.len() {
-            if let Some(permission) = access_list.get(i) {
-                if permission.resource_id == resource_id {
-                    // Verify revoker is either the original grantor or admin
-                    if permission.granted_by != revoker && revoker != admin {
-                        panic!("Not authorized to revoke this access");
-                    }
-                    found = true;
-                    // Skip this permission (effectively removing it)
-                } else {
-                    new_access_list.push_back(permission);
+        let mut chain = Vec::new(&env);
+        let mut link = Some((holder, permission));
+        while let Some((holder, permission)) = link {
+            let delegation = Self::delegation_of(&env, &holder, &permission);
+            // Stops early at a broken link, e.g. a revoked parent grant
+            link = match &delegation.origin {
+                GrantOrigin::Delegated(parent) => {
+                    Self::resolve_parent(&env, parent).map(|grant| (parent.holder.clone(), grant))
                 }
+                GrantOrigin::Direct => None,
+            };
+            chain.push_back(DelegationLink {
+                holder,
+                permission,
+                delegation,
+            });
+            if chain.len() > MAX_DELEGATION_DEPTH {
+                break;
             }
         }
+        chain
+    }
 
-        if !found {
-            panic!("Access permission not found");
+    /// A delegable grant held by `holder` that covers a sub-grant: same or
+    /// wider resource, every requested action, an expiry no earlier than
+    /// `expires_at` and more than `max_depth` levels of delegation left
+    fn find_delegable_parent(
+        env: &Env,
+        holder: &Address,
+        resource_id: &String,
+        actions: &Vec<Action>,
+        expires_at: u64,
+        max_depth: u32,
+    ) -> Option<(AccessPermission, Delegation)> {
+        let now = env.ledger().timestamp();
+        let mut candidates = Self::wildcard_ancestors(env, resource_id);
+        candidates.push_front(resource_id.clone());
+        for candidate in candidates.iter() {
+            for permission in Self::load_grants(env, holder, &candidate).iter() {
+                let delegation = match Self::load_delegation(env, holder, &permission) {
+                    Some(delegation) => delegation,
+                    None => continue,
+                };
+                let within_expiry = permission.expires_at == 0
+                    || (expires_at != 0 && expires_at <= permission.expires_at);
+                if delegation.can_delegate
+                    && delegation.max_depth > max_depth
+                    && within_expiry
+                    && actions
+                        .iter()
+                        .all(|action| permission.actions.contains(&action))
+                    && Self::permits(env, holder, &permission, &None, now)
+                {
+                    return Some((permission, delegation));
+                }
+            }
         }
+        None
+    }
 
-        env.storage()
-            .persistent()
-            .set(&access_key, &new_access_list);
+    fn find_grant(
+        env: &Env,
+        holder: &Address,
+        resource_id: &String,
+        granted_by: &Address,
+    ) -> Option<AccessPermission> {
+        Self::load_grants(env, holder, resource_id)
+            .iter()
+            .find(|permission| permission.granted_by == *granted_by)
+    }
 
-        // Remove from resource's authorized parties
-        let resource_key = DataKey::ResourceAccess(resource_id.clone());
-        let authorized: Vec<Address> = env
+    /// The grant a delegation points at, if it is still the same grant
+    fn resolve_parent(env: &Env, parent: &GrantRef) -> Option<AccessPermission> {
+        Self::find_grant(env, &parent.holder, &parent.resource_id, &parent.granted_by).filter(
+            |grant| Self::delegation_of(env, &parent.holder, grant).id == parent.delegation_id,
+        )
+    }
+
+    fn next_delegation_id(env: &Env) -> u64 {
+        let id: u64 = env
             .storage()
             .persistent()
-            .get(&resource_key)
-            .unwrap_or(Vec::new(&env));
-
-        let mut new_authorized: Vec<Address> = Vec::new(&env);
-        for i in 0..authorized.len() {
-            if let Some(addr) = authorized.get(i) {
-                if addr != revokee {
-                    new_authorized.push_back(addr);
-                }
-            }
-        }
+            .get(&DataKey::DelegationCounter)
+            .unwrap_or(0)
+            + 1;
         env.storage()
             .persistent()
-            .set(&resource_key, &new_authorized);
+            .set(&DataKey::DelegationCounter, &id);
+        id
+    }
 
-        env.events().publish(
-            (symbol_short!("revoke"), revokee, resource_id),
-            symbol_short!("success"),
-        );
+    fn load_delegation(
+        env: &Env,
+        holder: &Address,
+        permission: &AccessPermission,
+    ) -> Option<Delegation> {
+        env.storage().persistent().get(&DataKey::Delegation(
+            holder.clone(),
+            permission.resource_id.clone(),
+            permission.granted_by.clone(),
+        ))
     }
 
-    /// Check if an entity has access to a specific resource
+    /// Delegation terms of a grant; plain grants cannot be re-delegated
+    fn delegation_of(env: &Env, holder: &Address, permission: &AccessPermission) -> Delegation {
+        Self::load_delegation(env, holder, permission).unwrap_or(Delegation {
+            id: 0,
+            can_delegate: false,
+            max_depth: 0,
+            origin: GrantOrigin::Direct,
+        })
+    }
+
+    fn remove_delegation(env: &Env, holder: &Address, permission: &AccessPermission) {
+        env.storage().persistent().remove(&DataKey::Delegation(
+            holder.clone(),
+            permission.resource_id.clone(),
+            permission.granted_by.clone(),
+        ));
+    }
+
+    /// Register the ed25519 key a patient signs off-chain consents with
+    ///
+    /// Registering again replaces the previous key.
+    ///
+    /// # Arguments
+    /// * `patient` - A registered patient entity
+    /// * `public_key` - The ed25519 public key
+    pub fn register_consent_key(env: Env, patient: Address, public_key: BytesN<32>) {
+        patient.require_auth();
+
+        let entity: EntityData = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Entity(patient.clone()))
+            .expect("Entity not found");
+        if entity.entity_type != EntityType::Patient {
+            panic!("Only patients can register consent keys");
+        }
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::ConsentKey(patient.clone()), &public_key);
+
+        env.events()
+            .publish((symbol_short!("cons_key"), patient), public_key);
+    }
+
+    /// Get a patient's registered consent key, if any
+    pub fn get_consent_key(env: Env, patient: Address) -> Option<BytesN<32>> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::ConsentKey(patient))
+    }
+
+    /// Grant access on a patient's behalf using a consent they signed off-chain
+    ///
+    /// Lets a clinic record consent collected on paper or in a portal. Each
+    /// nonce can be used once per patient.
+    ///
+    /// # Arguments
+    /// * `submitter` - The address submitting the consent
+    /// * `consent` - The consent payload the patient signed
+    /// * `signature` - ed25519 signature over the consent message
+    pub fn grant_access_with_consent(
+        env: Env,
+        submitter: Address,
+        consent: ConsentPayload,
+        signature: BytesN<64>,
+    ) {
+        submitter.require_auth();
+
+        let public_key: BytesN<32> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::ConsentKey(consent.patient.clone()))
+            .expect("No consent key registered");
+
+        let nonce_key = DataKey::ConsentNonce(consent.patient.clone(), consent.nonce);
+        if env.storage().persistent().has(&nonce_key) {
+            panic!("Consent already used");
+        }
+        if consent.expires_at != 0 && consent.expires_at <= env.ledger().timestamp() {
+            panic!("Consent expired");
+        }
+
+        let message = (env.current_contract_address(), consent.clone()).to_xdr(&env);
+        env.crypto()
+            .ed25519_verify(&public_key, &message, &signature);
+
+        env.storage().persistent().set(&nonce_key, &true);
+
+        Self::grant(
+            &env,
+            &consent.patient,
+            consent.grantee,
+            consent.resource_id,
+            consent.actions,
+            consent.expires_at,
+            None,
+        );
+
+        env.events().publish(
+            (symbol_short!("consent"), consent.patient, submitter),
+            consent.nonce,
+        );
+    }
+
+    /// Whether a patient's consent nonce has already been used
+    pub fn is_consent_nonce_used(env: Env, patient: Address, nonce: u64) -> bool {
+        env.storage()
+            .persistent()
+            .has(&DataKey::ConsentNonce(patient, nonce))
+    }
+
+    fn grant(
+        env: &Env,
+        grantor: &Address,
+        grantee: Address,
+        resource_id: String,
+        actions: Vec<Action>,
+        expires_at: u64,
+        delegation: Option<Delegation>,
+    ) {
+        if *grantor == grantee {
+            panic!("Cannot grant access to self");
+        }
+
+        // Verify grantor is a registered entity
+        let grantor_key = DataKey::Entity(grantor.clone());
+        if !env.storage().persistent().has(&grantor_key) {
+            panic!("Grantor not registered");
+        }
+
+        // Verify grantee is a registered entity
+        let grantee_key = DataKey::Entity(grantee.clone());
+        if !env.storage().persistent().has(&grantee_key) {
+            panic!("Grantee not registered");
+        }
+
+        if !Self::is_in_good_standing(env, grantor) {
+            panic!("Grantor is not active");
+        }
+        if !Self::is_in_good_standing(env, &grantee) {
+            panic!("Grantee is not active");
+        }
+
+        if actions.is_empty() {
+            panic!("No actions specified");
+        }
+        Self::validate_resource_id(&resource_id);
+
+        // Another patient's data can only be passed on under a delegable
+        // grant from them, and the new grant joins that grant's chain so the
+        // scope, expiry and depth limits apply; delegated grants were matched
+        // to a parent already
+        let delegated = matches!(
+            &delegation,
+            Some(Delegation {
+                origin: GrantOrigin::Delegated(_),
+                ..
+            })
+        );
+        let mut delegation = delegation;
+        if !delegated && Self::is_foreign_patient_resource(env, grantor, &resource_id) {
+            let max_depth = delegation
+                .as_ref()
+                .map_or(0, |delegation| delegation.max_depth);
+            let (parent, parent_delegation) = match Self::find_delegable_parent(
+                env,
+                grantor,
+                &resource_id,
+                &actions,
+                expires_at,
+                max_depth,
+            ) {
+                Some(parent) => parent,
+                None => panic!("Not authorized to grant this access"),
+            };
+            delegation = Some(Delegation {
+                id: match delegation {
+                    Some(delegation) => delegation.id,
+                    None => Self::next_delegation_id(env),
+                },
+                can_delegate: max_depth > 0,
+                max_depth,
+                origin: GrantOrigin::Delegated(GrantRef {
+                    holder: grantor.clone(),
+                    resource_id: parent.resource_id,
+                    granted_by: parent.granted_by,
+                    delegation_id: parent_delegation.id,
+                }),
+            });
+        }
+
+        let permission = AccessPermission {
+            resource_id: resource_id.clone(),
+            actions,
+            granted_by: grantor.clone(),
+            granted_at: env.ledger().timestamp(),
+            expires_at,
+        };
+
+        Self::store_permission(env, &grantee, permission, delegation);
+
+        env.events().publish(
+            (symbol_short!("grant"), grantee, resource_id),
+            symbol_short!("success"),
+        );
+    }
+
+    /// Revoke access permission from an entity for a specific resource
+    ///
+    /// Removes every grant on the resource that the revoker is allowed to remove.
+    ///
+    /// # Arguments
+    /// * `revoker` - The address revoking access (must be the original grantor or hold
+    ///   `Capability::RevokeAnyAccess`)
+    /// * `revokee` - The address losing access
+    /// * `resource_id` - The identifier of the resource
+    pub fn revoke_access(env: Env, revoker: Address, revokee: Address, resource_id: String) {
+        revoker.require_auth();
+
+        if !env.storage().persistent().has(&DataKey::Admin) {
+            panic!("Contract not initialized");
+        }
+        let can_revoke_any =
+            Self::account_has_capability(&env, &revoker, &Capability::RevokeAnyAccess);
+
+        Self::migrate_legacy_grants(&env, &revokee);
+        let grants = Self::load_grants(&env, &revokee, &resource_id);
+        if grants.is_empty() {
+            panic!("Access permission not found");
+        }
+
+        let mut remaining: Vec<AccessPermission> = Vec::new(&env);
+        let mut removed = false;
+        for permission in grants.iter() {
+            // Only the original grantor or a RevokeAnyAccess holder may remove a grant
+            if permission.granted_by == revoker || can_revoke_any {
+                // Grants delegated from this one lapse with it
+                Self::remove_delegation(&env, &revokee, &permission);
+                removed = true;
+                continue;
+            }
+            remaining.push_back(permission);
+        }
+        if !removed {
+            panic!("Not authorized to revoke this access");
+        }
+
+        Self::save_grants(&env, &revokee, &resource_id, &remaining);
+
+        env.events().publish(
+            (symbol_short!("revoke"), revokee, resource_id),
+            symbol_short!("success"),
+        );
+    }
+
+    /// Check if an entity has access to a specific resource
     ///
     /// # Arguments
     /// * `entity` - The address to check
     /// * `resource_id` - The identifier of the resource
     ///
     /// # Returns
-    /// `true` if the entity has valid (non-expired) access, `false` otherwise
+    /// `true` if the entity has valid (non-expired) access for any action, `false` otherwise
     pub fn check_access(env: Env, entity: Address, resource_id: String) -> bool {
-        let access_key = DataKey::AccessList(entity);
-        let access_list: Vec<AccessPermission> = env
-            .storage()
-            .persistent()
-            .get(&access_key)
-            .unwrap_or(Vec::new(&env));
+        Self::has_permission(&env, entity, resource_id, None, true)
+    }
 
-        let current_time = env.ledger().timestamp();
+    /// Check if an entity may perform a specific action on a resource
+    ///
+    /// # Arguments
+    /// * `entity` - The address to check
+    /// * `resource_id` - The identifier of the resource
+    /// * `action` - The action being attempted
+    ///
+    /// # Returns
+    /// `true` if a valid (non-expired) grant covers the action, `false` otherwise
+    pub fn check_access_for(
+        env: Env,
+        entity: Address,
+        resource_id: String,
+        action: Action,
+    ) -> bool {
+        Self::has_permission(&env, entity, resource_id, Some(action), true)
+    }
 
-        for i in 0..access_list.len() {
-            if let Some(permission) = access_list.get(i) {
-                if permission.resource_id == resource_id {
-                    // Check if permission is expired
-                    if permission.expires_at == 0 || permission.expires_at > current_time {
-                        return true;
-                    }
+    /// Check if an entity holds a grant on exactly this resource
+    ///
+    /// Wildcard grants above the resource do not count, so resources too
+    /// sensitive to be swept up by a broad grant can require their own.
+    ///
+    /// # Arguments
+    /// * `entity` - The address to check
+    /// * `resource_id` - The identifier of the resource
+    /// * `action` - The action being attempted
+    ///
+    /// # Returns
+    /// `true` if a valid grant on `resource_id` itself covers the action
+    pub fn check_exact_access_for(
+        env: Env,
+        entity: Address,
+        resource_id: String,
+        action: Action,
+    ) -> bool {
+        Self::has_permission(&env, entity, resource_id, Some(action), false)
+    }
+
+    /// Check an access attempt and record it in the audit log
+    ///
+    /// Consuming contracts call this instead of `check_access_for` when the
+    /// check guards an actual disclosure, so the attempt is accountable.
+    ///
+    /// # Arguments
+    /// * `entity` - The address attempting the access
+    /// * `resource_id` - The identifier of the resource
+    /// * `action` - The action being attempted
+    /// * `purpose` - Optional purpose-of-use code
+    ///
+    /// # Returns
+    /// `true` if the access was granted, `false` otherwise
+    pub fn audit_access(
+        env: Env,
+        entity: Address,
+        resource_id: String,
+        action: Action,
+        purpose: Option<Symbol>,
+    ) -> bool {
+        Self::audit(&env, entity, resource_id, action, purpose, true)
+    }
+
+    /// Audited counterpart of `check_exact_access_for`
+    ///
+    /// # Arguments
+    /// * `entity` - The address attempting the access
+    /// * `resource_id` - The identifier of the resource
+    /// * `action` - The action being attempted
+    /// * `purpose` - Optional purpose-of-use code
+    ///
+    /// # Returns
+    /// `true` if a grant on `resource_id` itself allowed the access
+    pub fn audit_exact_access(
+        env: Env,
+        entity: Address,
+        resource_id: String,
+        action: Action,
+        purpose: Option<Symbol>,
+    ) -> bool {
+        Self::audit(&env, entity, resource_id, action, purpose, false)
+    }
+
+    fn audit(
+        env: &Env,
+        entity: Address,
+        resource_id: String,
+        action: Action,
+        purpose: Option<Symbol>,
+        wildcards: bool,
+    ) -> bool {
+        entity.require_auth();
+
+        let granted = Self::has_permission(
+            env,
+            entity.clone(),
+            resource_id.clone(),
+            Some(action.clone()),
+            wildcards,
+        );
+        let outcome = if granted {
+            AccessOutcome::Granted
+        } else {
+            AccessOutcome::Denied
+        };
+        Self::append_audit(env, &entity, &resource_id, action, outcome, purpose);
+
+        granted
+    }
+
+    /// Look up the exact resource and, with `wildcards`, each ancestor
+    /// wildcard directly, so the cost depends on the depth of the resource
+    /// path, not on how many grants the entity holds.
+    fn has_permission(
+        env: &Env,
+        entity: Address,
+        resource_id: String,
+        action: Option<Action>,
+        wildcards: bool,
+    ) -> bool {
+        if !Self::is_in_good_standing(env, &entity) {
+            return false;
+        }
+        let now = env.ledger().timestamp();
+
+        // Entities that have not been migrated yet keep their legacy list
+        if let Some(legacy) = Self::legacy_grants(env, &entity) {
+            for permission in legacy.iter() {
+                let matches = if wildcards {
+                    Self::resource_matches(&permission.resource_id, &resource_id)
+                } else {
+                    permission.resource_id == resource_id
+                };
+                if matches && Self::permits(env, &entity, &permission, &action, now) {
+                    return true;
                 }
             }
         }
 
+        if Self::grants_permit(env, &entity, &resource_id, &action, now) {
+            return true;
+        }
+        if !wildcards {
+            return false;
+        }
+        for wildcard in Self::wildcard_ancestors(env, &resource_id).iter() {
+            if Self::grants_permit(env, &entity, &wildcard, &action, now) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn grants_permit(
+        env: &Env,
+        entity: &Address,
+        resource_id: &String,
+        action: &Option<Action>,
+        now: u64,
+    ) -> bool {
+        for permission in Self::load_grants(env, entity, resource_id).iter() {
+            if Self::permits(env, entity, &permission, action, now) {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn permits(
+        env: &Env,
+        holder: &Address,
+        permission: &AccessPermission,
+        action: &Option<Action>,
+        now: u64,
+    ) -> bool {
+        Self::grant_in_effect(env, permission, action, now)
+            && Self::delegation_chain_valid(env, holder, permission, action, now)
+    }
+
+    fn grant_in_effect(
+        env: &Env,
+        permission: &AccessPermission,
+        action: &Option<Action>,
+        now: u64,
+    ) -> bool {
+        // Check if permission is expired
+        let active = permission.expires_at == 0 || permission.expires_at > now;
+        let allowed = match action {
+            Some(action) => permission.actions.contains(action),
+            None => true,
+        };
+        // Grants lapse while their grantor is deactivated or suspended
+        active && allowed && Self::is_in_good_standing(env, &permission.granted_by)
+    }
+
+    /// Every grant above a delegated grant must still exist, allow delegation
+    /// and cover the action; revoking any of them cuts off the descendants
+    fn delegation_chain_valid(
+        env: &Env,
+        holder: &Address,
+        permission: &AccessPermission,
+        action: &Option<Action>,
+        now: u64,
+    ) -> bool {
+        let mut parent = match Self::delegation_of(env, holder, permission).origin {
+            GrantOrigin::Delegated(parent) => parent,
+            GrantOrigin::Direct => return true,
+        };
+
+        for _ in 0..MAX_DELEGATION_DEPTH {
+            let grant = match Self::resolve_parent(env, &parent) {
+                Some(grant) => grant,
+                None => return false,
+            };
+            let delegation = match Self::load_delegation(env, &parent.holder, &grant) {
+                Some(delegation) => delegation,
+                None => return false,
+            };
+            if !delegation.can_delegate
+                || !Self::is_in_good_standing(env, &parent.holder)
+                || !Self::grant_in_effect(env, &grant, action, now)
+            {
+                return false;
+            }
+            match delegation.origin {
+                GrantOrigin::Delegated(next) => parent = next,
+                GrantOrigin::Direct => return true,
+            }
+        }
         false
     }
 
+    /// Registered, active and not currently suspended
+    fn is_in_good_standing(env: &Env, wallet: &Address) -> bool {
+        let entity: Option<EntityData> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Entity(wallet.clone()));
+        match entity {
+            Some(entity) => {
+                let suspended_until = Self::get_suspended_until(env.clone(), wallet.clone());
+                entity.active && suspended_until <= env.ledger().timestamp()
+            }
+            None => false,
+        }
+    }
+
+    /// Wildcard ids that cover a resource, e.g. `a/b/c` -> `a/*`, `a/b/*`
+    fn wildcard_ancestors(env: &Env, resource_id: &String) -> Vec<String> {
+        let mut ancestors = Vec::new(env);
+        let bytes = resource_id.to_bytes();
+        for i in 0..bytes.len() {
+            if bytes.get(i) != Some(b'/') || i + 1 == bytes.len() {
+                continue;
+            }
+            let mut wildcard = bytes.slice(0..i + 1);
+            wildcard.push_back(b'*');
+            if wildcard != bytes {
+                ancestors.push_back(String::from(wildcard));
+            }
+        }
+        ancestors
+    }
+
+    /// Whether a granted resource id covers the requested one.
+    ///
+    /// A grant ending in `/*` covers every descendant of its prefix, e.g.
+    /// `patient:G.../labs/*` covers `patient:G.../labs/2024/cbc`.
+    fn resource_matches(granted: &String, requested: &String) -> bool {
+        if granted == requested {
+            return true;
+        }
+
+        let granted = granted.to_bytes();
+        if !Self::is_wildcard(&granted) {
+            return false;
+        }
+
+        // Keep the trailing '/' so `labs/*` does not match `labs-archive/...`
+        let prefix = granted.slice(0..granted.len() - 1);
+        let requested = requested.to_bytes();
+        requested.len() > prefix.len() && requested.slice(0..prefix.len()) == prefix
+    }
+
+    fn is_wildcard(resource: &Bytes) -> bool {
+        let len = resource.len();
+        len >= 2 && resource.get(len - 1) == Some(b'*') && resource.get(len - 2) == Some(b'/')
+    }
+
+    fn validate_resource_id(resource_id: &String) {
+        let bytes = resource_id.to_bytes();
+        if bytes.is_empty() {
+            panic!("Invalid resource id");
+        }
+        // '*' is only allowed as a trailing "/*" segment
+        let wildcard = Self::is_wildcard(&bytes);
+        for i in 0..bytes.len() {
+            if bytes.get(i) == Some(b'*') && !(wildcard && i == bytes.len() - 1) {
+                panic!("Invalid resource id");
+            }
+        }
+    }
+
     /// Get all entities with access to a specific resource
     ///
+    /// Includes parties holding a wildcard grant on any ancestor of the resource.
+    ///
     /// # Arguments
     /// * `resource_id` - The identifier of the resource
     ///
     /// # Returns
     /// A vector of addresses that have access to the resource
     pub fn get_authorized_parties(env: Env, resource_id: String) -> Vec<Address> {
-        let resource_key = DataKey::ResourceAccess(resource_id);
-        env.storage()
-            .persistent()
-            .get(&resource_key)
-            .unwrap_or(Vec::new(&env))
+        let mut resources = Self::wildcard_ancestors(&env, &resource_id);
+        resources.push_front(resource_id);
+
+        let mut parties: Vec<Address> = Vec::new(&env);
+        for resource in resources.iter() {
+            let legacy: Vec<Address> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::ResourceAccess(resource.clone()))
+                .unwrap_or(Vec::new(&env));
+            for party in legacy.iter() {
+                if !parties.contains(&party) {
+                    parties.push_back(party);
+                }
+            }
+
+            let index = index::resource_parties(&resource);
+            for page in 0..index.len(&env).div_ceil(index::PAGE_SIZE) {
+                let page: Vec<Address> = index.page(&env, page);
+                for party in page.iter() {
+                    if !parties.contains(&party) {
+                        parties.push_back(party);
+                    }
+                }
+            }
+        }
+
+        parties
+    }
+
+    /// Get one page of the entities granted directly on a resource
+    ///
+    /// Unlike `get_authorized_parties`, wildcard grants on ancestors are not
+    /// included. Pages hold up to 50 entries; ordering is not stable across
+    /// revocations.
+    ///
+    /// # Arguments
+    /// * `resource_id` - The identifier of the resource
+    /// * `page` - Zero-based page number
+    pub fn get_authorized_parties_page(env: Env, resource_id: String, page: u32) -> Vec<Address> {
+        index::resource_parties(&resource_id).page(&env, page)
+    }
+
+    /// Number of entities granted directly on a resource
+    pub fn get_authorized_party_count(env: Env, resource_id: String) -> u32 {
+        index::resource_parties(&resource_id).len(&env)
     }
 
     /// Get entity details by wallet address
@@ -335,11 +1422,60 @@ impl AccessControl {
     /// # Returns
     /// A vector of all access permissions granted to the entity
     pub fn get_entity_permissions(env: Env, wallet: Address) -> Vec<AccessPermission> {
-        let access_key = DataKey::AccessList(wallet);
-        env.storage()
-            .persistent()
-            .get(&access_key)
-            .unwrap_or(Vec::new(&env))
+        let mut permissions = Self::legacy_grants(&env, &wallet).unwrap_or(Vec::new(&env));
+
+        let index = index::entity_resources(&wallet);
+        for page in 0..index.len(&env).div_ceil(index::PAGE_SIZE) {
+            permissions.append(&Self::get_entity_permissions_page(
+                env.clone(),
+                wallet.clone(),
+                page,
+            ));
+        }
+        permissions
+    }
+
+    /// Get the permissions for one page of an entity's granted resources
+    ///
+    /// Pages cover up to 50 resources; ordering is not stable across
+    /// revocations.
+    ///
+    /// # Arguments
+    /// * `wallet` - The wallet address of the entity
+    /// * `page` - Zero-based page number
+    pub fn get_entity_permissions_page(
+        env: Env,
+        wallet: Address,
+        page: u32,
+    ) -> Vec<AccessPermission> {
+        let resources: Vec<String> = index::entity_resources(&wallet).page(&env, page);
+        let mut permissions = Vec::new(&env);
+        for resource_id in resources.iter() {
+            permissions.append(&Self::load_grants(&env, &wallet, &resource_id));
+        }
+        permissions
+    }
+
+    /// Number of distinct resources an entity holds grants on
+    pub fn get_entity_resource_count(env: Env, wallet: Address) -> u32 {
+        index::entity_resources(&wallet).len(&env)
+    }
+
+    /// Move an entity's grants from the legacy single-vector layout into
+    /// per-resource entries
+    ///
+    /// Migration keeps every grant unchanged, so anyone may trigger it. An
+    /// entity is also migrated automatically the next time its grants change.
+    ///
+    /// # Returns
+    /// The number of grants migrated
+    pub fn migrate_access_list(env: Env, entity: Address) -> u32 {
+        let migrated = Self::migrate_legacy_grants(&env, &entity);
+        if migrated > 0 {
+            env.events()
+                .publish((symbol_short!("migrate"), entity), migrated);
+        }
+        migrated
     }
 
     /// Update entity metadata
@@ -364,21 +1500,19 @@ impl AccessControl {
             .publish((symbol_short!("upd_ent"), wallet), symbol_short!("success"));
     }
 
-    /// Deactivate an entity (admin only)
+    /// Deactivate an entity (requires `Capability::ManageEntities`)
     ///
     /// # Arguments
-    /// * `admin` - The admin address
+    /// * `admin` - The address performing the deactivation
     /// * `wallet` - The wallet address of the entity to deactivate
     pub fn deactivate_entity(env: Env, admin: Address, wallet: Address) {
         admin.require_auth();
 
-        let stored_admin: Address = env
-            .storage()
-            .persistent()
-            .get(&DataKey::Admin)
-            .expect("Contract not initialized");
+        if !env.storage().persistent().has(&DataKey::Admin) {
+            panic!("Contract not initialized");
+        }
 
-        if admin != stored_admin {
+        if !Self::account_has_capability(&env, &admin, &Capability::ManageEntities) {
             panic!("Only admin can deactivate entities");
         }
 
@@ -391,8 +1525,797 @@ impl AccessControl {
 
         entity.active = false;
         env.storage().persistent().set(&key, &entity);
+        Self::record_status(&env, &wallet, EntityStatus::Deactivated, &admin, 0, None);
 
         env.events()
             .publish((symbol_short!("deact"), wallet), symbol_short!("success"));
     }
+
+    /// Reactivate a deactivated or suspended entity (requires `Capability::ManageEntities`)
+    ///
+    /// Grants given and received before deactivation take effect again.
+    ///
+    /// # Arguments
+    /// * `admin` - The address performing the reactivation
+    /// * `wallet` - The wallet address of the entity to reactivate
+    pub fn reactivate_entity(env: Env, admin: Address, wallet: Address) {
+        admin.require_auth();
+
+        if !Self::account_has_capability(&env, &admin, &Capability::ManageEntities) {
+            panic!("Only admin can reactivate entities");
+        }
+
+        let key = DataKey::Entity(wallet.clone());
+        let mut entity: EntityData = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .expect("Entity not found");
+        if Self::is_in_good_standing(&env, &wallet) {
+            panic!("Entity already active");
+        }
+
+        entity.active = true;
+        env.storage().persistent().set(&key, &entity);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Suspension(wallet.clone()));
+        Self::record_status(&env, &wallet, EntityStatus::Active, &admin, 0, None);
+
+        env.events()
+            .publish((symbol_short!("react"), wallet), symbol_short!("success"));
+    }
+
+    /// Suspend an entity until a given time (requires `Capability::ManageEntities`)
+    ///
+    /// The entity's grants, given and received, are ignored until the
+    /// suspension ends or the entity is reactivated.
+    ///
+    /// # Arguments
+    /// * `admin` - The address performing the suspension
+    /// * `wallet` - The wallet address of the entity to suspend
+    /// * `until` - Timestamp at which the suspension ends
+    /// * `reason` - Why the entity is suspended
+    pub fn suspend_entity(env: Env, admin: Address, wallet: Address, until: u64, reason: String) {
+        admin.require_auth();
+
+        if !Self::account_has_capability(&env, &admin, &Capability::ManageEntities) {
+            panic!("Only admin can suspend entities");
+        }
+        if until <= env.ledger().timestamp() {
+            panic!("Suspension must end in the future");
+        }
+
+        let entity: EntityData = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Entity(wallet.clone()))
+            .expect("Entity not found");
+        if !entity.active {
+            panic!("Entity is deactivated");
+        }
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Suspension(wallet.clone()), &until);
+        Self::record_status(
+            &env,
+            &wallet,
+            EntityStatus::Suspended,
+            &admin,
+            until,
+            Some(reason),
+        );
+
+        env.events()
+            .publish((symbol_short!("suspend"), wallet), until);
+    }
+
+    /// Get the current lifecycle status of an entity
+    ///
+    /// A suspension whose end date has passed reports `Active`.
+    pub fn get_entity_status(env: Env, wallet: Address) -> EntityStatus {
+        let entity: EntityData = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Entity(wallet.clone()))
+            .expect("Entity not found");
+        if !entity.active {
+            EntityStatus::Deactivated
+        } else if Self::is_in_good_standing(&env, &wallet) {
+            EntityStatus::Active
+        } else {
+            EntityStatus::Suspended
+        }
+    }
+
+    /// Get the time an entity's suspension ends, or 0 if it was never suspended
+    ///
+    /// Kept apart from `EntityData` so entities stored before suspensions
+    /// existed still decode.
+    pub fn get_suspended_until(env: Env, wallet: Address) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Suspension(wallet))
+            .unwrap_or(0)
+    }
+
+    /// Get every status change recorded for an entity, oldest first
+    pub fn get_entity_status_history(env: Env, wallet: Address) -> Vec<StatusChange> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::StatusHistory(wallet))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    fn record_status(
+        env: &Env,
+        wallet: &Address,
+        status: EntityStatus,
+        changed_by: &Address,
+        until: u64,
+        reason: Option<String>,
+    ) {
+        let key = DataKey::StatusHistory(wallet.clone());
+        let mut history: Vec<StatusChange> = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(env));
+        history.push_back(StatusChange {
+            status,
+            changed_by: changed_by.clone(),
+            changed_at: env.ledger().timestamp(),
+            until,
+            reason,
+        });
+        env.storage().persistent().set(&key, &history);
+    }
+
+    // =====================================================
+    //                  ROLE MANAGEMENT
+    // =====================================================
+
+    /// Grant a role to an account (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - An address holding `Role::Admin`
+    /// * `account` - The address receiving the role
+    /// * `role` - The role to grant
+    pub fn grant_role(env: Env, admin: Address, account: Address, role: Role) {
+        admin.require_auth();
+        Self::require_admin(&env, &admin);
+
+        Self::add_role(&env, &account, &role);
+
+        env.events()
+            .publish((symbol_short!("role_grt"), account), role);
+    }
+
+    /// Revoke a role from an account (admin only)
+    ///
+    /// # Arguments
+    /// * `admin` - An address holding `Role::Admin`
+    /// * `account` - The address losing the role
+    /// * `role` - The role to revoke
+    pub fn revoke_role(env: Env, admin: Address, account: Address, role: Role) {
+        admin.require_auth();
+        Self::require_admin(&env, &admin);
+
+        let mut roles = Self::roles_of(&env, &account);
+        let index = roles.first_index_of(&role).expect("Role not granted");
+
+        let members_key = DataKey::RoleMembers(role.clone());
+        let mut members: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&members_key)
+            .unwrap_or(Vec::new(&env));
+
+        if role == Role::Admin && members.len() == 1 {
+            panic!("Cannot revoke the last admin");
+        }
+
+        roles.remove(index);
+        env.storage()
+            .persistent()
+            .set(&DataKey::AccountRoles(account.clone()), &roles);
+
+        if let Some(member_index) = members.first_index_of(&account) {
+            members.remove(member_index);
+        }
+        env.storage().persistent().set(&members_key, &members);
+
+        env.events()
+            .publish((symbol_short!("role_rvk"), account), role);
+    }
+
+    /// Check whether an account holds a role
+    ///
+    /// # Returns
+    /// `true` if the account currently holds the role
+    pub fn has_role(env: Env, account: Address, role: Role) -> bool {
+        Self::roles_of(&env, &account).contains(role)
+    }
+
+    /// Get all roles held by an account
+    pub fn get_roles(env: Env, account: Address) -> Vec<Role> {
+        Self::roles_of(&env, &account)
+    }
+
+    /// Get all accounts holding a role
+    pub fn get_role_members(env: Env, role: Role) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RoleMembers(role))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Set the capabilities granted by a role (admin only)
+    ///
+    /// `Role::Admin` always holds every capability and cannot be reconfigured.
+    ///
+    /// # Arguments
+    /// * `admin` - An address holding `Role::Admin`
+    /// * `role` - The role to configure
+    /// * `capabilities` - The full set of capabilities for the role
+    pub fn set_role_capabilities(
+        env: Env,
+        admin: Address,
+        role: Role,
+        capabilities: Vec<Capability>,
+    ) {
+        admin.require_auth();
+        Self::require_admin(&env, &admin);
+
+        if role == Role::Admin {
+            panic!("Admin capabilities cannot be changed");
+        }
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::RoleCapabilities(role.clone()), &capabilities);
+
+        env.events()
+            .publish((symbol_short!("role_cap"), role), capabilities);
+    }
+
+    /// Get the capabilities configured for a role
+    pub fn get_role_capabilities(env: Env, role: Role) -> Vec<Capability> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RoleCapabilities(role))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Check whether an account holds a capability through any of its roles
+    ///
+    /// # Returns
+    /// `true` if the account is an admin or one of its roles maps to the capability
+    pub fn has_capability(env: Env, account: Address, capability: Capability) -> bool {
+        Self::account_has_capability(&env, &account, &capability)
+    }
+
+    // =====================================================
+    //                BREAK-GLASS ACCESS
+    // =====================================================
+
+    /// Vouch for a registered doctor so they may break glass
+    ///
+    /// # Arguments
+    /// * `verifier` - An address holding `Role::Admin` or `Role::Verifier`
+    /// * `clinician` - A registered doctor entity
+    pub fn verify_clinician(env: Env, verifier: Address, clinician: Address) {
+        verifier.require_auth();
+        if !Self::can_verify_clinicians(&env, &verifier) {
+            panic!("Only admins and verifiers can verify clinicians");
+        }
+
+        let entity: EntityData = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Entity(clinician.clone()))
+            .expect("Clinician not registered");
+        if entity.entity_type != EntityType::Doctor {
+            panic!("Only doctors can be verified as clinicians");
+        }
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::ClinicianVerifier(clinician.clone()), &verifier);
+
+        env.events()
+            .publish((symbol_short!("clin_ver"), clinician), verifier);
+    }
+
+    /// Withdraw a clinician's verification
+    ///
+    /// # Arguments
+    /// * `verifier` - An address holding `Role::Admin` or `Role::Verifier`
+    /// * `clinician` - A verified clinician
+    pub fn revoke_clinician_verification(env: Env, verifier: Address, clinician: Address) {
+        verifier.require_auth();
+        if !Self::can_verify_clinicians(&env, &verifier) {
+            panic!("Only admins and verifiers can verify clinicians");
+        }
+
+        let key = DataKey::ClinicianVerifier(clinician.clone());
+        if !env.storage().persistent().has(&key) {
+            panic!("Clinician not verified");
+        }
+        env.storage().persistent().remove(&key);
+
+        env.events()
+            .publish((symbol_short!("clin_rvk"), clinician), verifier);
+    }
+
+    /// Whether a clinician is vouched for by someone who still holds
+    /// `Role::Admin` or `Role::Verifier`
+    pub fn is_verified_clinician(env: Env, clinician: Address) -> bool {
+        let verifier: Option<Address> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::ClinicianVerifier(clinician));
+        match verifier {
+            Some(verifier) => Self::can_verify_clinicians(&env, &verifier),
+            None => false,
+        }
+    }
+
+    fn can_verify_clinicians(env: &Env, account: &Address) -> bool {
+        let roles = Self::roles_of(env, account);
+        roles.contains(Role::Admin) || roles.contains(Role::Verifier)
+    }
+
+    /// Open a short time-boxed emergency grant without patient authorization
+    ///
+    /// The grant is read-only, expires after `BREAK_GLASS_DURATION` and is queued
+    /// for review by the patient or a `Capability::ReviewBreakGlass` holder.
+    /// Doctor registration is self-service, so only clinicians verified by an
+    /// admin or verifier may use it.
+    ///
+    /// # Arguments
+    /// * `clinician` - An active doctor verified with `verify_clinician`
+    /// * `patient` - The patient whose data is being accessed
+    /// * `resource_id` - The identifier of the resource
+    /// * `justification` - Why emergency access is needed (required)
+    ///
+    /// # Returns
+    /// The id of the break-glass record
+    pub fn break_glass_access(
+        env: Env,
+        clinician: Address,
+        patient: Address,
+        resource_id: String,
+        justification: String,
+    ) -> u64 {
+        clinician.require_auth();
+
+        let entity: EntityData = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Entity(clinician.clone()))
+            .expect("Clinician not registered");
+        if entity.entity_type != EntityType::Doctor || !Self::is_in_good_standing(&env, &clinician)
+        {
+            panic!("Only active clinicians can break glass");
+        }
+        if !Self::is_verified_clinician(env.clone(), clinician.clone()) {
+            panic!("Clinician not verified");
+        }
+        if justification.is_empty() {
+            panic!("Justification required");
+        }
+
+        // The patient must be a real, active patient entity so the mandatory
+        // review lands with them, and the grant must stay inside their records
+        let patient_entity: Option<EntityData> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Entity(patient.clone()));
+        match patient_entity {
+            Some(entity)
+                if entity.entity_type == EntityType::Patient
+                    && Self::is_in_good_standing(&env, &patient) => {}
+            _ => panic!("Patient not registered"),
+        }
+        Self::validate_resource_id(&resource_id);
+        if Self::is_wildcard(&resource_id.to_bytes()) {
+            panic!("Break-glass access cannot use wildcards");
+        }
+        if !Self::is_patient_resource(&env, &patient, &resource_id) {
+            panic!("Resource does not belong to patient");
+        }
+
+        let now = env.ledger().timestamp();
+        let expires_at = now + BREAK_GLASS_DURATION;
+
+        let mut actions = Vec::new(&env);
+        actions.push_back(Action::Read);
+        Self::store_permission(
+            &env,
+            &clinician,
+            AccessPermission {
+                resource_id: resource_id.clone(),
+                actions,
+                granted_by: clinician.clone(),
+                granted_at: now,
+                expires_at,
+            },
+            None,
+        );
+
+        let id: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::BreakGlassCounter)
+            .unwrap_or(0)
+            + 1;
+        env.storage()
+            .persistent()
+            .set(&DataKey::BreakGlassCounter, &id);
+
+        let record = BreakGlassRecord {
+            id,
+            clinician: clinician.clone(),
+            patient: patient.clone(),
+            resource_id,
+            justification,
+            granted_at: now,
+            expires_at,
+            status: BreakGlassStatus::PendingReview,
+            reviewed_by: None,
+            review_note: None,
+            reviewed_at: 0,
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::BreakGlass(id), &record);
+
+        let mut pending: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PendingBreakGlass)
+            .unwrap_or(Vec::new(&env));
+        pending.push_back(id);
+        env.storage()
+            .persistent()
+            .set(&DataKey::PendingBreakGlass, &pending);
+
+        let patient_key = DataKey::PatientBreakGlass(patient.clone());
+        let mut history: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&patient_key)
+            .unwrap_or(Vec::new(&env));
+        history.push_back(id);
+        env.storage().persistent().set(&patient_key, &history);
+
+        Self::append_audit(
+            &env,
+            &clinician,
+            &record.resource_id,
+            Action::Read,
+            AccessOutcome::Granted,
+            Some(symbol_short!("ETREAT")),
+        );
+
+        env.events()
+            .publish((symbol_short!("bg_open"), patient, clinician), id);
+
+        id
+    }
+
+    /// Approve a break-glass access after review
+    ///
+    /// # Arguments
+    /// * `reviewer` - The patient or a `Capability::ReviewBreakGlass` holder
+    /// * `id` - The break-glass record id
+    /// * `note` - Optional review note
+    pub fn approve_break_glass(env: Env, reviewer: Address, id: u64, note: Option<String>) {
+        let record =
+            Self::review_break_glass(&env, &reviewer, id, BreakGlassStatus::Approved, note);
+
+        env.events()
+            .publish((symbol_short!("bg_appr"), record.patient, reviewer), id);
+    }
+
+    /// Flag a break-glass access as inappropriate and end the grant immediately
+    ///
+    /// # Arguments
+    /// * `reviewer` - The patient or a `Capability::ReviewBreakGlass` holder
+    /// * `id` - The break-glass record id
+    /// * `reason` - Why the access was flagged
+    pub fn flag_break_glass(env: Env, reviewer: Address, id: u64, reason: String) {
+        let record =
+            Self::review_break_glass(&env, &reviewer, id, BreakGlassStatus::Flagged, Some(reason));
+
+        // Remove the emergency grant if it is still in place
+        Self::migrate_legacy_grants(&env, &record.clinician);
+        let grants = Self::load_grants(&env, &record.clinician, &record.resource_id);
+        let mut remaining: Vec<AccessPermission> = Vec::new(&env);
+        for permission in grants.iter() {
+            if permission.granted_by != record.clinician
+                || permission.granted_at != record.granted_at
+            {
+                remaining.push_back(permission);
+            } else {
+                Self::remove_delegation(&env, &record.clinician, &permission);
+            }
+        }
+        if remaining.len() != grants.len() {
+            Self::save_grants(&env, &record.clinician, &record.resource_id, &remaining);
+        }
+
+        env.events()
+            .publish((symbol_short!("bg_flag"), record.patient, reviewer), id);
+    }
+
+    fn review_break_glass(
+        env: &Env,
+        reviewer: &Address,
+        id: u64,
+        status: BreakGlassStatus,
+        note: Option<String>,
+    ) -> BreakGlassRecord {
+        reviewer.require_auth();
+
+        let key = DataKey::BreakGlass(id);
+        let mut record: BreakGlassRecord = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .expect("Break-glass record not found");
+
+        if record.patient != *reviewer
+            && !Self::account_has_capability(env, reviewer, &Capability::ReviewBreakGlass)
+        {
+            panic!("Not authorized to review break-glass access");
+        }
+        if record.status != BreakGlassStatus::PendingReview {
+            panic!("Break-glass access already reviewed");
+        }
+
+        record.status = status;
+        record.reviewed_by = Some(reviewer.clone());
+        record.review_note = note;
+        record.reviewed_at = env.ledger().timestamp();
+        env.storage().persistent().set(&key, &record);
+
+        let pending: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PendingBreakGlass)
+            .unwrap_or(Vec::new(env));
+        let mut new_pending: Vec<u64> = Vec::new(env);
+        for pending_id in pending.iter() {
+            if pending_id != id {
+                new_pending.push_back(pending_id);
+            }
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::PendingBreakGlass, &new_pending);
+
+        record
+    }
+
+    /// Get a break-glass record by id
+    pub fn get_break_glass(env: Env, id: u64) -> BreakGlassRecord {
+        env.storage()
+            .persistent()
+            .get(&DataKey::BreakGlass(id))
+            .expect("Break-glass record not found")
+    }
+
+    /// Get every break-glass access awaiting review (compliance queue)
+    pub fn get_pending_break_glass(env: Env) -> Vec<BreakGlassRecord> {
+        let pending: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PendingBreakGlass)
+            .unwrap_or(Vec::new(&env));
+        Self::load_break_glass(&env, pending)
+    }
+
+    /// Get every break-glass access against a patient (patient review queue)
+    pub fn get_patient_break_glass(env: Env, patient: Address) -> Vec<BreakGlassRecord> {
+        let ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::PatientBreakGlass(patient))
+            .unwrap_or(Vec::new(&env));
+        Self::load_break_glass(&env, ids)
+    }
+
+    fn load_break_glass(env: &Env, ids: Vec<u64>) -> Vec<BreakGlassRecord> {
+        let mut records = Vec::new(env);
+        for id in ids.iter() {
+            if let Some(record) = env.storage().persistent().get(&DataKey::BreakGlass(id)) {
+                records.push_back(record);
+            }
+        }
+        records
+    }
+
+    fn append_audit(
+        env: &Env,
+        accessor: &Address,
+        resource_id: &String,
+        action: Action,
+        outcome: AccessOutcome,
+        purpose: Option<Symbol>,
+    ) {
+        let id: u64 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::AuditCounter)
+            .unwrap_or(0)
+            + 1;
+        env.storage().persistent().set(&DataKey::AuditCounter, &id);
+
+        let entry = AuditEntry {
+            id,
+            accessor: accessor.clone(),
+            resource_id: resource_id.clone(),
+            action,
+            outcome: outcome.clone(),
+            timestamp: env.ledger().timestamp(),
+            purpose,
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::AuditEntry(id), &entry);
+
+        let count_key = DataKey::EntityAuditCount(accessor.clone());
+        let count: u32 = env.storage().persistent().get(&count_key).unwrap_or(0);
+        env.storage()
+            .persistent()
+            .set(&DataKey::EntityAudit(accessor.clone(), count), &id);
+        env.storage().persistent().set(&count_key, &(count + 1));
+
+        let count_key = DataKey::ResourceAuditCount(resource_id.clone());
+        let count: u32 = env.storage().persistent().get(&count_key).unwrap_or(0);
+        env.storage()
+            .persistent()
+            .set(&DataKey::ResourceAudit(resource_id.clone(), count), &id);
+        env.storage().persistent().set(&count_key, &(count + 1));
+
+        env.events().publish(
+            (
+                symbol_short!("audit"),
+                accessor.clone(),
+                resource_id.clone(),
+            ),
+            (id, outcome),
+        );
+    }
+
+    /// Get the accesses attempted by an entity, oldest first
+    ///
+    /// # Arguments
+    /// * `caller` - The entity itself or a `Capability::ViewAuditLog` holder
+    /// * `entity` - The accessor whose trail is requested
+    /// * `start` - Index of the first entry to return
+    /// * `limit` - Maximum number of entries (capped at 50)
+    pub fn get_entity_audit_log(
+        env: Env,
+        caller: Address,
+        entity: Address,
+        start: u32,
+        limit: u32,
+    ) -> Vec<AuditEntry> {
+        caller.require_auth();
+        if caller != entity
+            && !Self::account_has_capability(&env, &caller, &Capability::ViewAuditLog)
+        {
+            panic!("Not authorized to view audit log");
+        }
+
+        let count = Self::get_entity_audit_count(env.clone(), entity.clone());
+        let mut entries = Vec::new(&env);
+        for index in start..Self::page_end(start, limit, count) {
+            if let Some(id) = env
+                .storage()
+                .persistent()
+                .get::<_, u64>(&DataKey::EntityAudit(entity.clone(), index))
+            {
+                entries.push_back(Self::load_audit_entry(&env, id));
+            }
+        }
+        entries
+    }
+
+    /// Get the accesses attempted against a resource, oldest first
+    ///
+    /// # Arguments
+    /// * `caller` - The patient owning the resource or a `Capability::ViewAuditLog` holder
+    /// * `resource_id` - The resource whose trail is requested
+    /// * `start` - Index of the first entry to return
+    /// * `limit` - Maximum number of entries (capped at 50)
+    pub fn get_resource_audit_log(
+        env: Env,
+        caller: Address,
+        resource_id: String,
+        start: u32,
+        limit: u32,
+    ) -> Vec<AuditEntry> {
+        caller.require_auth();
+        if !Self::is_patient_resource(&env, &caller, &resource_id)
+            && !Self::account_has_capability(&env, &caller, &Capability::ViewAuditLog)
+        {
+            panic!("Not authorized to view audit log");
+        }
+
+        let count = Self::get_resource_audit_count(env.clone(), resource_id.clone());
+        let mut entries = Vec::new(&env);
+        for index in start..Self::page_end(start, limit, count) {
+            if let Some(id) = env
+                .storage()
+                .persistent()
+                .get::<_, u64>(&DataKey::ResourceAudit(resource_id.clone(), index))
+            {
+                entries.push_back(Self::load_audit_entry(&env, id));
+            }
+        }
+        entries
+    }
+
+    /// Number of audit entries recorded for an accessor
+    pub fn get_entity_audit_count(env: Env, entity: Address) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::EntityAuditCount(entity))
+            .unwrap_or(0)
+    }
+
+    /// Number of audit entries recorded for a resource
+    pub fn get_resource_audit_count(env: Env, resource_id: String) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::ResourceAuditCount(resource_id))
+            .unwrap_or(0)
+    }
+
+    fn page_end(start: u32, limit: u32, count: u32) -> u32 {
+        let limit = if limit > MAX_AUDIT_PAGE {
+            MAX_AUDIT_PAGE
+        } else {
+            limit
+        };
+        let end = start.saturating_add(limit);
+        if end > count {
+            count
+        } else {
+            end
+        }
+    }
+
+    fn load_audit_entry(env: &Env, id: u64) -> AuditEntry {
+        env.storage()
+            .persistent()
+            .get(&DataKey::AuditEntry(id))
+            .expect("Audit entry not found")
+    }
+
+    /// Whether a resource lives under the `patient:<address>` namespace of `patient`
+    fn is_patient_resource(env: &Env, patient: &Address, resource_id: &String) -> bool {
+        let mut prefix = Bytes::from_slice(env, b"patient:");
+        prefix.append(&patient.to_string().to_bytes());
+
+        let resource = resource_id.to_bytes();
+        if resource.len() < prefix.len() || resource.slice(0..prefix.len()) != prefix {
+            return false;
+        }
+        resource.len() == prefix.len() || resource.get(prefix.len()) == Some(b'/')
+    }
+
+    /// Whether a resource lives under a `patient:` namespace other than the grantor's
+    fn is_foreign_patient_resource(env: &Env, grantor: &Address, resource_id: &String) -> bool {
+        let namespace = Bytes::from_slice(env, b"patient:");
+        let resource = resource_id.to_bytes();
+        resource.len() >= namespace.len()
+            && resource.slice(0..namespace.len()) == namespace
+            && !Self::is_patient_resource(env, grantor, resource_id)
+    }
 }
@@ -1,7 +1,9 @@
 #![cfg(test)]
+extern crate std;
 
 use super::*;
-use soroban_sdk::{testutils::Address as _, Address, Env, String};
+use ed25519_dalek::{Signer, SigningKey};
+use soroban_sdk::{testutils::Address as _, vec, Address, Env, String, Vec};
 
 #[test]
 fn test_initialize() {
@@ -103,7 +105,13 @@ fn test_grant_access() {
 
     // Hospital grants access to doctor for patient records
     let resource_id = String::from_str(&env, "patient-123-records");
-    client.grant_access(&hospital, &doctor, &resource_id, &0);
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &vec![&env, Action::Read],
+        &0,
+    );
 
     // Check that doctor has access
     assert!(client.check_access(&doctor, &resource_id));
@@ -142,7 +150,13 @@ fn test_revoke_access() {
     );
 
     let resource_id = String::from_str(&env, "patient-123-records");
-    client.grant_access(&hospital, &doctor, &resource_id, &0);
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &vec![&env, Action::Read],
+        &0,
+    );
 
     // Verify access exists
     assert!(client.check_access(&doctor, &resource_id));
@@ -190,7 +204,13 @@ fn test_check_access_expired() {
 
     // Grant access with expiration at timestamp 100
     let resource_id = String::from_str(&env, "patient-123-records");
-    client.grant_access(&hospital, &doctor, &resource_id, &100);
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &vec![&env, Action::Read],
+        &100,
+    );
 
     // Access should be valid before expiration
     assert!(client.check_access(&doctor, &resource_id));
@@ -234,8 +254,20 @@ fn test_get_entity_permissions() {
     let resource_1 = String::from_str(&env, "patient-123-records");
     let resource_2 = String::from_str(&env, "patient-456-records");
 
-    client.grant_access(&hospital, &doctor, &resource_1, &0);
-    client.grant_access(&hospital, &doctor, &resource_2, &0);
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_1,
+        &vec![&env, Action::Read],
+        &0,
+    );
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_2,
+        &vec![&env, Action::Read],
+        &0,
+    );
 
     // Get all permissions for the doctor
     let permissions = client.get_entity_permissions(&doctor);
@@ -321,3 +353,1868 @@ fn test_update_entity() {
     let entity = client.get_entity(&hospital);
     assert_eq!(entity.metadata, new_metadata);
 }
+
+#[test]
+fn test_initialize_grants_admin_role() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    assert!(client.has_role(&admin, &Role::Admin));
+    assert_eq!(client.get_role_members(&Role::Admin).len(), 1);
+    assert!(client.has_capability(&admin, &Capability::ManageEntities));
+    assert!(client.has_capability(&admin, &Capability::RevokeAnyAccess));
+}
+
+#[test]
+fn test_grant_and_revoke_role() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let officer = Address::generate(&env);
+    client.grant_role(&admin, &officer, &Role::ComplianceOfficer);
+    client.grant_role(&admin, &officer, &Role::Verifier);
+
+    assert!(client.has_role(&officer, &Role::ComplianceOfficer));
+    assert!(client.has_role(&officer, &Role::Verifier));
+    assert!(!client.has_role(&officer, &Role::Admin));
+    assert_eq!(client.get_roles(&officer).len(), 2);
+
+    client.revoke_role(&admin, &officer, &Role::ComplianceOfficer);
+
+    assert!(!client.has_role(&officer, &Role::ComplianceOfficer));
+    assert!(client.has_role(&officer, &Role::Verifier));
+    assert_eq!(client.get_role_members(&Role::ComplianceOfficer).len(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Only admin can manage roles")]
+fn test_grant_role_non_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let officer = Address::generate(&env);
+    client.grant_role(&admin, &officer, &Role::ComplianceOfficer);
+
+    // A compliance officer cannot hand out roles
+    let clerk = Address::generate(&env);
+    client.grant_role(&officer, &clerk, &Role::RecordsClerk);
+}
+
+#[test]
+#[should_panic(expected = "Role already granted")]
+fn test_grant_role_twice() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let clerk = Address::generate(&env);
+    client.grant_role(&admin, &clerk, &Role::RecordsClerk);
+    client.grant_role(&admin, &clerk, &Role::RecordsClerk);
+}
+
+#[test]
+fn test_multiple_admins() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    // Delegate admin to a second account, which can then manage roles itself
+    let second_admin = Address::generate(&env);
+    client.grant_role(&admin, &second_admin, &Role::Admin);
+
+    let clerk = Address::generate(&env);
+    client.grant_role(&second_admin, &clerk, &Role::RecordsClerk);
+    assert!(client.has_role(&clerk, &Role::RecordsClerk));
+
+    // The original admin can be removed once another admin exists
+    client.revoke_role(&second_admin, &admin, &Role::Admin);
+    assert!(!client.has_role(&admin, &Role::Admin));
+    assert_eq!(client.get_role_members(&Role::Admin).len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "Cannot revoke the last admin")]
+fn test_revoke_last_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    client.revoke_role(&admin, &admin, &Role::Admin);
+}
+
+#[test]
+fn test_role_capabilities() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let verifier = Address::generate(&env);
+    client.grant_role(&admin, &verifier, &Role::Verifier);
+
+    // Roles carry no capabilities until configured
+    assert!(!client.has_capability(&verifier, &Capability::ManageEntities));
+
+    let mut capabilities = Vec::new(&env);
+    capabilities.push_back(Capability::ManageEntities);
+    client.set_role_capabilities(&admin, &Role::Verifier, &capabilities);
+
+    assert_eq!(client.get_role_capabilities(&Role::Verifier), capabilities);
+    assert!(client.has_capability(&verifier, &Capability::ManageEntities));
+    assert!(!client.has_capability(&verifier, &Capability::RevokeAnyAccess));
+
+    // The verifier can now deactivate entities
+    let hospital = Address::generate(&env);
+    client.register_entity(
+        &hospital,
+        &EntityType::Hospital,
+        &String::from_str(&env, "City Hospital"),
+        &String::from_str(&env, "metadata"),
+    );
+    client.deactivate_entity(&verifier, &hospital);
+    assert!(!client.get_entity(&hospital).active);
+}
+
+#[test]
+fn test_compliance_officer_revokes_access() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let officer = Address::generate(&env);
+    client.grant_role(&admin, &officer, &Role::ComplianceOfficer);
+    let mut capabilities = Vec::new(&env);
+    capabilities.push_back(Capability::RevokeAnyAccess);
+    client.set_role_capabilities(&admin, &Role::ComplianceOfficer, &capabilities);
+
+    let hospital = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    client.register_entity(
+        &hospital,
+        &EntityType::Hospital,
+        &String::from_str(&env, "City Hospital"),
+        &String::from_str(&env, "metadata"),
+    );
+    client.register_entity(
+        &doctor,
+        &EntityType::Doctor,
+        &String::from_str(&env, "Dr. Smith"),
+        &String::from_str(&env, "metadata"),
+    );
+
+    let resource_id = String::from_str(&env, "patient-123-records");
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &vec![&env, Action::Read],
+        &0,
+    );
+
+    client.revoke_access(&officer, &doctor, &resource_id);
+    assert!(!client.check_access(&doctor, &resource_id));
+}
+
+#[test]
+#[should_panic(expected = "Admin capabilities cannot be changed")]
+fn test_set_admin_capabilities_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    client.set_role_capabilities(&admin, &Role::Admin, &Vec::new(&env));
+}
+
+fn register_hospital_and_doctor(env: &Env, client: &AccessControlClient) -> (Address, Address) {
+    let hospital = Address::generate(env);
+    let doctor = Address::generate(env);
+
+    client.register_entity(
+        &hospital,
+        &EntityType::Hospital,
+        &String::from_str(env, "City Hospital"),
+        &String::from_str(env, "metadata"),
+    );
+    client.register_entity(
+        &doctor,
+        &EntityType::Doctor,
+        &String::from_str(env, "Dr. Smith"),
+        &String::from_str(env, "metadata"),
+    );
+
+    (hospital, doctor)
+}
+
+#[test]
+fn test_check_access_for_action() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, doctor) = register_hospital_and_doctor(&env, &client);
+
+    // View-only grant for a consulting physician
+    let resource_id = String::from_str(&env, "patient-123-records");
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &vec![&env, Action::Read],
+        &0,
+    );
+
+    assert!(client.check_access(&doctor, &resource_id));
+    assert!(client.check_access_for(&doctor, &resource_id, &Action::Read));
+    assert!(!client.check_access_for(&doctor, &resource_id, &Action::Write));
+    assert!(!client.check_access_for(&doctor, &resource_id, &Action::Share));
+}
+
+#[test]
+fn test_regrant_with_different_actions() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, doctor) = register_hospital_and_doctor(&env, &client);
+
+    let resource_id = String::from_str(&env, "patient-123-records");
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &vec![&env, Action::Read],
+        &0,
+    );
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &vec![&env, Action::Read, Action::Write, Action::Amend],
+        &0,
+    );
+
+    // The second grant replaces the first instead of duplicating it
+    let permissions = client.get_entity_permissions(&doctor);
+    assert_eq!(permissions.len(), 1);
+    assert_eq!(permissions.get(0).unwrap().actions.len(), 3);
+    assert!(client.check_access_for(&doctor, &resource_id, &Action::Amend));
+    assert!(!client.check_access_for(&doctor, &resource_id, &Action::Export));
+    assert_eq!(client.get_authorized_parties(&resource_id).len(), 1);
+}
+
+#[test]
+fn test_grants_from_multiple_grantors() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, doctor) = register_hospital_and_doctor(&env, &client);
+
+    let patient = Address::generate(&env);
+    client.register_entity(
+        &patient,
+        &EntityType::Patient,
+        &String::from_str(&env, "Jane Doe"),
+        &String::from_str(&env, "metadata"),
+    );
+
+    let resource_id = String::from_str(&env, "patient-123-records");
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &vec![&env, Action::Read],
+        &0,
+    );
+    client.grant_access(
+        &patient,
+        &doctor,
+        &resource_id,
+        &vec![&env, Action::Share],
+        &0,
+    );
+    assert_eq!(client.get_entity_permissions(&doctor).len(), 2);
+
+    // Revoking the hospital's grant leaves the patient's grant in place
+    client.revoke_access(&hospital, &doctor, &resource_id);
+    assert!(!client.check_access_for(&doctor, &resource_id, &Action::Read));
+    assert!(client.check_access_for(&doctor, &resource_id, &Action::Share));
+    assert_eq!(client.get_authorized_parties(&resource_id).len(), 1);
+
+    client.revoke_access(&patient, &doctor, &resource_id);
+    assert!(!client.check_access(&doctor, &resource_id));
+    assert_eq!(client.get_authorized_parties(&resource_id).len(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to revoke this access")]
+fn test_revoke_access_other_grantor() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, doctor) = register_hospital_and_doctor(&env, &client);
+
+    let resource_id = String::from_str(&env, "patient-123-records");
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &vec![&env, Action::Read],
+        &0,
+    );
+
+    let stranger = Address::generate(&env);
+    client.revoke_access(&stranger, &doctor, &resource_id);
+}
+
+#[test]
+#[should_panic(expected = "No actions specified")]
+fn test_grant_access_without_actions() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, doctor) = register_hospital_and_doctor(&env, &client);
+
+    let resource_id = String::from_str(&env, "patient-123-records");
+    client.grant_access(&hospital, &doctor, &resource_id, &Vec::new(&env), &0);
+}
+
+#[test]
+fn test_wildcard_grant_covers_children() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+
+    let labs = patient_resource(&env, &patient, "labs/*");
+    client.grant_access(&patient, &doctor, &labs, &vec![&env, Action::Read], &0);
+
+    let cbc = patient_resource(&env, &patient, "labs/cbc");
+    let nested = patient_resource(&env, &patient, "labs/2024/lipid-panel");
+    assert!(client.check_access(&doctor, &cbc));
+    assert!(client.check_access_for(&doctor, &nested, &Action::Read));
+    assert!(!client.check_access_for(&doctor, &nested, &Action::Write));
+
+    // Siblings, look-alike prefixes and the parent itself are not covered
+    assert!(!client.check_access(&doctor, &patient_resource(&env, &patient, "imaging/ct")));
+    assert!(!client.check_access(
+        &doctor,
+        &patient_resource(&env, &patient, "labs-archive/cbc")
+    ));
+    assert!(!client.check_access(&doctor, &patient_resource(&env, &patient, "labs")));
+}
+
+#[test]
+fn test_exact_access_ignores_wildcards() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+
+    let everything = patient_resource(&env, &patient, "*");
+    client.grant_access(
+        &patient,
+        &doctor,
+        &everything,
+        &vec![&env, Action::Read],
+        &0,
+    );
+
+    let therapy = patient_resource(&env, &patient, "records/mental_health");
+    assert!(client.check_access_for(&doctor, &therapy, &Action::Read));
+    assert!(!client.check_exact_access_for(&doctor, &therapy, &Action::Read));
+    assert!(!client.audit_exact_access(&doctor, &therapy, &Action::Read, &None));
+
+    client.grant_access(&patient, &doctor, &therapy, &vec![&env, Action::Read], &0);
+    assert!(client.check_exact_access_for(&doctor, &therapy, &Action::Read));
+    assert!(client.audit_exact_access(&doctor, &therapy, &Action::Read, &None));
+    assert_eq!(client.get_resource_audit_count(&therapy), 2);
+}
+
+#[test]
+#[should_panic(expected = "Cannot grant access to self")]
+fn test_grant_to_self_rejected() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+
+    client.grant_access(
+        &doctor,
+        &doctor,
+        &patient_resource(&env, &patient, "records"),
+        &vec![&env, Action::Read],
+        &0,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to grant this access")]
+fn test_grant_on_other_patients_resource_rejected() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, doctor) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &patient_resource(&env, &patient, "records"),
+        &vec![&env, Action::Read],
+        &0,
+    );
+}
+
+#[test]
+fn test_delegable_grant_holder_can_grant() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, doctor) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+    let records = patient_resource(&env, &patient, "records/*");
+
+    // The patient lets the hospital manage access to their records
+    client.grant_delegable_access(
+        &patient,
+        &hospital,
+        &records,
+        &vec![&env, Action::Read, Action::Write],
+        &0,
+        &1,
+    );
+    let labs = patient_resource(&env, &patient, "records/labs");
+    client.grant_access(&hospital, &doctor, &labs, &vec![&env, Action::Read], &0);
+
+    assert!(client.check_access_for(&doctor, &labs, &Action::Read));
+
+    // The grant hangs off the hospital's, so it lapses with it
+    let chain = client.get_delegation_chain(&doctor, &labs, &hospital);
+    assert_eq!(chain.len(), 2);
+    assert!(!chain.get(0).unwrap().delegation.can_delegate);
+    client.revoke_access(&patient, &hospital, &records);
+    assert!(!client.check_access_for(&doctor, &labs, &Action::Read));
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to grant this access")]
+fn test_plain_grant_holder_cannot_grant() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, doctor) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+    let records = patient_resource(&env, &patient, "records");
+    client.grant_access(
+        &patient,
+        &hospital,
+        &records,
+        &vec![&env, Action::Read],
+        &1_000,
+    );
+
+    client.grant_access(&hospital, &doctor, &records, &vec![&env, Action::Read], &0);
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to grant this access")]
+fn test_delegable_holder_cannot_grant_beyond_parent() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, doctor) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+    let records = patient_resource(&env, &patient, "records");
+    client.grant_delegable_access(
+        &patient,
+        &hospital,
+        &records,
+        &vec![&env, Action::Read],
+        &1_000,
+        &1,
+    );
+
+    // Adds Write and drops the expiry
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &records,
+        &vec![&env, Action::Read, Action::Write],
+        &0,
+    );
+}
+
+#[test]
+fn test_get_authorized_parties_includes_inherited() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+
+    let lab_tech = Address::generate(&env);
+    client.register_entity(
+        &lab_tech,
+        &EntityType::Doctor,
+        &String::from_str(&env, "Lab Tech"),
+        &String::from_str(&env, "metadata"),
+    );
+
+    let cbc = patient_resource(&env, &patient, "labs/cbc");
+    client.grant_access(
+        &patient,
+        &doctor,
+        &patient_resource(&env, &patient, "*"),
+        &vec![&env, Action::Read],
+        &0,
+    );
+    client.grant_access(
+        &patient,
+        &lab_tech,
+        &patient_resource(&env, &patient, "labs/*"),
+        &vec![&env, Action::Read],
+        &0,
+    );
+    client.grant_access(&patient, &doctor, &cbc, &vec![&env, Action::Write], &0);
+
+    let parties = client.get_authorized_parties(&cbc);
+    assert_eq!(parties.len(), 2);
+    assert!(parties.contains(&doctor));
+    assert!(parties.contains(&lab_tech));
+
+    let imaging = patient_resource(&env, &patient, "imaging/ct");
+    let parties = client.get_authorized_parties(&imaging);
+    assert_eq!(parties.len(), 1);
+    assert!(parties.contains(&doctor));
+}
+
+#[test]
+#[should_panic(expected = "Invalid resource id")]
+fn test_grant_access_rejects_inner_wildcard() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, doctor) = register_hospital_and_doctor(&env, &client);
+
+    let resource_id = String::from_str(&env, "patient:*/labs");
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &resource_id,
+        &vec![&env, Action::Read],
+        &0,
+    );
+}
+
+fn register_patient(env: &Env, client: &AccessControlClient) -> Address {
+    let patient = Address::generate(env);
+    client.register_entity(
+        &patient,
+        &EntityType::Patient,
+        &String::from_str(env, "Jane Doe"),
+        &String::from_str(env, "metadata"),
+    );
+    patient
+}
+
+fn open_break_glass(
+    env: &Env,
+    client: &AccessControlClient,
+    admin: &Address,
+) -> (Address, Address, String, u64) {
+    let (_, doctor) = register_hospital_and_doctor(env, client);
+    client.verify_clinician(admin, &doctor);
+    let patient = register_patient(env, client);
+    let resource_id = patient_resource(env, &patient, "labs");
+
+    let id = client.break_glass_access(
+        &doctor,
+        &patient,
+        &resource_id,
+        &String::from_str(env, "Unconscious patient in ER"),
+    );
+
+    (doctor, patient, resource_id, id)
+}
+
+#[test]
+fn test_break_glass_access() {
+    use soroban_sdk::testutils::Ledger;
+
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (doctor, patient, labs, id) = open_break_glass(&env, &client, &admin);
+
+    // Emergency access is read-only
+    assert!(client.check_access_for(&doctor, &labs, &Action::Read));
+    assert!(!client.check_access_for(&doctor, &labs, &Action::Write));
+
+    let record = client.get_break_glass(&id);
+    assert_eq!(record.patient, patient);
+    assert_eq!(record.status, BreakGlassStatus::PendingReview);
+    assert_eq!(record.expires_at, 1_000 + 4 * 60 * 60);
+    assert_eq!(client.get_pending_break_glass().len(), 1);
+    assert_eq!(client.get_patient_break_glass(&patient).len(), 1);
+
+    // The grant lapses on its own after the time box
+    env.ledger().set_timestamp(record.expires_at);
+    assert!(!client.check_access(&doctor, &labs));
+}
+
+#[test]
+fn test_patient_approves_break_glass() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, patient, _, id) = open_break_glass(&env, &client, &admin);
+
+    client.approve_break_glass(&patient, &id, &None);
+
+    let record = client.get_break_glass(&id);
+    assert_eq!(record.status, BreakGlassStatus::Approved);
+    assert_eq!(record.reviewed_by, Some(patient.clone()));
+    assert_eq!(client.get_pending_break_glass().len(), 0);
+    assert_eq!(client.get_patient_break_glass(&patient).len(), 1);
+}
+
+#[test]
+fn test_compliance_officer_flags_break_glass() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let officer = Address::generate(&env);
+    client.grant_role(&admin, &officer, &Role::ComplianceOfficer);
+    client.set_role_capabilities(
+        &admin,
+        &Role::ComplianceOfficer,
+        &vec![&env, Capability::ReviewBreakGlass],
+    );
+
+    let (doctor, _, resource_id, id) = open_break_glass(&env, &client, &admin);
+    let reason = String::from_str(&env, "No emergency recorded at that time");
+    client.flag_break_glass(&officer, &id, &reason);
+
+    let record = client.get_break_glass(&id);
+    assert_eq!(record.status, BreakGlassStatus::Flagged);
+    assert_eq!(record.review_note, Some(reason));
+
+    // Flagging ends the emergency grant immediately
+    assert!(!client.check_access(&doctor, &resource_id));
+    assert_eq!(client.get_authorized_parties(&resource_id).len(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to review break-glass access")]
+fn test_break_glass_review_by_stranger() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (doctor, _, _, id) = open_break_glass(&env, &client, &admin);
+
+    // The clinician cannot sign off on their own emergency access
+    client.approve_break_glass(&doctor, &id, &None);
+}
+
+#[test]
+#[should_panic(expected = "Break-glass access already reviewed")]
+fn test_break_glass_reviewed_twice() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, patient, _, id) = open_break_glass(&env, &client, &admin);
+
+    client.approve_break_glass(&patient, &id, &None);
+    client.flag_break_glass(&patient, &id, &String::from_str(&env, "changed my mind"));
+}
+
+#[test]
+#[should_panic(expected = "Justification required")]
+fn test_break_glass_requires_justification() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    client.verify_clinician(&admin, &doctor);
+
+    client.break_glass_access(
+        &doctor,
+        &Address::generate(&env),
+        &String::from_str(&env, "patient:123/*"),
+        &String::from_str(&env, ""),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Only active clinicians can break glass")]
+fn test_break_glass_requires_clinician() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, _) = register_hospital_and_doctor(&env, &client);
+
+    client.break_glass_access(
+        &hospital,
+        &Address::generate(&env),
+        &String::from_str(&env, "patient:123/*"),
+        &String::from_str(&env, "Emergency"),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Clinician not verified")]
+fn test_break_glass_requires_verified_clinician() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let patient = register_patient(&env, &client);
+
+    // Anyone can register as a doctor; that alone must not open emergency access
+    let doctor = register_doctor(&env, &client, "Dr. Nobody");
+    client.break_glass_access(
+        &doctor,
+        &patient,
+        &patient_resource(&env, &patient, "labs"),
+        &String::from_str(&env, "Emergency"),
+    );
+}
+
+#[test]
+fn test_verifier_vouches_for_clinician() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let verifier = Address::generate(&env);
+    client.grant_role(&admin, &verifier, &Role::Verifier);
+    let doctor = register_doctor(&env, &client, "Dr. Smith");
+
+    client.verify_clinician(&verifier, &doctor);
+    assert!(client.is_verified_clinician(&doctor));
+
+    // Vouches lapse with the verifier's role
+    client.revoke_role(&admin, &verifier, &Role::Verifier);
+    assert!(!client.is_verified_clinician(&doctor));
+
+    client.verify_clinician(&admin, &doctor);
+    client.revoke_clinician_verification(&admin, &doctor);
+    assert!(!client.is_verified_clinician(&doctor));
+}
+
+#[test]
+#[should_panic(expected = "Only admins and verifiers can verify clinicians")]
+fn test_doctor_cannot_verify_self() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let doctor = register_doctor(&env, &client, "Dr. Smith");
+
+    client.verify_clinician(&doctor, &doctor);
+}
+
+#[test]
+#[should_panic(expected = "Break-glass access cannot use wildcards")]
+fn test_break_glass_rejects_wildcard() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    client.verify_clinician(&admin, &doctor);
+    let patient = register_patient(&env, &client);
+
+    client.break_glass_access(
+        &doctor,
+        &patient,
+        &patient_resource(&env, &patient, "*"),
+        &String::from_str(&env, "Emergency"),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Resource does not belong to patient")]
+fn test_break_glass_limited_to_patient_resources() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    client.verify_clinician(&admin, &doctor);
+    let patient = register_patient(&env, &client);
+    let other = register_patient(&env, &client);
+
+    client.break_glass_access(
+        &doctor,
+        &patient,
+        &patient_resource(&env, &other, "labs"),
+        &String::from_str(&env, "Emergency"),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Patient not registered")]
+fn test_break_glass_requires_registered_patient() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    client.verify_clinician(&admin, &doctor);
+    let reviewer = Address::generate(&env);
+
+    client.break_glass_access(
+        &doctor,
+        &reviewer,
+        &patient_resource(&env, &reviewer, "labs"),
+        &String::from_str(&env, "Emergency"),
+    );
+}
+
+fn patient_resource(env: &Env, patient: &Address, scope: &str) -> String {
+    let mut resource = Bytes::from_slice(env, b"patient:");
+    resource.append(&patient.to_string().to_bytes());
+    resource.append(&Bytes::from_slice(env, b"/"));
+    resource.append(&Bytes::from_slice(env, scope.as_bytes()));
+    String::from(resource)
+}
+
+#[test]
+fn test_audit_access_records_outcome() {
+    use soroban_sdk::testutils::Ledger;
+
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(5_000);
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+    let resource_id = patient_resource(&env, &patient, "records");
+
+    client.grant_access(
+        &patient,
+        &doctor,
+        &resource_id,
+        &vec![&env, Action::Read],
+        &0,
+    );
+
+    let treat = Some(Symbol::new(&env, "TREAT"));
+    assert!(client.audit_access(&doctor, &resource_id, &Action::Read, &treat));
+    assert!(!client.audit_access(&doctor, &resource_id, &Action::Export, &None));
+
+    let log = client.get_entity_audit_log(&doctor, &doctor, &0, &10);
+    assert_eq!(log.len(), 2);
+
+    let first = log.get(0).unwrap();
+    assert_eq!(first.accessor, doctor);
+    assert_eq!(first.resource_id, resource_id);
+    assert_eq!(first.action, Action::Read);
+    assert_eq!(first.outcome, AccessOutcome::Granted);
+    assert_eq!(first.timestamp, 5_000);
+    assert_eq!(first.purpose, treat);
+
+    let second = log.get(1).unwrap();
+    assert_eq!(second.action, Action::Export);
+    assert_eq!(second.outcome, AccessOutcome::Denied);
+    assert_eq!(second.purpose, None);
+
+    // The patient sees every attempt against their records
+    let log = client.get_resource_audit_log(&patient, &resource_id, &0, &10);
+    assert_eq!(log.len(), 2);
+    assert_eq!(client.get_resource_audit_count(&resource_id), 2);
+}
+
+#[test]
+fn test_audit_log_pagination() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    let resource_id = String::from_str(&env, "patient-123-records");
+
+    for _ in 0..5 {
+        client.audit_access(&doctor, &resource_id, &Action::Read, &None);
+    }
+    assert_eq!(client.get_entity_audit_count(&doctor), 5);
+
+    let page = client.get_entity_audit_log(&admin, &doctor, &0, &2);
+    assert_eq!(page.len(), 2);
+    assert_eq!(page.get(0).unwrap().id, 1);
+
+    let page = client.get_entity_audit_log(&admin, &doctor, &4, &2);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get(0).unwrap().id, 5);
+
+    let page = client.get_entity_audit_log(&admin, &doctor, &10, &2);
+    assert_eq!(page.len(), 0);
+}
+
+#[test]
+fn test_compliance_officer_views_audit_log() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    let officer = Address::generate(&env);
+    client.grant_role(&admin, &officer, &Role::ComplianceOfficer);
+    client.set_role_capabilities(
+        &admin,
+        &Role::ComplianceOfficer,
+        &vec![&env, Capability::ViewAuditLog],
+    );
+
+    let resource_id = String::from_str(&env, "patient-123-records");
+    client.audit_access(&doctor, &resource_id, &Action::Read, &None);
+
+    assert_eq!(
+        client
+            .get_resource_audit_log(&officer, &resource_id, &0, &10)
+            .len(),
+        1
+    );
+    assert_eq!(
+        client
+            .get_entity_audit_log(&officer, &doctor, &0, &10)
+            .len(),
+        1
+    );
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to view audit log")]
+fn test_audit_log_hidden_from_other_patients() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    let patient = Address::generate(&env);
+    let resource_id = patient_resource(&env, &patient, "records");
+    client.audit_access(&doctor, &resource_id, &Action::Read, &None);
+
+    let stranger = Address::generate(&env);
+    client.get_resource_audit_log(&stranger, &resource_id, &0, &10);
+}
+
+#[test]
+fn test_break_glass_is_audited() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (doctor, _, resource_id, _) = open_break_glass(&env, &client, &admin);
+
+    let log = client.get_resource_audit_log(&admin, &resource_id, &0, &10);
+    assert_eq!(log.len(), 1);
+    let entry = log.get(0).unwrap();
+    assert_eq!(entry.accessor, doctor);
+    assert_eq!(entry.outcome, AccessOutcome::Granted);
+    assert_eq!(entry.purpose, Some(symbol_short!("ETREAT")));
+}
+
+/// `patient:<address>/rec-NN`, so every id has the same length
+fn numbered_resource(env: &Env, patient: &Address, n: u32) -> String {
+    let mut resource = patient_resource(env, patient, "rec-").to_bytes();
+    resource.push_back(b'0' + (n / 10) as u8);
+    resource.push_back(b'0' + (n % 10) as u8);
+    String::from(resource)
+}
+
+#[test]
+fn test_check_access_cost_is_constant() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.cost_estimate().budget().reset_unlimited();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, few) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+    let (_, many) = register_hospital_and_doctor(&env, &client);
+
+    client.grant_access(
+        &patient,
+        &few,
+        &numbered_resource(&env, &patient, 0),
+        &vec![&env, Action::Read],
+        &0,
+    );
+    for n in 0..60 {
+        client.grant_access(
+            &patient,
+            &many,
+            &numbered_resource(&env, &patient, n),
+            &vec![&env, Action::Read],
+            &0,
+        );
+    }
+
+    // Same ledger and same lookup; only the entity's grant count differs
+    let resource_id = numbered_resource(&env, &patient, 0);
+    env.cost_estimate().budget().reset_default();
+    assert!(client.check_access(&few, &resource_id));
+    let few_cost = env.cost_estimate().budget().cpu_instruction_cost();
+
+    env.cost_estimate().budget().reset_default();
+    assert!(client.check_access(&many, &resource_id));
+    let many_cost = env.cost_estimate().budget().cpu_instruction_cost();
+
+    // Storage map lookups vary slightly with key order, so allow a small margin
+    assert!(many_cost <= few_cost + few_cost / 10);
+}
+
+#[test]
+fn test_grant_indexes_are_paginated() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.cost_estimate().budget().reset_unlimited();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+    for n in 0..60 {
+        client.grant_access(
+            &patient,
+            &doctor,
+            &numbered_resource(&env, &patient, n),
+            &vec![&env, Action::Read],
+            &0,
+        );
+    }
+
+    assert_eq!(client.get_entity_resource_count(&doctor), 60);
+    assert_eq!(client.get_entity_permissions_page(&doctor, &0).len(), 50);
+    assert_eq!(client.get_entity_permissions_page(&doctor, &1).len(), 10);
+    assert_eq!(client.get_entity_permissions(&doctor).len(), 60);
+
+    // Revoking from the first page backfills it from the last one
+    client.revoke_access(&patient, &doctor, &numbered_resource(&env, &patient, 3));
+    assert_eq!(client.get_entity_resource_count(&doctor), 59);
+    assert_eq!(client.get_entity_permissions_page(&doctor, &0).len(), 50);
+    assert_eq!(client.get_entity_permissions_page(&doctor, &1).len(), 9);
+    assert!(!client.check_access(&doctor, &numbered_resource(&env, &patient, 3)));
+    assert!(client.check_access(&doctor, &numbered_resource(&env, &patient, 59)));
+
+    let resource_id = numbered_resource(&env, &patient, 10);
+    assert_eq!(client.get_authorized_party_count(&resource_id), 1);
+    assert_eq!(
+        client.get_authorized_parties_page(&resource_id, &0),
+        vec![&env, doctor.clone()]
+    );
+}
+
+#[test]
+fn test_migrate_legacy_access_list() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, doctor) = register_hospital_and_doctor(&env, &client);
+    let records = String::from_str(&env, "patient:123/records");
+    let labs = String::from_str(&env, "patient:123/labs/*");
+
+    // Grants written in the old single-vector layout, before actions existed
+    env.as_contract(&contract_id, || {
+        let mut legacy = Vec::new(&env);
+        for resource_id in [records.clone(), labs.clone()] {
+            legacy.push_back(LegacyAccessPermission {
+                resource_id: resource_id.clone(),
+                granted_by: hospital.clone(),
+                granted_at: 0,
+                expires_at: 0,
+            });
+            env.storage().persistent().set(
+                &DataKey::ResourceAccess(resource_id),
+                &vec![&env, doctor.clone()],
+            );
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::AccessList(doctor.clone()), &legacy);
+    });
+
+    // Legacy grants are honoured before migration
+    let cbc = String::from_str(&env, "patient:123/labs/cbc");
+    assert!(client.check_access(&doctor, &cbc));
+    assert_eq!(client.get_authorized_parties(&records).len(), 1);
+
+    assert_eq!(client.migrate_access_list(&doctor), 2);
+    assert_eq!(client.migrate_access_list(&doctor), 0);
+
+    env.as_contract(&contract_id, || {
+        let storage = env.storage().persistent();
+        assert!(!storage.has(&DataKey::AccessList(doctor.clone())));
+        assert!(!storage.has(&DataKey::ResourceAccess(records.clone())));
+    });
+    assert!(client.check_access(&doctor, &cbc));
+    assert!(client.check_access(&doctor, &records));
+    // Legacy grants covered every action
+    assert!(client.check_access_for(&doctor, &records, &Action::Export));
+    assert_eq!(client.get_entity_resource_count(&doctor), 2);
+    assert_eq!(
+        client.get_authorized_parties(&records),
+        vec![&env, doctor.clone()]
+    );
+
+    client.revoke_access(&hospital, &doctor, &records);
+    assert!(!client.check_access(&doctor, &records));
+}
+
+#[test]
+fn test_grant_migrates_legacy_access_list() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+    let records = patient_resource(&env, &patient, "records");
+
+    env.as_contract(&contract_id, || {
+        let legacy = vec![
+            &env,
+            LegacyAccessPermission {
+                resource_id: records.clone(),
+                granted_by: patient.clone(),
+                granted_at: 0,
+                expires_at: 0,
+            },
+        ];
+        env.storage()
+            .persistent()
+            .set(&DataKey::AccessList(doctor.clone()), &legacy);
+    });
+
+    client.grant_access(
+        &patient,
+        &doctor,
+        &patient_resource(&env, &patient, "imaging"),
+        &vec![&env, Action::Read],
+        &0,
+    );
+
+    assert_eq!(client.get_entity_resource_count(&doctor), 2);
+    assert_eq!(client.get_entity_permissions(&doctor).len(), 2);
+    assert!(client.check_access(&doctor, &records));
+}
+
+#[test]
+fn test_deactivation_invalidates_grants() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+    let resource_id = patient_resource(&env, &patient, "records");
+    client.grant_access(
+        &patient,
+        &doctor,
+        &resource_id,
+        &vec![&env, Action::Read],
+        &0,
+    );
+
+    // Received grants stop working
+    client.deactivate_entity(&admin, &doctor);
+    assert!(!client.check_access(&doctor, &resource_id));
+    client.reactivate_entity(&admin, &doctor);
+    assert!(client.check_access(&doctor, &resource_id));
+
+    // Given grants stop working too
+    client.deactivate_entity(&admin, &patient);
+    assert!(!client.check_access(&doctor, &resource_id));
+    client.reactivate_entity(&admin, &patient);
+    assert!(client.check_access(&doctor, &resource_id));
+}
+
+#[test]
+#[should_panic(expected = "Grantor is not active")]
+fn test_deactivated_entity_cannot_grant() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, doctor) = register_hospital_and_doctor(&env, &client);
+    client.deactivate_entity(&admin, &hospital);
+
+    client.grant_access(
+        &hospital,
+        &doctor,
+        &String::from_str(&env, "patient:123/records"),
+        &vec![&env, Action::Read],
+        &0,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Only admin can reactivate entities")]
+fn test_reactivate_entity_non_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, doctor) = register_hospital_and_doctor(&env, &client);
+    client.deactivate_entity(&admin, &hospital);
+
+    client.reactivate_entity(&doctor, &hospital);
+}
+
+#[test]
+fn test_suspension_ends_automatically() {
+    use soroban_sdk::testutils::Ledger;
+
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+    let resource_id = patient_resource(&env, &patient, "records");
+    client.grant_access(
+        &patient,
+        &doctor,
+        &resource_id,
+        &vec![&env, Action::Read],
+        &0,
+    );
+
+    client.suspend_entity(
+        &admin,
+        &doctor,
+        &5_000,
+        &String::from_str(&env, "License under review"),
+    );
+    assert_eq!(client.get_entity_status(&doctor), EntityStatus::Suspended);
+    assert!(!client.check_access(&doctor, &resource_id));
+
+    env.ledger().set_timestamp(5_000);
+    assert_eq!(client.get_entity_status(&doctor), EntityStatus::Active);
+    assert!(client.check_access(&doctor, &resource_id));
+}
+
+#[test]
+fn test_entity_status_history() {
+    use soroban_sdk::testutils::Ledger;
+
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, _) = register_hospital_and_doctor(&env, &client);
+
+    let reason = String::from_str(&env, "Audit pending");
+    client.suspend_entity(&admin, &hospital, &2_000, &reason);
+    client.deactivate_entity(&admin, &hospital);
+    assert_eq!(
+        client.get_entity_status(&hospital),
+        EntityStatus::Deactivated
+    );
+    client.reactivate_entity(&admin, &hospital);
+
+    let history = client.get_entity_status_history(&hospital);
+    assert_eq!(history.len(), 4);
+    assert_eq!(history.get(0).unwrap().status, EntityStatus::Active);
+    assert_eq!(history.get(0).unwrap().changed_by, hospital);
+
+    let suspension = history.get(1).unwrap();
+    assert_eq!(suspension.status, EntityStatus::Suspended);
+    assert_eq!(suspension.until, 2_000);
+    assert_eq!(suspension.reason, Some(reason));
+    assert_eq!(suspension.changed_by, admin);
+
+    assert_eq!(history.get(2).unwrap().status, EntityStatus::Deactivated);
+    assert_eq!(history.get(3).unwrap().status, EntityStatus::Active);
+    assert_eq!(client.get_suspended_until(&hospital), 0);
+}
+
+fn setup_consent(
+    env: &Env,
+    client: &AccessControlClient,
+) -> (Address, Address, SigningKey, ConsentPayload) {
+    let (_, doctor) = register_hospital_and_doctor(env, client);
+    let patient = Address::generate(env);
+    client.register_entity(
+        &patient,
+        &EntityType::Patient,
+        &String::from_str(env, "Jane Doe"),
+        &String::from_str(env, "metadata"),
+    );
+
+    let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+    client.register_consent_key(
+        &patient,
+        &BytesN::from_array(env, &signing_key.verifying_key().to_bytes()),
+    );
+
+    let consent = ConsentPayload {
+        patient: patient.clone(),
+        grantee: doctor.clone(),
+        resource_id: patient_resource(env, &patient, "records"),
+        actions: vec![env, Action::Read],
+        expires_at: 0,
+        nonce: 1,
+    };
+
+    (patient, doctor, signing_key, consent)
+}
+
+fn sign_consent(
+    env: &Env,
+    contract_id: &Address,
+    signing_key: &SigningKey,
+    consent: &ConsentPayload,
+) -> BytesN<64> {
+    let message: std::vec::Vec<u8> = (contract_id.clone(), consent.clone())
+        .to_xdr(env)
+        .iter()
+        .collect();
+    BytesN::from_array(env, &signing_key.sign(&message).to_bytes())
+}
+
+#[test]
+fn test_grant_access_with_consent() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (patient, doctor, signing_key, consent) = setup_consent(&env, &client);
+    let clinic = Address::generate(&env);
+
+    let signature = sign_consent(&env, &contract_id, &signing_key, &consent);
+    client.grant_access_with_consent(&clinic, &consent, &signature);
+
+    assert!(client.check_access_for(&doctor, &consent.resource_id, &Action::Read));
+    assert!(client.is_consent_nonce_used(&patient, &1));
+    assert!(!client.is_consent_nonce_used(&patient, &2));
+
+    // The grant belongs to the patient, not the submitting clinic
+    let permissions = client.get_entity_permissions(&doctor);
+    assert_eq!(permissions.get(0).unwrap().granted_by, patient);
+}
+
+#[test]
+#[should_panic(expected = "Consent already used")]
+fn test_consent_replay_rejected() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, _, signing_key, consent) = setup_consent(&env, &client);
+    let clinic = Address::generate(&env);
+
+    let signature = sign_consent(&env, &contract_id, &signing_key, &consent);
+    client.grant_access_with_consent(&clinic, &consent, &signature);
+    client.grant_access_with_consent(&clinic, &consent, &signature);
+}
+
+#[test]
+#[should_panic]
+fn test_consent_tampered_payload_rejected() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, _, signing_key, mut consent) = setup_consent(&env, &client);
+    let clinic = Address::generate(&env);
+
+    let signature = sign_consent(&env, &contract_id, &signing_key, &consent);
+    consent.actions = vec![&env, Action::Read, Action::Export];
+    client.grant_access_with_consent(&clinic, &consent, &signature);
+}
+
+#[test]
+#[should_panic(expected = "No consent key registered")]
+fn test_consent_requires_registered_key() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (hospital, doctor) = register_hospital_and_doctor(&env, &client);
+    let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+    let consent = ConsentPayload {
+        patient: hospital,
+        grantee: doctor,
+        resource_id: String::from_str(&env, "patient:123/records"),
+        actions: vec![&env, Action::Read],
+        expires_at: 0,
+        nonce: 1,
+    };
+
+    let signature = sign_consent(&env, &contract_id, &signing_key, &consent);
+    client.grant_access_with_consent(&Address::generate(&env), &consent, &signature);
+}
+
+#[test]
+#[should_panic(expected = "Only patients can register consent keys")]
+fn test_register_consent_key_requires_patient() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, doctor) = register_hospital_and_doctor(&env, &client);
+
+    client.register_consent_key(&doctor, &BytesN::from_array(&env, &[1u8; 32]));
+}
+
+fn register_doctor(env: &Env, client: &AccessControlClient, name: &str) -> Address {
+    let doctor = Address::generate(env);
+    client.register_entity(
+        &doctor,
+        &EntityType::Doctor,
+        &String::from_str(env, name),
+        &String::from_str(env, "metadata"),
+    );
+    doctor
+}
+
+#[test]
+fn test_delegate_access_chain() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, attending) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+    let resident = register_doctor(&env, &client, "Dr. Resident");
+
+    let records = patient_resource(&env, &patient, "records/*");
+    client.grant_delegable_access(
+        &patient,
+        &attending,
+        &records,
+        &vec![&env, Action::Read, Action::Write],
+        &10_000,
+        &2,
+    );
+
+    // Covering resident gets read-only access to part of the records
+    let labs = patient_resource(&env, &patient, "records/labs");
+    client.delegate_access(
+        &attending,
+        &resident,
+        &labs,
+        &vec![&env, Action::Read],
+        &5_000,
+        &0,
+    );
+    assert!(client.check_access_for(&resident, &labs, &Action::Read));
+    assert!(!client.check_access_for(&resident, &labs, &Action::Write));
+
+    let chain = client.get_delegation_chain(&resident, &labs, &attending);
+    assert_eq!(chain.len(), 2);
+    assert_eq!(chain.get(0).unwrap().holder, resident);
+    assert!(!chain.get(0).unwrap().delegation.can_delegate);
+    let root = chain.get(1).unwrap();
+    assert_eq!(root.holder, attending);
+    assert_eq!(root.permission.granted_by, patient);
+    assert_eq!(root.delegation.origin, GrantOrigin::Direct);
+    assert_eq!(root.delegation.max_depth, 2);
+}
+
+#[test]
+fn test_revoking_parent_invalidates_descendants() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, attending) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+    let resident = register_doctor(&env, &client, "Dr. Resident");
+    let student = register_doctor(&env, &client, "Student");
+
+    let records = patient_resource(&env, &patient, "records");
+    let read = vec![&env, Action::Read];
+    client.grant_delegable_access(&patient, &attending, &records, &read, &0, &2);
+    client.delegate_access(&attending, &resident, &records, &read, &0, &1);
+    client.delegate_access(&resident, &student, &records, &read, &0, &0);
+    assert!(client.check_access(&student, &records));
+    assert_eq!(
+        client
+            .get_delegation_chain(&student, &records, &resident)
+            .len(),
+        3
+    );
+
+    client.revoke_access(&patient, &attending, &records);
+    assert!(!client.check_access(&resident, &records));
+    assert!(!client.check_access(&student, &records));
+
+    // A new grant to the attending does not revive the old chain
+    client.grant_delegable_access(&patient, &attending, &records, &read, &0, &2);
+    assert!(!client.check_access(&resident, &records));
+    assert_eq!(
+        client
+            .get_delegation_chain(&resident, &records, &attending)
+            .len(),
+        1
+    );
+}
+
+#[test]
+#[should_panic(expected = "No delegable grant covers this delegation")]
+fn test_delegation_cannot_widen_actions() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, attending) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+    let resident = register_doctor(&env, &client, "Dr. Resident");
+
+    let records = patient_resource(&env, &patient, "records");
+    client.grant_delegable_access(
+        &patient,
+        &attending,
+        &records,
+        &vec![&env, Action::Read],
+        &0,
+        &1,
+    );
+    client.delegate_access(
+        &attending,
+        &resident,
+        &records,
+        &vec![&env, Action::Read, Action::Export],
+        &0,
+        &0,
+    );
+}
+
+#[test]
+#[should_panic(expected = "No delegable grant covers this delegation")]
+fn test_delegation_cannot_outlive_parent() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, attending) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+    let resident = register_doctor(&env, &client, "Dr. Resident");
+
+    let records = patient_resource(&env, &patient, "records");
+    let read = vec![&env, Action::Read];
+    client.grant_delegable_access(&patient, &attending, &records, &read, &1_000, &1);
+    client.delegate_access(&attending, &resident, &records, &read, &0, &0);
+}
+
+#[test]
+#[should_panic(expected = "No delegable grant covers this delegation")]
+fn test_delegation_depth_limit() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, attending) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+    let resident = register_doctor(&env, &client, "Dr. Resident");
+    let student = register_doctor(&env, &client, "Student");
+
+    let records = patient_resource(&env, &patient, "records");
+    let read = vec![&env, Action::Read];
+    client.grant_delegable_access(&patient, &attending, &records, &read, &0, &1);
+    client.delegate_access(&attending, &resident, &records, &read, &0, &0);
+    client.delegate_access(&resident, &student, &records, &read, &0, &0);
+}
+
+#[test]
+#[should_panic(expected = "No delegable grant covers this delegation")]
+fn test_plain_grant_cannot_be_delegated() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(AccessControl, ());
+    let client = AccessControlClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    let (_, attending) = register_hospital_and_doctor(&env, &client);
+    let patient = register_patient(&env, &client);
+    let resident = register_doctor(&env, &client, "Dr. Resident");
+
+    let records = patient_resource(&env, &patient, "records");
+    let read = vec![&env, Action::Read];
+    client.grant_access(&patient, &attending, &records, &read, &0);
+    client.delegate_access(&attending, &resident, &records, &read, &0, &0);
+}
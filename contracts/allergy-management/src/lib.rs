@@ -63,8 +63,9 @@ pub struct AllergyManagement;
 
 #[contractimpl]
 impl AllergyManagement {
-    /// Initialize the contract with an admin address
-    pub fn initialize(env: Env, admin: Address) {
+    /// Initialize the contract with an admin address and an optional
+    /// access-control contract that replaces the local grant map
+    pub fn initialize(env: Env, admin: Address, access_control: Option<Address>) {
         admin.require_auth();
         
         if env.storage().instance().has(&DataKey::Admin) {
@@ -73,6 +74,33 @@ impl AllergyManagement {
         
         env.storage().instance().set(&DataKey::Admin, &admin);
         env.storage().instance().set(&DataKey::AllergyCounter, &0u64);
+        storage::set_access_control(&env, access_control);
+    }
+
+    /// Set or clear the access-control contract (admin only)
+    pub fn set_access_control(
+        env: Env,
+        admin: Address,
+        access_control: Option<Address>,
+    ) -> Result<(), Error> {
+        admin.require_auth();
+
+        let stored_admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(Error::Unauthorized)?;
+        if admin != stored_admin {
+            return Err(Error::Unauthorized);
+        }
+
+        storage::set_access_control(&env, access_control);
+        Ok(())
+    }
+
+    /// Get the configured access-control contract, if any
+    pub fn get_access_control(env: Env) -> Option<Address> {
+        storage::get_access_control(&env)
     }
 
     /// Record a new allergy for a patient
@@ -257,6 +285,9 @@ impl AllergyManagement {
     }
 
     /// Get all active allergies for a patient
+    ///
+    /// A denial by the access-control contract returns an empty list, so the
+    /// denial it logged is not rolled back.
     pub fn get_active_allergies(
         env: Env,
         patient_id: Address,
@@ -266,6 +297,9 @@ impl AllergyManagement {
 
         // Check access permissions
         if !storage::check_access_permission(&env, &patient_id, &requester) {
+            if storage::audits_denials(&env) {
+                return Ok(Vec::new(&env));
+            }
             return Err(Error::AccessDenied);
         }
 
@@ -284,6 +318,9 @@ impl AllergyManagement {
     }
 
     /// Get all allergies (active and resolved) for a patient
+    ///
+    /// A denial by the access-control contract returns an empty list, so the
+    /// denial it logged is not rolled back.
     pub fn get_all_allergies(
         env: Env,
         patient_id: Address,
@@ -293,6 +330,9 @@ impl AllergyManagement {
 
         // Check access permissions
         if !storage::check_access_permission(&env, &patient_id, &requester) {
+            if storage::audits_denials(&env) {
+                return Ok(Vec::new(&env));
+            }
             return Err(Error::AccessDenied);
         }
 
@@ -333,21 +373,27 @@ impl AllergyManagement {
     }
 
     /// Get allergy by ID (requires access)
+    ///
+    /// A denial by the access-control contract returns `None`, so the denial
+    /// it logged is not rolled back.
     pub fn get_allergy(
         env: Env,
         allergy_id: u64,
         requester: Address,
-    ) -> Result<AllergyRecord, Error> {
+    ) -> Result<Option<AllergyRecord>, Error> {
         requester.require_auth();
 
         let allergy = storage::get_allergy(&env, allergy_id)?;
 
         // Check access permissions
         if !storage::check_access_permission(&env, &allergy.patient_id, &requester) {
+            if storage::audits_denials(&env) {
+                return Ok(None);
+            }
             return Err(Error::AccessDenied);
         }
 
-        Ok(allergy)
+        Ok(Some(allergy))
     }
 }
 
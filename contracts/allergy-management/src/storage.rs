@@ -1,3 +1,4 @@
+use access_control_interface::{patient_resource_id, AccessControlClient, Action};
 use soroban_sdk::{Address, Env, String, Symbol, Vec};
 
 use crate::{AllergyRecord, DataKey, Error};
@@ -102,11 +103,54 @@ pub fn check_access_permission(env: &Env, patient_id: &Address, requester: &Addr
         }
     }
     
+    // Defer to the access-control contract when one is configured
+    if let Some(access_control) = get_access_control(env) {
+        return AccessControlClient::new(env, &access_control).audit_access(
+            requester,
+            &allergy_resource_id(env, patient_id),
+            &Action::Read,
+            &None,
+        );
+    }
+
     // Check explicit access grant
     let key = DataKey::AccessControl(patient_id.clone(), requester.clone());
     env.storage().persistent().has(&key)
 }
 
+/// Whether `check_access_permission` denials are written to the
+/// access-control audit log. Callers must then return normally on a denial,
+/// or the log entry is rolled back with the failed call.
+pub fn audits_denials(env: &Env) -> bool {
+    get_access_control(env).is_some()
+}
+
+/// Store or clear the access-control contract address
+pub fn set_access_control(env: &Env, access_control: Option<Address>) {
+    match access_control {
+        Some(address) => env
+            .storage()
+            .instance()
+            .set(&DataKey::AccessControlContract, &address),
+        None => env
+            .storage()
+            .instance()
+            .remove(&DataKey::AccessControlContract),
+    }
+}
+
+/// Get the configured access-control contract address
+pub fn get_access_control(env: &Env) -> Option<Address> {
+    env.storage()
+        .instance()
+        .get(&DataKey::AccessControlContract)
+}
+
+/// Resource id used for a patient's allergies in the access-control contract
+pub fn allergy_resource_id(env: &Env, patient_id: &Address) -> String {
+    patient_resource_id(env, patient_id, "allergies")
+}
+
 /// Store cross-sensitivity relationship between allergens
 pub fn add_cross_sensitivity(env: &Env, allergen1: &String, allergen2: &String) {
     let key1 = DataKey::CrossSensitivity(allergen1.clone(), allergen2.clone());
@@ -72,4 +72,5 @@ pub enum DataKey {
     PatientAllergies(Address),
     AccessControl(Address, Address), // (patient, provider)
     CrossSensitivity(String, String), // (allergen1, allergen2)
+    AccessControlContract,
 }
@@ -1,5 +1,6 @@
 #![no_std]
 
+use access_control_interface::{patient_resource_id, AccessControlClient, Action};
 use soroban_sdk::{
     contract, contracterror, contractimpl, contracttype, symbol_short, Address, Env, String,
     Symbol, Vec,
@@ -102,6 +103,8 @@ pub enum DataKey {
     PatientAllergies(Address),
     SeverityHistory(u64),
     DrugCrossSensitivity(String),
+    Admin,
+    AccessControl,
 }
 
 // Validation constants
@@ -115,6 +118,62 @@ pub struct AllergyTrackingContract;
 
 #[contractimpl]
 impl AllergyTrackingContract {
+    /// Initialize the contract with an admin and an optional access-control contract.
+    /// When configured, reads of patient allergies are checked against it.
+    pub fn initialize(
+        env: Env,
+        admin: Address,
+        access_control: Option<Address>,
+    ) -> Result<(), Error> {
+        admin.require_auth();
+
+        if env.storage().instance().has(&DataKey::Admin) {
+            return Err(Error::Unauthorized);
+        }
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        if let Some(access_control) = access_control {
+            env.storage()
+                .instance()
+                .set(&DataKey::AccessControl, &access_control);
+        }
+
+        Ok(())
+    }
+
+    /// Set or clear the access-control contract (admin only)
+    pub fn set_access_control(
+        env: Env,
+        admin: Address,
+        access_control: Option<Address>,
+    ) -> Result<(), Error> {
+        admin.require_auth();
+
+        let stored_admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .ok_or(Error::Unauthorized)?;
+        if admin != stored_admin {
+            return Err(Error::Unauthorized);
+        }
+
+        match access_control {
+            Some(access_control) => env
+                .storage()
+                .instance()
+                .set(&DataKey::AccessControl, &access_control),
+            None => env.storage().instance().remove(&DataKey::AccessControl),
+        }
+
+        Ok(())
+    }
+
+    /// Get the configured access-control contract, if any
+    pub fn get_access_control(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::AccessControl)
+    }
+
     /// Record a new allergy for a patient
     pub fn record_allergy(
         env: Env,
@@ -386,6 +445,9 @@ impl AllergyTrackingContract {
     }
 
     /// Get all active allergies for a patient
+    ///
+    /// A denial by the access-control contract returns an empty list, so the
+    /// denial it logged is not rolled back.
     pub fn get_active_allergies(
         env: Env,
         patient_id: Address,
@@ -393,6 +455,10 @@ impl AllergyTrackingContract {
     ) -> Result<Vec<AllergyRecord>, Error> {
         requester.require_auth();
 
+        if !Self::check_read_access(&env, &patient_id, &requester) {
+            return Ok(Vec::new(&env));
+        }
+
         let patient_key = DataKey::PatientAllergies(patient_id);
         let patient_allergies: Vec<u64> = env
             .storage()
@@ -471,6 +537,27 @@ impl AllergyTrackingContract {
 
     // ==================== Helper Functions ====================
 
+    fn check_read_access(env: &Env, patient_id: &Address, requester: &Address) -> bool {
+        if patient_id == requester {
+            return true;
+        }
+
+        let access_control: Option<Address> = env.storage().instance().get(&DataKey::AccessControl);
+        match access_control {
+            Some(access_control) => AccessControlClient::new(env, &access_control).audit_access(
+                requester,
+                &Self::allergy_resource_id(env, patient_id),
+                &Action::Read,
+                &None,
+            ),
+            None => true,
+        }
+    }
+
+    fn allergy_resource_id(env: &Env, patient_id: &Address) -> String {
+        patient_resource_id(env, patient_id, "allergies")
+    }
+
     fn validate_allergen(allergen: &String) -> Result<(), Error> {
         let len = allergen.len();
         if len < MIN_ALLERGEN_LENGTH {
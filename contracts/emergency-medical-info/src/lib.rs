@@ -1,5 +1,6 @@
 #![no_std]
 
+use access_control_interface::{patient_resource_id, AccessControlClient};
 use soroban_sdk::{
     contract, contractimpl, contracttype, Address, BytesN, Env, String, Symbol, Vec,
 };
@@ -46,6 +47,7 @@ pub struct EmergencyAccessLog {
     pub justification: String,
     pub location: String,
     pub access_time: u64,
+    pub break_glass_id: Option<u64>, // Review record in the access-control contract
 }
 
 #[contracttype]
@@ -68,6 +70,8 @@ pub enum DataKey {
     EmergencyAccessLog(Address),
     DNROrder(Address),
     EmergencyNotifications(Address),
+    Admin,
+    AccessControl,
 }
 
 #[contract]
@@ -75,6 +79,49 @@ pub struct EmergencyMedicalInfo;
 
 #[contractimpl]
 impl EmergencyMedicalInfo {
+    /// Initialize with an admin and an optional access-control contract.
+    /// When configured, emergency access goes through its break-glass policy.
+    pub fn initialize(env: Env, admin: Address, access_control: Option<Address>) {
+        if env.storage().instance().has(&DataKey::Admin) {
+            panic!("Already initialized");
+        }
+        admin.require_auth();
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        if let Some(access_control) = access_control {
+            env.storage()
+                .instance()
+                .set(&DataKey::AccessControl, &access_control);
+        }
+    }
+
+    /// Set or clear the access-control contract (admin only)
+    pub fn set_access_control(env: Env, admin: Address, access_control: Option<Address>) {
+        admin.require_auth();
+
+        let stored_admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .expect("Not initialized");
+        if admin != stored_admin {
+            panic!("Only admin");
+        }
+
+        match access_control {
+            Some(access_control) => env
+                .storage()
+                .instance()
+                .set(&DataKey::AccessControl, &access_control),
+            None => env.storage().instance().remove(&DataKey::AccessControl),
+        }
+    }
+
+    /// Get the configured access-control contract, if any
+    pub fn get_access_control(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::AccessControl)
+    }
+
     /// Set or update emergency profile for a patient
     /// Sub-second access optimized with persistent storage
     #[allow(clippy::too_many_arguments)]
@@ -150,7 +197,8 @@ impl EmergencyMedicalInfo {
     }
 
     /// Emergency access with break-glass protocol
-    /// Provides immediate access with full audit logging
+    /// Provides immediate access with full audit logging. When an access-control
+    /// contract is configured, the access is opened there for post-hoc review.
     pub fn emergency_access_request(
         env: Env,
         provider_id: Address,
@@ -161,6 +209,17 @@ impl EmergencyMedicalInfo {
     ) -> EmergencyProfile {
         provider_id.require_auth();
 
+        // Apply the shared break-glass policy when configured
+        let access_control: Option<Address> = env.storage().instance().get(&DataKey::AccessControl);
+        let break_glass_id = access_control.map(|access_control| {
+            AccessControlClient::new(&env, &access_control).break_glass_access(
+                &provider_id,
+                &patient_id,
+                &patient_resource_id(&env, &patient_id, "emergency"),
+                &justification,
+            )
+        });
+
         // Log the emergency access (break-glass audit)
         let access_log = EmergencyAccessLog {
             provider_id: provider_id.clone(),
@@ -168,6 +227,7 @@ impl EmergencyMedicalInfo {
             justification,
             location,
             access_time: env.ledger().timestamp(),
+            break_glass_id,
         };
 
         let log_key = DataKey::EmergencyAccessLog(patient_id.clone());
@@ -456,3 +456,124 @@ fn test_comprehensive_emergency_scenario() {
     let logs = client.get_emergency_access_logs(&patient);
     assert_eq!(logs.len(), 1);
 }
+
+fn setup_with_access_control(
+    env: &Env,
+) -> (
+    EmergencyMedicalInfoClient<'static>,
+    access_control::AccessControlClient<'static>,
+    Address,
+) {
+    let acl_id = env.register(access_control::AccessControl, ());
+    let acl = access_control::AccessControlClient::new(env, &acl_id);
+    let contract_id = env.register(EmergencyMedicalInfo, ());
+    let client = EmergencyMedicalInfoClient::new(env, &contract_id);
+
+    let admin = Address::generate(env);
+    acl.initialize(&admin);
+    client.initialize(&admin, &Some(acl_id));
+
+    (client, acl, admin)
+}
+
+fn set_basic_profile(env: &Env, client: &EmergencyMedicalInfoClient, patient: &Address) {
+    client.set_emergency_profile(
+        patient,
+        &Symbol::new(env, "O_NEG"),
+        &String::from_str(env, "Latex"),
+        &Vec::new(env),
+        &Vec::new(env),
+        &create_test_emergency_contacts(env),
+        &None,
+    );
+}
+
+#[test]
+fn test_emergency_access_opens_break_glass_review() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, acl, admin) = setup_with_access_control(&env);
+
+    let patient = Address::generate(&env);
+    let provider = Address::generate(&env);
+    acl.register_entity(
+        &patient,
+        &access_control::EntityType::Patient,
+        &String::from_str(&env, "Patient"),
+        &String::from_str(&env, "metadata"),
+    );
+    acl.register_entity(
+        &provider,
+        &access_control::EntityType::Doctor,
+        &String::from_str(&env, "Dr. ER"),
+        &String::from_str(&env, "metadata"),
+    );
+    acl.verify_clinician(&admin, &provider);
+    set_basic_profile(&env, &client, &patient);
+
+    client.emergency_access_request(
+        &provider,
+        &patient,
+        &Symbol::new(&env, "TRAUMA"),
+        &String::from_str(&env, "Motor vehicle accident, unresponsive"),
+        &String::from_str(&env, "ER Bay 1"),
+    );
+
+    let logs = client.get_emergency_access_logs(&patient);
+    let break_glass_id = logs.get(0).unwrap().break_glass_id.unwrap();
+
+    // The access now sits in the patient's review queue
+    let record = acl.get_break_glass(&break_glass_id);
+    assert_eq!(record.clinician, provider);
+    assert_eq!(record.patient, patient);
+    assert_eq!(
+        record.status,
+        access_control::BreakGlassStatus::PendingReview
+    );
+
+    acl.approve_break_glass(&patient, &break_glass_id, &None);
+    assert_eq!(acl.get_pending_break_glass().len(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Clinician not registered")]
+fn test_emergency_access_enforces_break_glass_policy() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _, _) = setup_with_access_control(&env);
+
+    let patient = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    set_basic_profile(&env, &client, &patient);
+
+    client.emergency_access_request(
+        &stranger,
+        &patient,
+        &Symbol::new(&env, "TRAUMA"),
+        &String::from_str(&env, "Claimed emergency"),
+        &String::from_str(&env, "Unknown"),
+    );
+}
+
+#[test]
+fn test_emergency_access_without_access_control() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(EmergencyMedicalInfo, ());
+    let client = EmergencyMedicalInfoClient::new(&env, &contract_id);
+
+    let patient = Address::generate(&env);
+    let provider = Address::generate(&env);
+    set_basic_profile(&env, &client, &patient);
+
+    client.emergency_access_request(
+        &provider,
+        &patient,
+        &Symbol::new(&env, "CARDIAC"),
+        &String::from_str(&env, "Cardiac arrest"),
+        &String::from_str(&env, "ER Room 3"),
+    );
+
+    let logs = client.get_emergency_access_logs(&patient);
+    assert_eq!(logs.get(0).unwrap().break_glass_id, None);
+}
@@ -1,4 +1,5 @@
 #![no_std]
+use access_control_interface::{patient_resource_id, AccessControlClient, Action};
 use soroban_sdk::{contract, contractimpl, contracttype, vec, Address, Env, String, Vec};
 
 #[contracttype]
@@ -26,6 +27,8 @@ pub enum DataKey {
     Record(Address, u32),     // (Owner, Index) -> FinancialRecord
     RecordCount(Address),     // Owner -> Number of records
     Access(Address, Address), // (Owner, Authorized) -> bool
+    Admin,
+    AccessControl, // Optional access-control contract consulted for grants
 }
 
 #[contract]
@@ -33,6 +36,46 @@ pub struct FinancialRecordContract;
 
 #[contractimpl]
 impl FinancialRecordContract {
+    /// Initializes the contract with an admin and an optional access-control contract.
+    /// When an access-control contract is configured, it replaces the local grant map.
+    pub fn initialize(e: Env, admin: Address, access_control: Option<Address>) {
+        if e.storage().instance().has(&DataKey::Admin) {
+            panic!("Already initialized");
+        }
+        admin.require_auth();
+        e.storage().instance().set(&DataKey::Admin, &admin);
+        if let Some(access_control) = access_control {
+            e.storage()
+                .instance()
+                .set(&DataKey::AccessControl, &access_control);
+        }
+    }
+
+    /// Sets or clears the access-control contract (admin only).
+    pub fn set_access_control(e: Env, admin: Address, access_control: Option<Address>) {
+        admin.require_auth();
+        let stored_admin: Address = e
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .expect("Not initialized");
+        if admin != stored_admin {
+            panic!("Unauthorized");
+        }
+        match access_control {
+            Some(access_control) => e
+                .storage()
+                .instance()
+                .set(&DataKey::AccessControl, &access_control),
+            None => e.storage().instance().remove(&DataKey::AccessControl),
+        }
+    }
+
+    /// Returns the configured access-control contract, if any.
+    pub fn get_access_control(e: Env) -> Option<Address> {
+        e.storage().instance().get(&DataKey::AccessControl)
+    }
+
     /// Adds a new financial record for the caller.
     pub fn add_financial_record(
         e: Env,
@@ -69,7 +112,9 @@ impl FinancialRecordContract {
     /// Retrieves all financial records for an owner.
     /// Access is allowed if the caller is the owner or has been granted access.
     pub fn get_financial_records(e: Env, caller: Address, owner: Address) -> Vec<FinancialRecord> {
-        Self::check_access(&e, &caller, &owner);
+        if !Self::check_access(&e, &caller, &owner) {
+            return vec![&e];
+        }
 
         let count: u32 = e
             .storage()
@@ -98,7 +143,9 @@ impl FinancialRecordContract {
         start: u64,
         end: u64,
     ) -> Vec<FinancialRecord> {
-        Self::check_access(&e, &caller, &owner);
+        if !Self::check_access(&e, &caller, &owner) {
+            return vec![&e];
+        }
 
         let count: u32 = e
             .storage()
@@ -128,7 +175,9 @@ impl FinancialRecordContract {
         owner: Address,
         record_type: RecordType,
     ) -> Vec<FinancialRecord> {
-        Self::check_access(&e, &caller, &owner);
+        if !Self::check_access(&e, &caller, &owner) {
+            return vec![&e];
+        }
 
         let count: u32 = e
             .storage()
@@ -168,19 +217,39 @@ impl FinancialRecordContract {
     }
 
     /// Internal helper to check access.
-    fn check_access(e: &Env, caller: &Address, owner: &Address) {
+    /// Consults the access-control contract for `patient:<owner>/financial` when configured.
+    /// A denial there returns `false` instead of panicking, so the denial it
+    /// logged is not rolled back; callers answer with an empty list.
+    fn check_access(e: &Env, caller: &Address, owner: &Address) -> bool {
         if caller == owner {
-            return;
+            return true;
         }
+        let access_control: Option<Address> = e.storage().instance().get(&DataKey::AccessControl);
+        if let Some(access_control) = access_control {
+            // Audited reads must be attributable to the caller
+            caller.require_auth();
+            return AccessControlClient::new(e, &access_control).audit_access(
+                caller,
+                &Self::resource_id(e, owner),
+                &Action::Read,
+                &None,
+            );
+        }
+
         let is_authorized: bool = e
             .storage()
             .persistent()
             .get(&DataKey::Access(owner.clone(), caller.clone()))
             .unwrap_or(false);
-
         if !is_authorized {
             panic!("Access denied");
         }
+        true
+    }
+
+    /// Internal helper building the access-control resource id for an owner's records.
+    fn resource_id(e: &Env, owner: &Address) -> String {
+        patient_resource_id(e, owner, "financial")
     }
 }
 
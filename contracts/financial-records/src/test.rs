@@ -143,3 +143,98 @@ fn test_filtering() {
     assert_eq!(range_records.len(), 1);
     assert_eq!(range_records.get(0).unwrap().timestamp, 200);
 }
+
+fn owner_resource(e: &Env, owner: &Address) -> String {
+    let mut id = soroban_sdk::Bytes::from_slice(e, b"patient:");
+    id.append(&owner.to_string().to_bytes());
+    id.append(&soroban_sdk::Bytes::from_slice(e, b"/financial"));
+    String::from(id)
+}
+
+#[test]
+fn test_access_via_access_control_contract() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let acl_id = e.register(access_control::AccessControl, ());
+    let acl = access_control::AccessControlClient::new(&e, &acl_id);
+    let contract_id = e.register(FinancialRecordContract, ());
+    let client = FinancialRecordContractClient::new(&e, &contract_id);
+
+    let admin = Address::generate(&e);
+    let owner = Address::generate(&e);
+    let auditor = Address::generate(&e);
+
+    acl.initialize(&admin);
+    client.initialize(&admin, &Some(acl_id.clone()));
+    assert_eq!(client.get_access_control(), Some(acl_id));
+
+    acl.register_entity(
+        &owner,
+        &access_control::EntityType::Patient,
+        &String::from_str(&e, "Owner"),
+        &String::from_str(&e, "metadata"),
+    );
+    acl.register_entity(
+        &auditor,
+        &access_control::EntityType::Insurer,
+        &String::from_str(&e, "Auditor"),
+        &String::from_str(&e, "metadata"),
+    );
+
+    client.add_financial_record(
+        &owner,
+        &RecordType::Invoice,
+        &String::from_str(&e, "h"),
+        &String::from_str(&e, "d"),
+    );
+
+    let resource = owner_resource(&e, &owner);
+    acl.grant_access(
+        &owner,
+        &auditor,
+        &resource,
+        &soroban_sdk::vec![&e, access_control::Action::Read],
+        &0,
+    );
+
+    let records = client.get_financial_records(&auditor, &owner);
+    assert_eq!(records.len(), 1);
+}
+
+#[test]
+fn test_local_grant_ignored_with_access_control_contract() {
+    let e = Env::default();
+    e.mock_all_auths();
+
+    let acl_id = e.register(access_control::AccessControl, ());
+    let acl = access_control::AccessControlClient::new(&e, &acl_id);
+    let contract_id = e.register(FinancialRecordContract, ());
+    let client = FinancialRecordContractClient::new(&e, &contract_id);
+
+    let admin = Address::generate(&e);
+    let owner = Address::generate(&e);
+    let auditor = Address::generate(&e);
+
+    acl.initialize(&admin);
+    client.initialize(&admin, &Some(acl_id));
+
+    client.add_financial_record(
+        &owner,
+        &RecordType::Invoice,
+        &String::from_str(&e, "h"),
+        &String::from_str(&e, "d"),
+    );
+
+    // Consent lives in the access-control contract, so the local grant has no effect
+    client.grant_access(&owner, &auditor);
+    assert_eq!(client.get_financial_records(&auditor, &owner).len(), 0);
+
+    // The denial is answered with an empty list, so its audit entry persists
+    let denials = acl.get_entity_audit_log(&auditor, &auditor, &0, &10);
+    assert_eq!(denials.len(), 1);
+    assert_eq!(
+        denials.get(0).unwrap().outcome,
+        access_control::AccessOutcome::Denied
+    );
+}
@@ -1,7 +1,10 @@
 #![no_std]
 
+use access_control_interface::{patient_resource_id, AccessControlClient, Action};
+use soroban_sdk::xdr::ToXdr;
 use soroban_sdk::{
-    contract, contractimpl, contracttype, symbol_short, Address, Bytes, Env, Map, String, Vec,
+    contract, contractimpl, contracttype, symbol_short, Address, Bytes, BytesN, Env, Map, String,
+    Vec,
 };
 
 /// --------------------
@@ -25,6 +28,69 @@ pub struct DoctorData {
     pub specialization: String,
     pub certificate_hash: Bytes,
     pub verified: bool,
+    pub verified_by: Option<Address>, // Institution that verified the doctor
+    pub verified_at: u64,
+}
+
+/// --------------------
+/// Institution Structures
+/// --------------------
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InstitutionStatus {
+    Pending,
+    Approved,
+    Revoked,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InstitutionRecord {
+    pub status: InstitutionStatus,
+    pub applied_at: u64,
+    pub reviewed_by: Option<Address>, // Admin who last approved or revoked
+    pub reviewed_at: u64,
+    pub reason: Option<String>, // Given on revocation
+}
+
+/// --------------------
+/// Proxy Structures
+/// --------------------
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ProxyRelationship {
+    Parent,
+    Guardian,
+    Caregiver,
+    PowerOfAttorney,
+}
+
+/// What a proxy may do on the patient's behalf
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ProxyPermission {
+    ManageAccess,  // grant_access / revoke_access
+    UpdateProfile, // update_patient
+    ViewRecords,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProxyTerms {
+    pub relationship: ProxyRelationship,
+    pub permissions: Vec<ProxyPermission>,
+    pub valid_from: u64,
+    pub valid_until: u64,                  // 0 for no expiry
+    pub document_hash: Option<BytesN<32>>, // Court order or signed consent
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProxyGrant {
+    pub proxy: Address,
+    pub appointed_by: Address,
+    pub appointed_at: u64,
+    pub terms: ProxyTerms,
 }
 
 /// --------------------
@@ -34,14 +100,246 @@ pub struct DoctorData {
 pub enum DataKey {
     Patient(Address),
     Doctor(Address),
-    Institution(Address),
-    MedicalRecords(Address),
+    Institution(Address), // Legacy self-registration flag; no longer trusted
+    InstitutionRecord(Address),
+    Record(Address, u64), // (Patient, record id) -> MedicalRecord
+    RecordCount(Address),
+    MedicalRecords(Address), // Legacy: Patient -> Vec<LegacyMedicalRecord>
     AuthorizedDoctors(Address),
+    DoctorCategories(Address, Address), // (Patient, doctor) -> Vec<RecordCategory> scope
+    Reviewer(Address),                  // Reviewer -> appointing institution
+    Proxy(Address, Address),            // (Patient, proxy) -> ProxyGrant
+    Proxies(Address),                   // Patient -> Vec<Address> of proxies
+    ProxyConsent(Address, Address),     // (Patient, proxy) -> true; advance consent to appointment
+    Envelope(Address, Address, EnvelopeScope), // (Patient, doctor, scope) -> KeyEnvelope
+    DoctorEnvelopes(Address, Address),  // (Patient, doctor) -> Vec<EnvelopeScope>
+    Erasure(Address),                   // Patient -> ErasureState while erased
+    RetainedRecord(Address, u64),       // (Patient, record id) -> RetainedRecord tombstone
+    RetentionPeriod,
+    DeregisteredDoctor(Address),
+    FormerVerifier(Address), // Deregistered doctor -> institution that had verified them
+    RecordFrontier(Address), // Patient -> Vec<BytesN<32>> Merkle frontier
+    RecordRoot(Address),     // Patient -> BytesN<32> Merkle root
+    Fingerprint(Address),    // Patient -> BytesN<32> identity fingerprint
+    FingerprintPatients(BytesN<32>), // Fingerprint -> Vec<Address> of patients
+    MergedInto(Address),     // Merged patient -> MergeRecord
+    PendingMerge(Address),   // Old patient -> PendingMerge awaiting its notice period
+    IncomingMerge(Address),  // New patient -> old patient whose records are still moving
+    Admin,
+    AccessControl,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RecordCategory {
+    General,
+    Notes,
+    Imaging,
+    Labs,
+    MentalHealth,
+    ReproductiveHealth,
+}
+
+impl RecordCategory {
+    const ALL: [RecordCategory; 6] = [
+        RecordCategory::General,
+        RecordCategory::Notes,
+        RecordCategory::Imaging,
+        RecordCategory::Labs,
+        RecordCategory::MentalHealth,
+        RecordCategory::ReproductiveHealth,
+    ];
+
+    /// Sensitive categories are hidden from doctors unless explicitly shared
+    pub fn is_sensitive(&self) -> bool {
+        matches!(
+            self,
+            RecordCategory::MentalHealth | RecordCategory::ReproductiveHealth
+        )
+    }
+
+    fn slug(&self) -> &'static [u8] {
+        match self {
+            RecordCategory::General => b"general",
+            RecordCategory::Notes => b"notes",
+            RecordCategory::Imaging => b"imaging",
+            RecordCategory::Labs => b"labs",
+            RecordCategory::MentalHealth => b"mental_health",
+            RecordCategory::ReproductiveHealth => b"reproductive_health",
+        }
+    }
+}
+
+/// Most records returned by one `get_medical_records` call
+const MAX_RECORD_PAGE: u32 = 50;
+
+/// --------------------
+/// Key Envelopes
+/// --------------------
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EnvelopeScope {
+    Record(u64),
+    Category(RecordCategory),
+}
+
+/// A record data key encrypted to one doctor's public key
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeyEnvelope {
+    pub recipient_key: BytesN<32>, // Doctor public key the data key is wrapped to
+    pub wrapped_key: Bytes,
+    pub posted_at: u64,
+}
+
+/// Most envelopes a patient can hold for one doctor
+const MAX_ENVELOPES_PER_DOCTOR: u32 = 100;
+
+/// --------------------
+/// Data Export
+/// --------------------
+/// Bumped whenever the layout of `PatientBundle` changes
+pub const BUNDLE_VERSION: u32 = 2;
+
+/// Most records carried by one exported bundle
+const MAX_EXPORT_RECORDS: u32 = 100;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GrantExport {
+    pub doctor: Address,
+    pub scoped: bool,                    // False for the default non-sensitive scope
+    pub categories: Vec<RecordCategory>, // Set when scoped
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DoctorVerification {
+    pub doctor: Address,
+    pub verified: bool, // Per `is_doctor_verified`
+    pub verified_by: Option<Address>,
+    pub verified_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PatientBundle {
+    pub version: u32,
+    pub patient: Address,
+    pub exported_at: u64,
+    pub profile: PatientData,
+    pub record_start: u64,
+    pub total_records: u64,
+    pub records: Vec<MedicalRecord>,
+    pub grants: Vec<GrantExport>, // Empty when `access_control` is set
+    pub access_control: Option<Address>, // Contract holding the grants that are in force
+    pub proxies: Vec<ProxyGrant>,
+    pub verifications: Vec<DoctorVerification>, // Granted doctors and record authors
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExportedBundle {
+    pub bundle: PatientBundle,
+    pub digest: BytesN<32>,
+}
+
+/// --------------------
+/// Record Commitment
+/// --------------------
+/// Position of a leaf and its sibling at each level, leaf level first
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MerkleProof {
+    pub index: u64,
+    pub siblings: Vec<BytesN<32>>,
+}
+
+/// Depth of each patient's record tree (up to 2^20 record versions)
+pub const MERKLE_DEPTH: u32 = 20;
+
+/// Most patients indexed under one identity fingerprint
+const MAX_FINGERPRINT_MATCHES: u32 = 20;
+
+/// Most records moved per merge call
+const MAX_MERGE_BATCH: u32 = 50;
+
+/// How long an admin-requested merge waits for the old wallet to object
+const MERGE_NOTICE_PERIOD: u64 = 14 * 24 * 60 * 60;
+
+/// A merge requested by the admin for a lost wallet
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingMerge {
+    pub new: Address,
+    pub requested_by: Address,
+    pub evidence_hash: BytesN<32>, // Identity evidence the admin checked
+    pub requested_at: u64,
+    pub executable_at: u64,
+}
+
+/// Where a merged patient's history went, and on whose authority
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MergeRecord {
+    pub into: Address,
+    pub approved_by: Address, // The old wallet itself, or the admin
+    pub evidence_hash: Option<BytesN<32>>,
+    pub merged_at: u64,
+    pub record_offset: u64, // Added to old record ids to give their ids under `into`
+    pub total_records: u64,
+    pub moved: u64, // Records moved so far
+}
+
+/// --------------------
+/// Erasure
+/// --------------------
+/// What survives of a record during the retention period
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RetainedRecord {
+    pub id: u64,
+    pub doctor: Address,
+    pub record_hash: Bytes,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ErasureState {
+    pub requested_by: Address,
+    pub erased_at: u64,
+    pub retain_until: u64,
+    pub total_records: u64,
+    pub tombstoned: u64, // Records reduced to tombstones so far
+    pub purged: u64,     // Tombstones deleted so far
 }
 
+/// Retention used until the admin configures one: ten years
+const DEFAULT_RETENTION_PERIOD: u64 = 10 * 365 * 24 * 60 * 60;
+
+/// Most records tombstoned or purged per call
+const MAX_ERASURE_BATCH: u32 = 50;
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct MedicalRecord {
+    pub id: u64, // Sequential per patient
+    pub doctor: Address,
+    pub record_hash: Bytes,
+    pub description: String,
+    pub category: RecordCategory,
+    pub timestamp: u64,
+    pub supersedes: Option<u64>, // Earlier version this record amends
+    pub amendment_reason: Option<String>,
+    pub superseded_by: Option<u64>, // Set once a newer version replaces this one
+}
+
+/// Record layout held in `DataKey::MedicalRecords` before records were
+/// stored one per key
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LegacyMedicalRecord {
     pub doctor: Address,
     pub record_hash: Bytes,
     pub description: String,
@@ -53,6 +351,54 @@ pub struct MedicalRegistry;
 
 #[contractimpl]
 impl MedicalRegistry {
+    // =====================================================
+    //                   CONFIGURATION
+    // =====================================================
+
+    /// Store the admin and an optional access-control contract. When configured,
+    /// doctor authorization is read from that contract instead of `AuthorizedDoctors`.
+    pub fn initialize(env: Env, admin: Address, access_control: Option<Address>) {
+        if env.storage().instance().has(&DataKey::Admin) {
+            panic!("Already initialized");
+        }
+        admin.require_auth();
+
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        if let Some(access_control) = access_control {
+            env.storage()
+                .instance()
+                .set(&DataKey::AccessControl, &access_control);
+        }
+    }
+
+    pub fn set_access_control(env: Env, admin: Address, access_control: Option<Address>) {
+        admin.require_auth();
+        Self::require_admin(&env, &admin);
+
+        match access_control {
+            Some(access_control) => env
+                .storage()
+                .instance()
+                .set(&DataKey::AccessControl, &access_control),
+            None => env.storage().instance().remove(&DataKey::AccessControl),
+        }
+    }
+
+    pub fn get_access_control(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::AccessControl)
+    }
+
+    fn require_admin(env: &Env, admin: &Address) {
+        let stored_admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .expect("Not initialized");
+        if *admin != stored_admin {
+            panic!("Only admin");
+        }
+    }
+
     // =====================================================
     //                    PATIENT LOGIC
     // =====================================================
@@ -64,6 +410,8 @@ impl MedicalRegistry {
         if env.storage().persistent().has(&key) {
             panic!("Patient already registered");
         }
+        Self::require_not_erased(&env, &wallet);
+        Self::require_not_merged(&env, &wallet);
 
         let patient = PatientData {
             name,
@@ -78,7 +426,15 @@ impl MedicalRegistry {
 
     pub fn update_patient(env: Env, wallet: Address, metadata: String) {
         wallet.require_auth();
+        Self::set_patient_metadata(&env, wallet, metadata);
+    }
+
+    pub fn update_patient_on_behalf(env: Env, proxy: Address, patient: Address, metadata: String) {
+        Self::require_proxy(&env, &proxy, &patient, ProxyPermission::UpdateProfile);
+        Self::set_patient_metadata(&env, patient, metadata);
+    }
 
+    fn set_patient_metadata(env: &Env, wallet: Address, metadata: String) {
         let key = DataKey::Patient(wallet.clone());
         let mut patient: PatientData = env
             .storage()
@@ -123,12 +479,23 @@ impl MedicalRegistry {
         if env.storage().persistent().has(&key) {
             panic!("Doctor already registered");
         }
+        // Patients' grants to a deregistered wallet stay void, so it cannot
+        // come back as a doctor
+        if env
+            .storage()
+            .persistent()
+            .has(&DataKey::DeregisteredDoctor(wallet.clone()))
+        {
+            panic!("Doctor deregistered");
+        }
 
         let doctor = DoctorData {
             name,
             specialization,
             certificate_hash,
             verified: false,
+            verified_by: None,
+            verified_at: 0,
         };
 
         env.storage().persistent().set(&key, &doctor);
@@ -139,11 +506,7 @@ impl MedicalRegistry {
 
     pub fn verify_doctor(env: Env, wallet: Address, institution_wallet: Address) {
         institution_wallet.require_auth();
-
-        let inst_key = DataKey::Institution(institution_wallet);
-        if !env.storage().persistent().has(&inst_key) {
-            panic!("Unauthorized institution");
-        }
+        Self::require_approved_institution(&env, &institution_wallet);
 
         let doc_key = DataKey::Doctor(wallet.clone());
         let mut doctor: DoctorData = env
@@ -153,12 +516,49 @@ impl MedicalRegistry {
             .expect("Doctor not found");
 
         doctor.verified = true;
+        doctor.verified_by = Some(institution_wallet.clone());
+        doctor.verified_at = env.ledger().timestamp();
         env.storage().persistent().set(&doc_key, &doctor);
 
-        env.events().publish(
-            (symbol_short!("ver_doc"), wallet),
-            symbol_short!("verified"),
-        );
+        env.events()
+            .publish((symbol_short!("ver_doc"), wallet), institution_wallet);
+    }
+
+    /// Withdraw a doctor's verification. Allowed for the verifying institution
+    /// and the admin.
+    pub fn unverify_doctor(env: Env, wallet: Address, caller: Address, reason: String) {
+        caller.require_auth();
+
+        let doc_key = DataKey::Doctor(wallet.clone());
+        let mut doctor: DoctorData = env
+            .storage()
+            .persistent()
+            .get(&doc_key)
+            .expect("Doctor not found");
+        if !doctor.verified {
+            panic!("Doctor not verified");
+        }
+        let admin: Option<Address> = env.storage().instance().get(&DataKey::Admin);
+        if doctor.verified_by.as_ref() != Some(&caller) && admin.as_ref() != Some(&caller) {
+            panic!("Unauthorized institution");
+        }
+
+        doctor.verified = false;
+        doctor.verified_by = None;
+        doctor.verified_at = 0;
+        env.storage().persistent().set(&doc_key, &doctor);
+
+        env.events()
+            .publish((symbol_short!("unv_doc"), wallet), (caller, reason));
+    }
+
+    /// Verified by an institution that is still approved
+    pub fn is_doctor_verified(env: Env, wallet: Address) -> bool {
+        let doctor: Option<DoctorData> = env.storage().persistent().get(&DataKey::Doctor(wallet));
+        match doctor.and_then(|doctor| doctor.verified_by) {
+            Some(institution) => Self::is_institution_approved(env, institution),
+            None => false,
+        }
     }
 
     pub fn get_doctor(env: Env, wallet: Address) -> DoctorData {
@@ -173,100 +573,1918 @@ impl MedicalRegistry {
     //              INSTITUTION MANAGEMENT
     // =====================================================
 
+    /// Apply to be an institution. The application has no effect until the
+    /// admin approves it.
     pub fn register_institution(env: Env, institution_wallet: Address) {
         institution_wallet.require_auth();
-        let key = DataKey::Institution(institution_wallet);
-        env.storage().persistent().set(&key, &true);
-    }
 
-    // =====================================================
-    //            MEDICAL RECORD ACCESS CONTROL
-    // =====================================================
+        let key = DataKey::InstitutionRecord(institution_wallet.clone());
+        if let Some(record) = env.storage().persistent().get::<_, InstitutionRecord>(&key) {
+            if record.status != InstitutionStatus::Revoked {
+                panic!("Institution already registered");
+            }
+        }
 
-    pub fn grant_access(env: Env, patient: Address, doctor: Address) {
-        patient.require_auth();
+        let record = InstitutionRecord {
+            status: InstitutionStatus::Pending,
+            applied_at: env.ledger().timestamp(),
+            reviewed_by: None,
+            reviewed_at: 0,
+            reason: None,
+        };
+        env.storage().persistent().set(&key, &record);
 
-        let key = DataKey::AuthorizedDoctors(patient.clone());
-        let mut map: Map<Address, bool> = env
+        env.events().publish(
+            (symbol_short!("inst_app"), institution_wallet),
+            symbol_short!("pending"),
+        );
+    }
+
+    pub fn approve_institution(env: Env, admin: Address, institution_wallet: Address) {
+        admin.require_auth();
+        Self::require_admin(&env, &admin);
+
+        let key = DataKey::InstitutionRecord(institution_wallet.clone());
+        let mut record: InstitutionRecord = env
             .storage()
             .persistent()
             .get(&key)
-            .unwrap_or(Map::new(&env));
+            .expect("Institution not found");
+        if record.status != InstitutionStatus::Pending {
+            panic!("Institution not pending");
+        }
 
-        map.set(doctor, true);
-        env.storage().persistent().set(&key, &map);
+        record.status = InstitutionStatus::Approved;
+        record.reviewed_by = Some(admin.clone());
+        record.reviewed_at = env.ledger().timestamp();
+        env.storage().persistent().set(&key, &record);
+
+        env.events()
+            .publish((symbol_short!("inst_ok"), institution_wallet), admin);
     }
 
-    pub fn revoke_access(env: Env, patient: Address, doctor: Address) {
-        patient.require_auth();
+    /// Revoke an approved institution. Doctors it verified stop counting as
+    /// verified (see `is_doctor_verified`) and its reviewers lose their role.
+    pub fn revoke_institution(
+        env: Env,
+        admin: Address,
+        institution_wallet: Address,
+        reason: String,
+    ) {
+        admin.require_auth();
+        Self::require_admin(&env, &admin);
 
-        let key = DataKey::AuthorizedDoctors(patient.clone());
-        let mut map: Map<Address, bool> = env
+        let key = DataKey::InstitutionRecord(institution_wallet.clone());
+        let mut record: InstitutionRecord = env
             .storage()
             .persistent()
             .get(&key)
-            .unwrap_or(Map::new(&env));
+            .expect("Institution not found");
+        if record.status == InstitutionStatus::Revoked {
+            panic!("Institution already revoked");
+        }
 
-        map.remove(doctor);
-        env.storage().persistent().set(&key, &map);
+        record.status = InstitutionStatus::Revoked;
+        record.reviewed_by = Some(admin.clone());
+        record.reviewed_at = env.ledger().timestamp();
+        record.reason = Some(reason.clone());
+        env.storage().persistent().set(&key, &record);
+
+        env.events().publish(
+            (symbol_short!("inst_rev"), institution_wallet),
+            (admin, reason),
+        );
     }
 
-    pub fn get_authorized_doctors(env: Env, patient: Address) -> Vec<Address> {
-        let key = DataKey::AuthorizedDoctors(patient);
-        let map: Map<Address, bool> = env
+    pub fn get_institution(env: Env, institution_wallet: Address) -> InstitutionRecord {
+        env.storage()
+            .persistent()
+            .get(&DataKey::InstitutionRecord(institution_wallet))
+            .expect("Institution not found")
+    }
+
+    pub fn is_institution_approved(env: Env, institution_wallet: Address) -> bool {
+        let record: Option<InstitutionRecord> = env
             .storage()
             .persistent()
-            .get(&key)
-            .unwrap_or(Map::new(&env));
+            .get(&DataKey::InstitutionRecord(institution_wallet));
+        matches!(record, Some(record) if record.status == InstitutionStatus::Approved)
+    }
 
-        map.keys()
+    fn require_approved_institution(env: &Env, institution_wallet: &Address) {
+        if !Self::is_institution_approved(env.clone(), institution_wallet.clone()) {
+            panic!("Unauthorized institution");
+        }
     }
 
-    pub fn add_medical_record(
-        env: Env,
-        patient: Address,
-        doctor: Address,
-        record_hash: Bytes,
-        description: String,
-    ) {
-        doctor.require_auth();
+    /// Appoint a reviewer who may amend any record on the institution's behalf
+    pub fn add_reviewer(env: Env, institution_wallet: Address, reviewer: Address) {
+        institution_wallet.require_auth();
+        Self::require_approved_institution(&env, &institution_wallet);
 
-        // Check access
-        let access_key = DataKey::AuthorizedDoctors(patient.clone());
-        let access_map: Map<Address, bool> = env
-            .storage()
+        env.storage()
             .persistent()
-            .get(&access_key)
-            .unwrap_or(Map::new(&env));
+            .set(&DataKey::Reviewer(reviewer), &institution_wallet);
+    }
 
-        if !access_map.contains_key(doctor.clone()) {
-            panic!("Doctor not authorized");
+    pub fn remove_reviewer(env: Env, institution_wallet: Address, reviewer: Address) {
+        institution_wallet.require_auth();
+
+        let key = DataKey::Reviewer(reviewer);
+        let appointed_by: Address = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .expect("Reviewer not found");
+        if appointed_by != institution_wallet {
+            panic!("Unauthorized institution");
         }
+        env.storage().persistent().remove(&key);
+    }
 
-        let record = MedicalRecord {
-            doctor,
-            record_hash,
-            description,
-            timestamp: env.ledger().timestamp(),
-        };
+    pub fn is_reviewer(env: Env, reviewer: Address) -> bool {
+        let institution: Option<Address> =
+            env.storage().persistent().get(&DataKey::Reviewer(reviewer));
+        match institution {
+            Some(institution) => Self::is_institution_approved(env, institution),
+            None => false,
+        }
+    }
 
-        let records_key = DataKey::MedicalRecords(patient.clone());
-        let mut records: Vec<MedicalRecord> = env
+    /// Reviewer appointed by the still-approved institution that verified `doctor`
+    fn reviews_for(env: &Env, reviewer: &Address, doctor: &Address) -> bool {
+        let institution: Option<Address> = env
             .storage()
             .persistent()
-            .get(&records_key)
-            .unwrap_or(Vec::new(&env));
-
-        records.push_back(record);
-        env.storage().persistent().set(&records_key, &records);
+            .get(&DataKey::Reviewer(reviewer.clone()));
+        match (institution, Self::doctor_verifier(env, doctor)) {
+            (Some(institution), Some(verified_by)) if institution == verified_by => {
+                Self::is_institution_approved(env.clone(), institution)
+            }
+            _ => false,
+        }
     }
 
-    pub fn get_medical_records(env: Env, patient: Address) -> Vec<MedicalRecord> {
-        let key = DataKey::MedicalRecords(patient);
-        env.storage()
+    /// The institution that verified a doctor, kept after the doctor is
+    /// deregistered so their records stay amendable by its reviewers
+    fn doctor_verifier(env: &Env, doctor: &Address) -> Option<Address> {
+        let data: Option<DoctorData> = env
+            .storage()
             .persistent()
-            .get(&key)
-            .unwrap_or(Vec::new(&env))
+            .get(&DataKey::Doctor(doctor.clone()));
+        match data {
+            Some(data) => data.verified_by,
+            None => env
+                .storage()
+                .persistent()
+                .get(&DataKey::FormerVerifier(doctor.clone())),
+        }
+    }
+
+    // =====================================================
+    //                  PROXY ACCOUNTS
+    // =====================================================
+
+    /// Appoint a proxy for a patient. The patient may appoint their own; the
+    /// admin or an approved institution may appoint one for a patient who
+    /// cannot sign, but only on the strength of a court or consent document,
+    /// with the admin countersigning, and only if the patient consented to
+    /// the proxy in advance or the institution already treats the patient.
+    pub fn add_proxy(
+        env: Env,
+        appointed_by: Address,
+        patient: Address,
+        proxy: Address,
+        terms: ProxyTerms,
+    ) {
+        appointed_by.require_auth();
+
+        let admin: Option<Address> = env.storage().instance().get(&DataKey::Admin);
+        let is_institution = Self::is_institution_approved(env.clone(), appointed_by.clone());
+        if appointed_by != patient && admin.as_ref() != Some(&appointed_by) && !is_institution {
+            panic!("Not authorized to appoint proxy");
+        }
+        if proxy == patient {
+            panic!("Patient cannot be their own proxy");
+        }
+        if !env
+            .storage()
+            .persistent()
+            .has(&DataKey::Patient(patient.clone()))
+        {
+            panic!("Patient not registered");
+        }
+        // Without the patient's own signature, the appointment must rest on a
+        // court order or signed consent, and cannot benefit the appointer
+        if appointed_by != patient {
+            if terms.document_hash.is_none() {
+                panic!("Appointment document required");
+            }
+            if proxy == appointed_by {
+                panic!("Cannot appoint yourself as proxy");
+            }
+            let admin = admin.expect("Admin countersignature required");
+            if admin != appointed_by {
+                admin.require_auth();
+            }
+            let consented = env
+                .storage()
+                .persistent()
+                .has(&DataKey::ProxyConsent(patient.clone(), proxy.clone()));
+            if !consented && !Self::treats_patient(&env, &appointed_by, &patient) {
+                panic!("Patient consent or care relationship required");
+            }
+        }
+        if terms.valid_until != 0
+            && (terms.valid_until <= terms.valid_from
+                || terms.valid_until <= env.ledger().timestamp())
+        {
+            panic!("Invalid validity period");
+        }
+
+        let grant = ProxyGrant {
+            proxy: proxy.clone(),
+            appointed_by,
+            appointed_at: env.ledger().timestamp(),
+            terms,
+        };
+        env.storage()
+            .persistent()
+            .set(&DataKey::Proxy(patient.clone(), proxy.clone()), &grant);
+
+        let list_key = DataKey::Proxies(patient.clone());
+        let mut proxies: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&list_key)
+            .unwrap_or(Vec::new(&env));
+        if !proxies.contains(&proxy) {
+            proxies.push_back(proxy.clone());
+            env.storage().persistent().set(&list_key, &proxies);
+        }
+
+        env.events()
+            .publish((symbol_short!("prx_add"), patient), proxy);
+    }
+
+    /// Record, while the patient can still sign, that they agree to `proxy`
+    /// being appointed for them later
+    pub fn consent_to_proxy(env: Env, patient: Address, proxy: Address) {
+        patient.require_auth();
+        env.storage().persistent().set(
+            &DataKey::ProxyConsent(patient.clone(), proxy.clone()),
+            &true,
+        );
+        env.events()
+            .publish((symbol_short!("prx_cons"), patient), proxy);
+    }
+
+    pub fn withdraw_proxy_consent(env: Env, patient: Address, proxy: Address) {
+        patient.require_auth();
+        env.storage()
+            .persistent()
+            .remove(&DataKey::ProxyConsent(patient.clone(), proxy.clone()));
+        env.events()
+            .publish((symbol_short!("prx_wdr"), patient), proxy);
+    }
+
+    /// Whether a doctor the institution verified currently holds one of the
+    /// patient's grants
+    fn treats_patient(env: &Env, institution: &Address, patient: &Address) -> bool {
+        Self::get_authorized_doctors(env.clone(), patient.clone())
+            .iter()
+            .any(|doctor| {
+                Self::doctor_verifier(env, &doctor).as_ref() == Some(institution)
+                    && Self::is_doctor_authorized(env, patient, &doctor, Action::Read)
+            })
+    }
+
+    /// Remove a proxy. The patient can always do this once capable; whoever
+    /// appointed the proxy and the admin can too.
+    pub fn revoke_proxy(env: Env, caller: Address, patient: Address, proxy: Address) {
+        caller.require_auth();
+
+        let key = DataKey::Proxy(patient.clone(), proxy.clone());
+        let grant: ProxyGrant = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .expect("Proxy not found");
+        let admin: Option<Address> = env.storage().instance().get(&DataKey::Admin);
+        if caller != patient && caller != grant.appointed_by && admin.as_ref() != Some(&caller) {
+            panic!("Not authorized to revoke proxy");
+        }
+        env.storage().persistent().remove(&key);
+
+        let list_key = DataKey::Proxies(patient.clone());
+        let mut proxies: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&list_key)
+            .unwrap_or(Vec::new(&env));
+        if let Some(index) = proxies.first_index_of(&proxy) {
+            proxies.remove(index);
+            env.storage().persistent().set(&list_key, &proxies);
+        }
+
+        env.events()
+            .publish((symbol_short!("prx_rev"), patient), (proxy, caller));
+    }
+
+    pub fn get_proxies(env: Env, patient: Address) -> Vec<ProxyGrant> {
+        let proxies: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Proxies(patient.clone()))
+            .unwrap_or(Vec::new(&env));
+
+        let mut grants = Vec::new(&env);
+        for proxy in proxies.iter() {
+            if let Some(grant) = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Proxy(patient.clone(), proxy))
+            {
+                grants.push_back(grant);
+            }
+        }
+        grants
+    }
+
+    /// Whether `proxy` may currently act for `patient` with `permission`
+    pub fn is_active_proxy(
+        env: Env,
+        patient: Address,
+        proxy: Address,
+        permission: ProxyPermission,
+    ) -> bool {
+        let grant: ProxyGrant = match env
+            .storage()
+            .persistent()
+            .get(&DataKey::Proxy(patient, proxy))
+        {
+            Some(grant) => grant,
+            None => return false,
+        };
+        let now = env.ledger().timestamp();
+        now >= grant.terms.valid_from
+            && (grant.terms.valid_until == 0 || now < grant.terms.valid_until)
+            && grant.terms.permissions.contains(&permission)
+    }
+
+    fn require_proxy(env: &Env, proxy: &Address, patient: &Address, permission: ProxyPermission) {
+        proxy.require_auth();
+        if !Self::is_active_proxy(env.clone(), patient.clone(), proxy.clone(), permission) {
+            panic!("Proxy not authorized");
+        }
+        env.events()
+            .publish((symbol_short!("prx_act"), patient.clone()), proxy.clone());
+    }
+
+    // =====================================================
+    //            MEDICAL RECORD ACCESS CONTROL
+    // =====================================================
+
+    pub fn grant_access(env: Env, patient: Address, doctor: Address) {
+        patient.require_auth();
+        Self::set_doctor_access(&env, patient, doctor, true);
+    }
+
+    /// Grant access limited to the listed categories. This is the only way to
+    /// share sensitive categories with a doctor; a later `grant_access`
+    /// replaces the scope with the default of all non-sensitive categories.
+    pub fn grant_scoped_access(
+        env: Env,
+        patient: Address,
+        doctor: Address,
+        categories: Vec<RecordCategory>,
+    ) {
+        patient.require_auth();
+        Self::set_doctor_access(&env, patient.clone(), doctor.clone(), true);
+        env.storage()
+            .persistent()
+            .set(&DataKey::DoctorCategories(patient, doctor), &categories);
+    }
+
+    pub fn get_access_scope(
+        env: Env,
+        patient: Address,
+        doctor: Address,
+    ) -> Option<Vec<RecordCategory>> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::DoctorCategories(patient, doctor))
+    }
+
+    pub fn revoke_access(env: Env, patient: Address, doctor: Address) {
+        patient.require_auth();
+        Self::set_doctor_access(&env, patient, doctor, false);
+    }
+
+    pub fn grant_access_on_behalf(env: Env, proxy: Address, patient: Address, doctor: Address) {
+        Self::require_proxy(&env, &proxy, &patient, ProxyPermission::ManageAccess);
+        Self::set_doctor_access(&env, patient, doctor, true);
+    }
+
+    pub fn revoke_access_on_behalf(env: Env, proxy: Address, patient: Address, doctor: Address) {
+        Self::require_proxy(&env, &proxy, &patient, ProxyPermission::ManageAccess);
+        Self::set_doctor_access(&env, patient, doctor, false);
+    }
+
+    fn set_doctor_access(env: &Env, patient: Address, doctor: Address, authorized: bool) {
+        env.storage()
+            .persistent()
+            .remove(&DataKey::DoctorCategories(patient.clone(), doctor.clone()));
+
+        let key = DataKey::AuthorizedDoctors(patient.clone());
+        let mut map: Map<Address, bool> = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Map::new(env));
+
+        if authorized {
+            Self::require_not_erased(env, &patient);
+            Self::require_not_merged(env, &patient);
+            map.set(doctor, true);
+        } else {
+            Self::purge_key_envelopes(env, &patient, &doctor);
+            map.remove(doctor);
+        }
+        env.storage().persistent().set(&key, &map);
+    }
+
+    pub fn get_authorized_doctors(env: Env, patient: Address) -> Vec<Address> {
+        let key = DataKey::AuthorizedDoctors(patient);
+        let map: Map<Address, bool> = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Map::new(&env));
+
+        map.keys()
+    }
+
+    /// Append a record to the patient's history and return its id. A denial
+    /// logged by the access-control contract returns `None` instead of
+    /// panicking, so the log entry is not rolled back.
+    pub fn add_medical_record(
+        env: Env,
+        patient: Address,
+        doctor: Address,
+        record_hash: Bytes,
+        description: String,
+        category: RecordCategory,
+    ) -> Option<u64> {
+        doctor.require_auth();
+        Self::require_not_erased(&env, &patient);
+        Self::require_not_merged(&env, &patient);
+        Self::migrate_legacy_records(&env, &patient);
+
+        // Check access
+        if !Self::audit_category_access(&env, &patient, &doctor, &category, Action::Write) {
+            if Self::audits_denials(&env) {
+                return None;
+            }
+            panic!("Doctor not authorized");
+        }
+
+        let id = Self::get_record_count(env.clone(), patient.clone());
+        let record = MedicalRecord {
+            id,
+            doctor,
+            record_hash,
+            description,
+            category,
+            timestamp: env.ledger().timestamp(),
+            supersedes: None,
+            amendment_reason: None,
+            superseded_by: None,
+        };
+        Self::push_record(&env, &patient, &record);
+        Some(id)
+    }
+
+    /// Correct a record by appending a new version linked to it. The original
+    /// stays in place, flagged as superseded. Only the latest version can be
+    /// amended, by the doctor who wrote the original or a current reviewer of
+    /// the institution that verified them.
+    pub fn amend_medical_record(
+        env: Env,
+        patient: Address,
+        record_id: u64,
+        author: Address,
+        record_hash: Bytes,
+        description: String,
+        reason: String,
+    ) -> u64 {
+        author.require_auth();
+        Self::require_not_erased(&env, &patient);
+        Self::require_not_merged(&env, &patient);
+        Self::migrate_legacy_records(&env, &patient);
+
+        let mut previous = Self::load_record(&env, &patient, record_id);
+        if previous.superseded_by.is_some() {
+            panic!("Record already superseded");
+        }
+        let original = Self::first_version(&env, &patient, &previous);
+        if !Self::is_active_party(&env, &patient, &author) {
+            panic!("Not authorized to amend record");
+        }
+        if author != original.doctor && !Self::reviews_for(&env, &author, &original.doctor) {
+            panic!("Not authorized to amend record");
+        }
+
+        let new_id = Self::get_record_count(env.clone(), patient.clone());
+        previous.superseded_by = Some(new_id);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Record(patient.clone(), record_id), &previous);
+        Self::push_record(
+            &env,
+            &patient,
+            &MedicalRecord {
+                id: new_id,
+                doctor: author.clone(),
+                record_hash,
+                description,
+                category: previous.category,
+                timestamp: env.ledger().timestamp(),
+                supersedes: Some(record_id),
+                amendment_reason: Some(reason),
+                superseded_by: None,
+            },
+        );
+
+        env.events().publish(
+            (symbol_short!("rec_amd"), patient),
+            (record_id, new_id, author),
+        );
+        new_id
+    }
+
+    /// Every version of the record `record_id` belongs to, oldest first.
+    /// Empty when the access-control contract denies the read.
+    pub fn get_record_versions(
+        env: Env,
+        caller: Address,
+        patient: Address,
+        record_id: u64,
+    ) -> Vec<MedicalRecord> {
+        caller.require_auth();
+        Self::require_not_erased(&env, &patient);
+        Self::migrate_legacy_records(&env, &patient);
+
+        let record = Self::load_record(&env, &patient, record_id);
+        if caller != patient
+            && !Self::audit_category_access(&env, &patient, &caller, &record.category, Action::Read)
+        {
+            if Self::audits_denials(&env) {
+                return Vec::new(&env);
+            }
+            panic!("Not authorized to view records");
+        }
+
+        let mut versions = Vec::new(&env);
+        let mut current = Some(Self::first_version(&env, &patient, &record));
+        while let Some(version) = current {
+            current = version
+                .superseded_by
+                .map(|next| Self::load_record(&env, &patient, next));
+            versions.push_back(version);
+        }
+        versions
+    }
+
+    fn first_version(env: &Env, patient: &Address, record: &MedicalRecord) -> MedicalRecord {
+        let mut first = record.clone();
+        while let Some(previous) = first.supersedes {
+            first = Self::load_record(env, patient, previous);
+        }
+        first
+    }
+
+    fn load_record(env: &Env, patient: &Address, record_id: u64) -> MedicalRecord {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Record(patient.clone(), record_id))
+            .expect("Record not found")
+    }
+
+    fn push_record(env: &Env, patient: &Address, record: &MedicalRecord) {
+        env.storage()
+            .persistent()
+            .set(&DataKey::Record(patient.clone(), record.id), record);
+        env.storage()
+            .persistent()
+            .set(&DataKey::RecordCount(patient.clone()), &(record.id + 1));
+        let leaf = Self::record_leaf(env.clone(), record.clone());
+        Self::append_record_leaf(env, patient, record.id, leaf);
+    }
+
+    /// Move a patient's records out of the single legacy vector into
+    /// per-record keys; returns how many were moved. Anyone may call this,
+    /// and every call that reads or writes the patient's records does it
+    /// first.
+    pub fn migrate_medical_records(env: Env, patient: Address) -> u32 {
+        Self::migrate_legacy_records(&env, &patient)
+    }
+
+    fn migrate_legacy_records(env: &Env, patient: &Address) -> u32 {
+        let legacy_key = DataKey::MedicalRecords(patient.clone());
+        let legacy: Vec<LegacyMedicalRecord> = match env.storage().persistent().get(&legacy_key) {
+            Some(legacy) => legacy,
+            None => return 0,
+        };
+        env.storage().persistent().remove(&legacy_key);
+
+        let start = Self::get_record_count(env.clone(), patient.clone());
+        for (position, record) in legacy.iter().enumerate() {
+            Self::push_record(
+                env,
+                patient,
+                &MedicalRecord {
+                    id: start + position as u64,
+                    doctor: record.doctor,
+                    record_hash: record.record_hash,
+                    description: record.description,
+                    category: RecordCategory::General,
+                    timestamp: record.timestamp,
+                    supersedes: None,
+                    amendment_reason: None,
+                    superseded_by: None,
+                },
+            );
+        }
+        legacy.len()
+    }
+
+    pub fn get_record_count(env: Env, patient: Address) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RecordCount(patient))
+            .unwrap_or(0)
+    }
+
+    /// Resource id for a patient's records in the access-control contract
+    pub fn records_resource_id(env: Env, patient: Address) -> String {
+        patient_resource_id(&env, &patient, "records")
+    }
+
+    /// Resource id for one category of a patient's records, e.g.
+    /// `patient:G.../records/labs`
+    pub fn category_resource_id(env: Env, patient: Address, category: RecordCategory) -> String {
+        let mut id = Self::records_resource_id(env.clone(), patient).to_bytes();
+        id.push_back(b'/');
+        id.append(&Bytes::from_slice(&env, category.slug()));
+        String::from(id)
+    }
+
+    fn is_doctor_authorized(
+        env: &Env,
+        patient: &Address,
+        doctor: &Address,
+        action: Action,
+    ) -> bool {
+        if !Self::is_active_party(env, patient, doctor) {
+            return false;
+        }
+        let access_control: Option<Address> = env.storage().instance().get(&DataKey::AccessControl);
+        if let Some(access_control) = access_control {
+            let resource_id = Self::records_resource_id(env.clone(), patient.clone());
+            return AccessControlClient::new(env, &access_control).check_access_for(
+                doctor,
+                &resource_id,
+                &action,
+            );
+        }
+
+        let access_key = DataKey::AuthorizedDoctors(patient.clone());
+        let access_map: Map<Address, bool> = env
+            .storage()
+            .persistent()
+            .get(&access_key)
+            .unwrap_or(Map::new(env));
+        access_map.contains_key(doctor.clone())
+    }
+
+    /// Whether a doctor may use one category of a patient's records.
+    ///
+    /// Locally, an unscoped grant covers every non-sensitive category and a
+    /// scoped grant covers exactly the categories it lists. With access
+    /// control configured, a grant on the records resource covers the
+    /// non-sensitive categories, and a grant on the category's own resource
+    /// covers that category. A wildcard above the category's resource covers
+    /// it only if it is not sensitive.
+    fn can_access_category(
+        env: &Env,
+        patient: &Address,
+        doctor: &Address,
+        category: &RecordCategory,
+        action: Action,
+    ) -> bool {
+        if !Self::is_active_party(env, patient, doctor) {
+            return false;
+        }
+        let access_control: Option<Address> = env.storage().instance().get(&DataKey::AccessControl);
+        if let Some(access_control) = access_control {
+            if !category.is_sensitive()
+                && Self::is_doctor_authorized(env, patient, doctor, action.clone())
+            {
+                return true;
+            }
+            let resource_id =
+                Self::category_resource_id(env.clone(), patient.clone(), category.clone());
+            let client = AccessControlClient::new(env, &access_control);
+            return if category.is_sensitive() {
+                client.check_exact_access_for(doctor, &resource_id, &action)
+            } else {
+                client.check_access_for(doctor, &resource_id, &action)
+            };
+        }
+
+        if !Self::is_doctor_authorized(env, patient, doctor, action) {
+            return false;
+        }
+        let scope: Option<Vec<RecordCategory>> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::DoctorCategories(patient.clone(), doctor.clone()));
+        match scope {
+            Some(categories) => categories.contains(category),
+            None => !category.is_sensitive(),
+        }
+    }
+
+    /// `can_access_category`, plus one entry in the access-control audit log
+    /// when it is configured. The entry is written against the resource
+    /// whose grant is used: the records resource for a non-sensitive
+    /// category it covers, the category's own resource otherwise.
+    fn audit_category_access(
+        env: &Env,
+        patient: &Address,
+        doctor: &Address,
+        category: &RecordCategory,
+        action: Action,
+    ) -> bool {
+        let access_control: Option<Address> = env.storage().instance().get(&DataKey::AccessControl);
+        let access_control = match access_control {
+            Some(access_control) if Self::is_active_party(env, patient, doctor) => access_control,
+            _ => return Self::can_access_category(env, patient, doctor, category, action),
+        };
+
+        let client = AccessControlClient::new(env, &access_control);
+        if category.is_sensitive() {
+            let resource_id =
+                Self::category_resource_id(env.clone(), patient.clone(), category.clone());
+            return client.audit_exact_access(doctor, &resource_id, &action, &None);
+        }
+        let records = Self::records_resource_id(env.clone(), patient.clone());
+        let resource_id = if client.check_access_for(doctor, &records, &action) {
+            records
+        } else {
+            Self::category_resource_id(env.clone(), patient.clone(), category.clone())
+        };
+        client.audit_access(doctor, &resource_id, &action, &None)
+    }
+
+    /// Whether denials from `audit_category_access` are written to the
+    /// access-control audit log. Callers must then return normally on a
+    /// denial, or the log entry is rolled back with the failed call.
+    fn audits_denials(env: &Env) -> bool {
+        env.storage().instance().has(&DataKey::AccessControl)
+    }
+
+    /// Whether any category is shared with the doctor, be it through a grant
+    /// on all records or on a single category
+    fn can_read_any_category(env: &Env, patient: &Address, doctor: &Address) -> bool {
+        RecordCategory::ALL
+            .iter()
+            .any(|category| Self::can_access_category(env, patient, doctor, category, Action::Read))
+    }
+
+    /// Page through a patient's records by id. The patient sees everything;
+    /// an authorized doctor sees only the categories shared with them. Records
+    /// the caller may not see are skipped, so a page can hold fewer than
+    /// `limit` entries; continue from `start + limit`.
+    pub fn get_medical_records(
+        env: Env,
+        caller: Address,
+        patient: Address,
+        start: u64,
+        limit: u32,
+    ) -> Vec<MedicalRecord> {
+        caller.require_auth();
+        if caller != patient && !Self::can_read_any_category(&env, &patient, &caller) {
+            panic!("Not authorized to view records");
+        }
+        Self::read_records(&env, &caller, &patient, start, limit)
+    }
+
+    pub fn get_medical_records_on_behalf(
+        env: Env,
+        proxy: Address,
+        patient: Address,
+        start: u64,
+        limit: u32,
+    ) -> Vec<MedicalRecord> {
+        Self::require_proxy(&env, &proxy, &patient, ProxyPermission::ViewRecords);
+        Self::read_records(&env, &patient, &patient, start, limit)
+    }
+
+    fn read_records(
+        env: &Env,
+        reader: &Address,
+        patient: &Address,
+        start: u64,
+        limit: u32,
+    ) -> Vec<MedicalRecord> {
+        Self::require_not_erased(env, patient);
+        Self::migrate_legacy_records(env, patient);
+        let limit = if limit > MAX_RECORD_PAGE {
+            MAX_RECORD_PAGE
+        } else {
+            limit
+        };
+        let end = core::cmp::min(
+            start.saturating_add(limit as u64),
+            Self::get_record_count(env.clone(), patient.clone()),
+        );
+
+        // Decide each category once per page, and log one disclosure for
+        // each category that is actually returned
+        let mut visible: Map<RecordCategory, bool> = Map::new(env);
+        let mut disclosed: Vec<RecordCategory> = Vec::new(env);
+        let mut records = Vec::new(env);
+        for id in start..end {
+            let record = Self::load_record(env, patient, id);
+            let allowed = match visible.get(record.category.clone()) {
+                Some(allowed) => allowed,
+                None => {
+                    let allowed = reader == patient
+                        || Self::can_access_category(
+                            env,
+                            patient,
+                            reader,
+                            &record.category,
+                            Action::Read,
+                        );
+                    visible.set(record.category.clone(), allowed);
+                    allowed
+                }
+            };
+            if allowed {
+                if reader != patient && !disclosed.contains(&record.category) {
+                    disclosed.push_back(record.category.clone());
+                }
+                records.push_back(record);
+            }
+        }
+        for category in disclosed.iter() {
+            Self::audit_category_access(env, patient, reader, &category, Action::Read);
+        }
+        records
+    }
+
+    // =====================================================
+    //                   KEY ENVELOPES
+    // =====================================================
+
+    /// Post a record data key wrapped to a doctor's public key. The doctor
+    /// must currently be allowed to read what the envelope covers.
+    pub fn put_key_envelope(
+        env: Env,
+        patient: Address,
+        doctor: Address,
+        scope: EnvelopeScope,
+        recipient_key: BytesN<32>,
+        wrapped_key: Bytes,
+    ) {
+        patient.require_auth();
+        Self::migrate_legacy_records(&env, &patient);
+
+        let category = Self::envelope_category(&env, &patient, &scope);
+        if !Self::can_access_category(&env, &patient, &doctor, &category, Action::Read) {
+            panic!("Doctor not authorized");
+        }
+
+        let index_key = DataKey::DoctorEnvelopes(patient.clone(), doctor.clone());
+        let mut scopes: Vec<EnvelopeScope> = env
+            .storage()
+            .persistent()
+            .get(&index_key)
+            .unwrap_or(Vec::new(&env));
+        if !scopes.contains(&scope) {
+            if scopes.len() >= MAX_ENVELOPES_PER_DOCTOR {
+                panic!("Too many key envelopes");
+            }
+            scopes.push_back(scope.clone());
+            env.storage().persistent().set(&index_key, &scopes);
+        }
+
+        let envelope = KeyEnvelope {
+            recipient_key,
+            wrapped_key,
+            posted_at: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(
+            &DataKey::Envelope(patient.clone(), doctor.clone(), scope),
+            &envelope,
+        );
+
+        env.events()
+            .publish((symbol_short!("key_put"), patient), doctor);
+    }
+
+    /// Fetch the envelope for a record or category. Access is re-checked, so
+    /// an envelope left behind by a revocation elsewhere is not served.
+    /// `None` when there is no envelope or the access-control contract denies
+    /// the read.
+    pub fn get_key_envelope(
+        env: Env,
+        doctor: Address,
+        patient: Address,
+        scope: EnvelopeScope,
+    ) -> Option<KeyEnvelope> {
+        doctor.require_auth();
+
+        let category = Self::envelope_category(&env, &patient, &scope);
+        if !Self::audit_category_access(&env, &patient, &doctor, &category, Action::Read) {
+            if Self::audits_denials(&env) {
+                return None;
+            }
+            panic!("Doctor not authorized");
+        }
+        env.storage()
+            .persistent()
+            .get(&DataKey::Envelope(patient, doctor, scope))
+    }
+
+    pub fn get_envelope_scopes(env: Env, patient: Address, doctor: Address) -> Vec<EnvelopeScope> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::DoctorEnvelopes(patient, doctor))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    /// Drop every envelope held for a doctor. `revoke_access` does this
+    /// automatically; use this when access was revoked in the access-control
+    /// contract instead.
+    pub fn delete_key_envelopes(env: Env, patient: Address, doctor: Address) {
+        patient.require_auth();
+        Self::purge_key_envelopes(&env, &patient, &doctor);
+    }
+
+    fn envelope_category(env: &Env, patient: &Address, scope: &EnvelopeScope) -> RecordCategory {
+        match scope {
+            EnvelopeScope::Record(record_id) => {
+                Self::load_record(env, patient, *record_id).category
+            }
+            EnvelopeScope::Category(category) => category.clone(),
+        }
+    }
+
+    // The doctor may have cached the data keys, so the patient is told to
+    // re-encrypt whatever the envelopes covered
+    fn purge_key_envelopes(env: &Env, patient: &Address, doctor: &Address) {
+        let index_key = DataKey::DoctorEnvelopes(patient.clone(), doctor.clone());
+        let scopes: Vec<EnvelopeScope> = match env.storage().persistent().get(&index_key) {
+            Some(scopes) => scopes,
+            None => return,
+        };
+        for scope in scopes.iter() {
+            env.storage().persistent().remove(&DataKey::Envelope(
+                patient.clone(),
+                doctor.clone(),
+                scope,
+            ));
+        }
+        env.storage().persistent().remove(&index_key);
+
+        env.events().publish(
+            (symbol_short!("key_rot"), patient.clone()),
+            (doctor.clone(), scopes),
+        );
+    }
+
+    // =====================================================
+    //                    DATA EXPORT
+    // =====================================================
+
+    /// Everything held about a patient, for the patient or a proxy with
+    /// `ViewRecords`. Records come in chunks of at most MAX_EXPORT_RECORDS
+    /// starting at `record_start`; each chunk carries its own digest. With
+    /// an access-control contract configured, local grants have no effect
+    /// and are left out; the bundle names the contract to query instead.
+    pub fn export_patient_bundle(
+        env: Env,
+        caller: Address,
+        patient: Address,
+        record_start: u64,
+        record_limit: u32,
+    ) -> ExportedBundle {
+        caller.require_auth();
+        Self::require_not_erased(&env, &patient);
+        Self::migrate_legacy_records(&env, &patient);
+        if caller != patient
+            && !Self::is_active_proxy(
+                env.clone(),
+                patient.clone(),
+                caller.clone(),
+                ProxyPermission::ViewRecords,
+            )
+        {
+            panic!("Not authorized to export");
+        }
+
+        let total_records = Self::get_record_count(env.clone(), patient.clone());
+        let record_limit = core::cmp::min(record_limit, MAX_EXPORT_RECORDS);
+        let record_end = core::cmp::min(
+            record_start.saturating_add(record_limit as u64),
+            total_records,
+        );
+        let mut records = Vec::new(&env);
+        for id in record_start..record_end {
+            records.push_back(Self::load_record(&env, &patient, id));
+        }
+
+        let access_control: Option<Address> = env.storage().instance().get(&DataKey::AccessControl);
+        let mut grants = Vec::new(&env);
+        let mut doctors: Vec<Address> = Vec::new(&env);
+        let local_doctors = match access_control {
+            Some(_) => Vec::new(&env),
+            None => Self::get_authorized_doctors(env.clone(), patient.clone()),
+        };
+        for doctor in local_doctors.iter() {
+            let scope = Self::get_access_scope(env.clone(), patient.clone(), doctor.clone());
+            grants.push_back(GrantExport {
+                doctor: doctor.clone(),
+                scoped: scope.is_some(),
+                categories: scope.unwrap_or(Vec::new(&env)),
+            });
+            doctors.push_back(doctor);
+        }
+        for record in records.iter() {
+            if !doctors.contains(&record.doctor) {
+                doctors.push_back(record.doctor);
+            }
+        }
+
+        let mut verifications = Vec::new(&env);
+        for doctor in doctors.iter() {
+            let data: Option<DoctorData> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Doctor(doctor.clone()));
+            let (verified_by, verified_at) = match data {
+                Some(data) => (data.verified_by, data.verified_at),
+                None => (None, 0),
+            };
+            verifications.push_back(DoctorVerification {
+                verified: Self::is_doctor_verified(env.clone(), doctor.clone()),
+                doctor,
+                verified_by,
+                verified_at,
+            });
+        }
+
+        let bundle = PatientBundle {
+            version: BUNDLE_VERSION,
+            patient: patient.clone(),
+            exported_at: env.ledger().timestamp(),
+            profile: Self::get_patient(env.clone(), patient.clone()),
+            record_start,
+            total_records,
+            records,
+            grants,
+            access_control,
+            proxies: Self::get_proxies(env.clone(), patient),
+            verifications,
+        };
+        ExportedBundle {
+            digest: Self::compute_bundle_digest(env, bundle.clone()),
+            bundle,
+        }
+    }
+
+    /// SHA-256 of the bundle's XDR encoding; recompute to check an exported copy
+    pub fn compute_bundle_digest(env: Env, bundle: PatientBundle) -> BytesN<32> {
+        env.crypto().sha256(&bundle.to_xdr(&env)).to_bytes()
+    }
+
+    // =====================================================
+    //                  RECORD COMMITMENT
+    // =====================================================
+
+    /// Fold a new leaf into the patient's Merkle tree. The tree has a fixed
+    /// depth; only the left-hand frontier is stored, so each insert costs
+    /// O(depth) hashes however long the history grows.
+    fn append_record_leaf(env: &Env, patient: &Address, index: u64, leaf: BytesN<32>) {
+        if index >> MERKLE_DEPTH != 0 {
+            panic!("Record tree full");
+        }
+        let frontier_key = DataKey::RecordFrontier(patient.clone());
+        let mut frontier: Vec<BytesN<32>> = env
+            .storage()
+            .persistent()
+            .get(&frontier_key)
+            .unwrap_or(Vec::new(env));
+        let zeros = Self::zero_hashes(env);
+
+        let mut node = leaf.clone();
+        let mut size = index;
+        for level in 0..MERKLE_DEPTH {
+            if size & 1 == 0 {
+                if level < frontier.len() {
+                    frontier.set(level, node);
+                } else {
+                    frontier.push_back(node);
+                }
+                break;
+            }
+            node = Self::hash_node(env, &frontier.get(level).unwrap(), &node);
+            size >>= 1;
+        }
+
+        let mut root = zeros.get(0).unwrap();
+        let mut size = index + 1;
+        for level in 0..MERKLE_DEPTH {
+            root = if size & 1 == 1 {
+                Self::hash_node(env, &frontier.get(level).unwrap(), &root)
+            } else {
+                Self::hash_node(env, &root, &zeros.get(level).unwrap())
+            };
+            size >>= 1;
+        }
+
+        env.storage().persistent().set(&frontier_key, &frontier);
+        env.storage()
+            .persistent()
+            .set(&DataKey::RecordRoot(patient.clone()), &root);
+        env.events().publish(
+            (symbol_short!("rec_leaf"), patient.clone()),
+            (index, leaf, root),
+        );
+    }
+
+    /// Leaf committed for a record: SHA-256 of `0x00` and the record's XDR
+    /// as first stored, i.e. with `superseded_by` cleared
+    pub fn record_leaf(env: Env, record: MedicalRecord) -> BytesN<32> {
+        let mut record = record;
+        record.superseded_by = None;
+        let mut data = Bytes::from_array(&env, &[0]);
+        data.append(&record.to_xdr(&env));
+        env.crypto().sha256(&data).to_bytes()
+    }
+
+    /// Current Merkle root over every record version the patient has
+    pub fn get_record_root(env: Env, patient: Address) -> Option<BytesN<32>> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RecordRoot(patient))
+    }
+
+    /// Check that `leaf` sits at `proof.index` under the patient's current
+    /// root. `proof.siblings` lists one hash per level, leaf level first.
+    pub fn verify_record_inclusion(
+        env: Env,
+        patient: Address,
+        leaf: BytesN<32>,
+        proof: MerkleProof,
+    ) -> bool {
+        let root = match Self::get_record_root(env.clone(), patient.clone()) {
+            Some(root) => root,
+            None => return false,
+        };
+        if proof.index >= Self::get_record_count(env.clone(), patient)
+            || proof.siblings.len() != MERKLE_DEPTH
+        {
+            return false;
+        }
+
+        let mut node = leaf;
+        let mut index = proof.index;
+        for sibling in proof.siblings.iter() {
+            node = if index & 1 == 0 {
+                Self::hash_node(&env, &node, &sibling)
+            } else {
+                Self::hash_node(&env, &sibling, &node)
+            };
+            index >>= 1;
+        }
+        node == root
+    }
+
+    fn hash_node(env: &Env, left: &BytesN<32>, right: &BytesN<32>) -> BytesN<32> {
+        let mut data = Bytes::from_array(env, &[1]);
+        data.append(&left.clone().into());
+        data.append(&right.clone().into());
+        env.crypto().sha256(&data).to_bytes()
+    }
+
+    /// Root of an empty subtree at each level
+    fn zero_hashes(env: &Env) -> Vec<BytesN<32>> {
+        let mut zeros = Vec::new(env);
+        let mut node = BytesN::from_array(env, &[0; 32]);
+        for _ in 0..MERKLE_DEPTH {
+            zeros.push_back(node.clone());
+            node = Self::hash_node(env, &node, &node);
+        }
+        zeros
+    }
+
+    // =====================================================
+    //                ERASURE / DEREGISTRATION
+    // =====================================================
+
+    /// How long hash-only tombstones of an erased patient's records are kept
+    pub fn set_retention_period(env: Env, admin: Address, seconds: u64) {
+        admin.require_auth();
+        Self::require_admin(&env, &admin);
+        env.storage()
+            .instance()
+            .set(&DataKey::RetentionPeriod, &seconds);
+    }
+
+    pub fn get_retention_period(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::RetentionPeriod)
+            .unwrap_or(DEFAULT_RETENTION_PERIOD)
+    }
+
+    /// Erase a patient at their own request or the admin's. The profile,
+    /// local grants, scopes, key envelopes and proxies are deleted at once.
+    /// Records are reduced to hash-only tombstones, in batches continued by
+    /// `continue_erasure`, and kept for the retention period before
+    /// `purge_retained_records` removes them too.
+    pub fn deregister_patient(env: Env, caller: Address, patient: Address) {
+        caller.require_auth();
+        let admin: Option<Address> = env.storage().instance().get(&DataKey::Admin);
+        if caller != patient && admin.as_ref() != Some(&caller) {
+            panic!("Not authorized to erase patient");
+        }
+        let state_key = DataKey::Erasure(patient.clone());
+        if env.storage().persistent().has(&state_key) {
+            panic!("Patient erased");
+        }
+        if !env
+            .storage()
+            .persistent()
+            .has(&DataKey::Patient(patient.clone()))
+        {
+            panic!("Patient not found");
+        }
+        Self::require_not_merged(&env, &patient);
+        Self::migrate_legacy_records(&env, &patient);
+
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Patient(patient.clone()));
+        Self::unindex_fingerprint(&env, &patient);
+
+        for doctor in Self::get_authorized_doctors(env.clone(), patient.clone()).iter() {
+            env.storage()
+                .persistent()
+                .remove(&DataKey::DoctorCategories(patient.clone(), doctor.clone()));
+            Self::purge_key_envelopes(&env, &patient, &doctor);
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::AuthorizedDoctors(patient.clone()));
+
+        let proxies: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Proxies(patient.clone()))
+            .unwrap_or(Vec::new(&env));
+        for proxy in proxies.iter() {
+            env.storage()
+                .persistent()
+                .remove(&DataKey::Proxy(patient.clone(), proxy));
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Proxies(patient.clone()));
+
+        let now = env.ledger().timestamp();
+        let state = ErasureState {
+            requested_by: caller.clone(),
+            erased_at: now,
+            retain_until: now.saturating_add(Self::get_retention_period(env.clone())),
+            total_records: Self::get_record_count(env.clone(), patient.clone()),
+            tombstoned: 0,
+            purged: 0,
+        };
+        env.storage().persistent().set(&state_key, &state);
+
+        env.events()
+            .publish((symbol_short!("pat_del"), patient.clone()), caller);
+        Self::continue_erasure(env, patient, MAX_ERASURE_BATCH);
+    }
+
+    /// Tombstone up to `limit` more records; returns how many remain. Anyone
+    /// may call this.
+    pub fn continue_erasure(env: Env, patient: Address, limit: u32) -> u64 {
+        let state_key = DataKey::Erasure(patient.clone());
+        let mut state: ErasureState = env
+            .storage()
+            .persistent()
+            .get(&state_key)
+            .expect("Patient not erased");
+
+        let end = core::cmp::min(
+            state
+                .tombstoned
+                .saturating_add(core::cmp::min(limit, MAX_ERASURE_BATCH) as u64),
+            state.total_records,
+        );
+        for id in state.tombstoned..end {
+            let record_key = DataKey::Record(patient.clone(), id);
+            let record: MedicalRecord = env
+                .storage()
+                .persistent()
+                .get(&record_key)
+                .expect("Record not found");
+            env.storage().persistent().remove(&record_key);
+            env.storage().persistent().set(
+                &DataKey::RetainedRecord(patient.clone(), id),
+                &RetainedRecord {
+                    id,
+                    doctor: record.doctor,
+                    record_hash: record.record_hash,
+                    timestamp: record.timestamp,
+                },
+            );
+        }
+        state.tombstoned = end;
+        env.storage().persistent().set(&state_key, &state);
+
+        state.total_records - state.tombstoned
+    }
+
+    /// Delete up to `limit` tombstones once the retention period is over;
+    /// returns how many remain. When none do, the erasure is complete and the
+    /// address may register again. Anyone may call this.
+    pub fn purge_retained_records(env: Env, patient: Address, limit: u32) -> u64 {
+        let state_key = DataKey::Erasure(patient.clone());
+        let mut state: ErasureState = env
+            .storage()
+            .persistent()
+            .get(&state_key)
+            .expect("Patient not erased");
+        if state.tombstoned < state.total_records {
+            panic!("Erasure still in progress");
+        }
+        if env.ledger().timestamp() < state.retain_until {
+            panic!("Retention period not over");
+        }
+
+        let end = core::cmp::min(
+            state
+                .purged
+                .saturating_add(core::cmp::min(limit, MAX_ERASURE_BATCH) as u64),
+            state.total_records,
+        );
+        for id in state.purged..end {
+            env.storage()
+                .persistent()
+                .remove(&DataKey::RetainedRecord(patient.clone(), id));
+        }
+        state.purged = end;
+
+        if state.purged == state.total_records {
+            env.storage().persistent().remove(&state_key);
+            env.storage()
+                .persistent()
+                .remove(&DataKey::RecordCount(patient.clone()));
+            env.storage()
+                .persistent()
+                .remove(&DataKey::RecordFrontier(patient.clone()));
+            env.storage()
+                .persistent()
+                .remove(&DataKey::RecordRoot(patient.clone()));
+            env.events()
+                .publish((symbol_short!("pat_purg"), patient), state.total_records);
+        } else {
+            env.storage().persistent().set(&state_key, &state);
+        }
+        state.total_records - state.purged
+    }
+
+    pub fn get_erasure_state(env: Env, patient: Address) -> Option<ErasureState> {
+        env.storage().persistent().get(&DataKey::Erasure(patient))
+    }
+
+    /// Tombstones held for legal retention; admin only
+    pub fn get_retained_records(
+        env: Env,
+        admin: Address,
+        patient: Address,
+        start: u64,
+        limit: u32,
+    ) -> Vec<RetainedRecord> {
+        admin.require_auth();
+        Self::require_admin(&env, &admin);
+
+        let limit = core::cmp::min(limit, MAX_RECORD_PAGE);
+        let mut retained = Vec::new(&env);
+        for id in start..start.saturating_add(limit as u64) {
+            if let Some(record) = env
+                .storage()
+                .persistent()
+                .get(&DataKey::RetainedRecord(patient.clone(), id))
+            {
+                retained.push_back(record);
+            }
+        }
+        retained
+    }
+
+    /// Remove a doctor's profile and reviewer role. Grants the doctor still
+    /// appears in stop being honoured, and the wallet cannot register as a
+    /// doctor again. The verifying institution is kept, so its reviewers can
+    /// still amend the doctor's records.
+    pub fn deregister_doctor(env: Env, caller: Address, doctor: Address) {
+        caller.require_auth();
+        let admin: Option<Address> = env.storage().instance().get(&DataKey::Admin);
+        if caller != doctor && admin.as_ref() != Some(&caller) {
+            panic!("Not authorized to deregister doctor");
+        }
+        let doctor_key = DataKey::Doctor(doctor.clone());
+        let data: DoctorData = env
+            .storage()
+            .persistent()
+            .get(&doctor_key)
+            .expect("Doctor not found");
+
+        env.storage().persistent().remove(&doctor_key);
+        if let Some(verified_by) = data.verified_by {
+            env.storage()
+                .persistent()
+                .set(&DataKey::FormerVerifier(doctor.clone()), &verified_by);
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Reviewer(doctor.clone()));
+        env.storage()
+            .persistent()
+            .set(&DataKey::DeregisteredDoctor(doctor.clone()), &true);
+
+        env.events()
+            .publish((symbol_short!("doc_del"), doctor), caller);
+    }
+
+    fn is_erased(env: &Env, patient: &Address) -> bool {
+        env.storage()
+            .persistent()
+            .has(&DataKey::Erasure(patient.clone()))
+    }
+
+    /// Grants held by a deregistered doctor, or on an erased patient, are
+    /// never honoured, whichever access-control backend is in use
+    fn is_active_party(env: &Env, patient: &Address, doctor: &Address) -> bool {
+        !Self::is_erased(env, patient)
+            && !env
+                .storage()
+                .persistent()
+                .has(&DataKey::DeregisteredDoctor(doctor.clone()))
+    }
+
+    fn require_not_erased(env: &Env, patient: &Address) {
+        if Self::is_erased(env, patient) {
+            panic!("Patient erased");
+        }
+    }
+
+    // =====================================================
+    //              DUPLICATE DETECTION / MERGE
+    // =====================================================
+
+    /// Store the patient's identity fingerprint: a salted hash of their
+    /// demographic fields, computed off-chain so the fields themselves never
+    /// reach the ledger. Other patients with the same fingerprint are flagged
+    /// as likely duplicates.
+    pub fn set_identity_fingerprint(env: Env, patient: Address, fingerprint: BytesN<32>) {
+        patient.require_auth();
+        if !env
+            .storage()
+            .persistent()
+            .has(&DataKey::Patient(patient.clone()))
+        {
+            panic!("Patient not registered");
+        }
+
+        Self::unindex_fingerprint(&env, &patient);
+        Self::index_fingerprint(&env, &patient, fingerprint);
+    }
+
+    /// Other patients sharing this patient's fingerprint; for the patient or
+    /// the admin
+    pub fn get_possible_duplicates(env: Env, caller: Address, patient: Address) -> Vec<Address> {
+        caller.require_auth();
+        let admin: Option<Address> = env.storage().instance().get(&DataKey::Admin);
+        if caller != patient && admin.as_ref() != Some(&caller) {
+            panic!("Not authorized to view duplicates");
+        }
+
+        let mut duplicates = Vec::new(&env);
+        let fingerprint: Option<BytesN<32>> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Fingerprint(patient.clone()));
+        if let Some(fingerprint) = fingerprint {
+            let patients: Vec<Address> = env
+                .storage()
+                .persistent()
+                .get(&DataKey::FingerprintPatients(fingerprint))
+                .unwrap_or(Vec::new(&env));
+            for other in patients.iter() {
+                if other != patient {
+                    duplicates.push_back(other);
+                }
+            }
+        }
+        duplicates
+    }
+
+    /// Fold `old` into `new`, leaving a forwarding pointer at `old`. Both
+    /// wallets must sign; a patient who has lost the old wallet goes through
+    /// `request_merge` instead.
+    ///
+    /// Records are appended to `new`'s history with ids shifted past its
+    /// own, in batches continued by `continue_merge`; until the last batch
+    /// `new` takes no new records. Local doctor grants, scopes and proxies
+    /// move across at once unless `new` already has its own. Key envelopes
+    /// name the old record ids, so they are purged instead and must be
+    /// re-posted. Grants held in the access-control contract are not moved.
+    pub fn merge_patient(env: Env, old: Address, new: Address) {
+        old.require_auth();
+        new.require_auth();
+        Self::check_mergeable(&env, &old, &new);
+
+        // Signing directly supersedes any merge the admin has queued
+        env.storage()
+            .persistent()
+            .remove(&DataKey::PendingMerge(old.clone()));
+        Self::execute_merge(&env, &old, &new, &old, None);
+    }
+
+    /// Queue a merge for a patient whose old wallet is lost. `new` and the
+    /// admin sign, the admin records a hash of the identity evidence they
+    /// checked, and the two fingerprints must match. The merge can only be
+    /// completed after a notice period during which the old wallet, if it
+    /// is not lost after all, can cancel it.
+    pub fn request_merge(
+        env: Env,
+        admin: Address,
+        old: Address,
+        new: Address,
+        evidence_hash: BytesN<32>,
+    ) {
+        admin.require_auth();
+        new.require_auth();
+        Self::require_admin(&env, &admin);
+        Self::check_mergeable(&env, &old, &new);
+
+        let old_fingerprint: Option<BytesN<32>> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Fingerprint(old.clone()));
+        let new_fingerprint: Option<BytesN<32>> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Fingerprint(new.clone()));
+        if old_fingerprint.is_none() || old_fingerprint != new_fingerprint {
+            panic!("Identity fingerprints do not match");
+        }
+
+        let key = DataKey::PendingMerge(old.clone());
+        if env.storage().persistent().has(&key) {
+            panic!("Merge already pending");
+        }
+        let now = env.ledger().timestamp();
+        let pending = PendingMerge {
+            new: new.clone(),
+            requested_by: admin,
+            evidence_hash: evidence_hash.clone(),
+            requested_at: now,
+            executable_at: now.saturating_add(MERGE_NOTICE_PERIOD),
+        };
+        env.storage().persistent().set(&key, &pending);
+
+        env.events().publish(
+            (symbol_short!("mrg_req"), old),
+            (new, evidence_hash, pending.executable_at),
+        );
+    }
+
+    /// Drop a queued merge; the old wallet or the admin may do this
+    pub fn cancel_merge(env: Env, caller: Address, old: Address) {
+        caller.require_auth();
+        let admin: Option<Address> = env.storage().instance().get(&DataKey::Admin);
+        if caller != old && admin.as_ref() != Some(&caller) {
+            panic!("Not authorized to cancel merge");
+        }
+        let key = DataKey::PendingMerge(old.clone());
+        if !env.storage().persistent().has(&key) {
+            panic!("No pending merge");
+        }
+        env.storage().persistent().remove(&key);
+
+        env.events()
+            .publish((symbol_short!("mrg_can"), old), caller);
+    }
+
+    /// Carry out a queued merge once its notice period is over. Anyone may
+    /// call this.
+    pub fn complete_merge(env: Env, old: Address) {
+        let key = DataKey::PendingMerge(old.clone());
+        let pending: PendingMerge = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .expect("No pending merge");
+        if env.ledger().timestamp() < pending.executable_at {
+            panic!("Merge notice period not over");
+        }
+        Self::check_mergeable(&env, &old, &pending.new);
+
+        env.storage().persistent().remove(&key);
+        Self::execute_merge(
+            &env,
+            &old,
+            &pending.new,
+            &pending.requested_by,
+            Some(pending.evidence_hash),
+        );
+    }
+
+    pub fn get_pending_merge(env: Env, old: Address) -> Option<PendingMerge> {
+        env.storage().persistent().get(&DataKey::PendingMerge(old))
+    }
+
+    fn check_mergeable(env: &Env, old: &Address, new: &Address) {
+        if old == new {
+            panic!("Cannot merge patient into itself");
+        }
+        for patient in [old, new] {
+            if !env
+                .storage()
+                .persistent()
+                .has(&DataKey::Patient(patient.clone()))
+            {
+                panic!("Patient not registered");
+            }
+            Self::require_not_erased(env, patient);
+            Self::require_not_merged(env, patient);
+        }
+    }
+
+    fn execute_merge(
+        env: &Env,
+        old: &Address,
+        new: &Address,
+        approved_by: &Address,
+        evidence_hash: Option<BytesN<32>>,
+    ) {
+        Self::migrate_legacy_records(env, old);
+        Self::migrate_legacy_records(env, new);
+        let old_count = Self::get_record_count(env.clone(), old.clone());
+
+        // Doctor grants and their scopes
+        let new_doctors = Self::get_authorized_doctors(env.clone(), new.clone());
+        let mut grants: Map<Address, bool> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::AuthorizedDoctors(new.clone()))
+            .unwrap_or(Map::new(env));
+        for doctor in Self::get_authorized_doctors(env.clone(), old.clone()).iter() {
+            Self::purge_key_envelopes(env, old, &doctor);
+            let scope_key = DataKey::DoctorCategories(old.clone(), doctor.clone());
+            let scope: Option<Vec<RecordCategory>> = env.storage().persistent().get(&scope_key);
+            env.storage().persistent().remove(&scope_key);
+            if new_doctors.contains(&doctor) {
+                continue;
+            }
+            if let Some(scope) = scope {
+                env.storage().persistent().set(
+                    &DataKey::DoctorCategories(new.clone(), doctor.clone()),
+                    &scope,
+                );
+            }
+            grants.set(doctor, true);
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::AuthorizedDoctors(new.clone()), &grants);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::AuthorizedDoctors(old.clone()));
+
+        // Proxies
+        let old_proxies: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Proxies(old.clone()))
+            .unwrap_or(Vec::new(env));
+        let mut new_proxies: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Proxies(new.clone()))
+            .unwrap_or(Vec::new(env));
+        for proxy in old_proxies.iter() {
+            let grant_key = DataKey::Proxy(old.clone(), proxy.clone());
+            let grant: Option<ProxyGrant> = env.storage().persistent().get(&grant_key);
+            env.storage().persistent().remove(&grant_key);
+            if proxy == *new || new_proxies.contains(&proxy) {
+                continue;
+            }
+            if let Some(grant) = grant {
+                env.storage()
+                    .persistent()
+                    .set(&DataKey::Proxy(new.clone(), proxy.clone()), &grant);
+                new_proxies.push_back(proxy);
+            }
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::Proxies(new.clone()), &new_proxies);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Proxies(old.clone()));
+
+        // Identity
+        let old_fingerprint: Option<BytesN<32>> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Fingerprint(old.clone()));
+        Self::unindex_fingerprint(env, old);
+        if let Some(fingerprint) = old_fingerprint {
+            if !env
+                .storage()
+                .persistent()
+                .has(&DataKey::Fingerprint(new.clone()))
+            {
+                Self::index_fingerprint(env, new, fingerprint);
+            }
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Patient(old.clone()));
+        env.storage().persistent().set(
+            &DataKey::MergedInto(old.clone()),
+            &MergeRecord {
+                into: new.clone(),
+                approved_by: approved_by.clone(),
+                evidence_hash,
+                merged_at: env.ledger().timestamp(),
+                record_offset: Self::get_record_count(env.clone(), new.clone()),
+                total_records: old_count,
+                moved: 0,
+            },
+        );
+        env.storage()
+            .persistent()
+            .set(&DataKey::IncomingMerge(new.clone()), old);
+
+        env.events().publish(
+            (symbol_short!("pat_merg"), old.clone()),
+            (new.clone(), approved_by.clone(), old_count),
+        );
+        Self::continue_merge(env.clone(), old.clone(), MAX_MERGE_BATCH);
+    }
+
+    /// Move up to `limit` more records of a merged patient; returns how many
+    /// remain. Anyone may call this.
+    pub fn continue_merge(env: Env, old: Address, limit: u32) -> u64 {
+        let merge_key = DataKey::MergedInto(old.clone());
+        let mut merge: MergeRecord = env
+            .storage()
+            .persistent()
+            .get(&merge_key)
+            .expect("Patient not merged");
+        let new = merge.into.clone();
+        let offset = merge.record_offset;
+
+        // Records, keeping amendment links intact
+        let end = core::cmp::min(
+            merge
+                .moved
+                .saturating_add(core::cmp::min(limit, MAX_MERGE_BATCH) as u64),
+            merge.total_records,
+        );
+        for id in merge.moved..end {
+            let record_key = DataKey::Record(old.clone(), id);
+            let mut record: MedicalRecord = env
+                .storage()
+                .persistent()
+                .get(&record_key)
+                .expect("Record not found");
+            record.id += offset;
+            record.supersedes = record.supersedes.map(|previous| previous + offset);
+            record.superseded_by = record.superseded_by.map(|next| next + offset);
+            Self::push_record(&env, &new, &record);
+            env.storage().persistent().remove(&record_key);
+        }
+        merge.moved = end;
+        env.storage().persistent().set(&merge_key, &merge);
+
+        if merge.moved == merge.total_records
+            && env
+                .storage()
+                .persistent()
+                .has(&DataKey::IncomingMerge(new.clone()))
+        {
+            for key in [
+                DataKey::RecordCount(old.clone()),
+                DataKey::RecordFrontier(old.clone()),
+                DataKey::RecordRoot(old.clone()),
+                DataKey::IncomingMerge(new.clone()),
+            ] {
+                env.storage().persistent().remove(&key);
+            }
+            env.events().publish((symbol_short!("mrg_done"), old), new);
+        }
+        merge.total_records - merge.moved
+    }
+
+    /// Where a merged patient's history now lives
+    pub fn get_forwarding_address(env: Env, patient: Address) -> Option<Address> {
+        Self::get_merge_record(env, patient).map(|record| record.into)
+    }
+
+    pub fn get_merge_record(env: Env, patient: Address) -> Option<MergeRecord> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::MergedInto(patient))
+    }
+
+    fn index_fingerprint(env: &Env, patient: &Address, fingerprint: BytesN<32>) {
+        let index_key = DataKey::FingerprintPatients(fingerprint.clone());
+        let mut patients: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&index_key)
+            .unwrap_or(Vec::new(env));
+        if patients.len() >= MAX_FINGERPRINT_MATCHES {
+            panic!("Too many patients share this fingerprint");
+        }
+        let others = patients.clone();
+        patients.push_back(patient.clone());
+        env.storage().persistent().set(&index_key, &patients);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Fingerprint(patient.clone()), &fingerprint);
+
+        if !others.is_empty() {
+            env.events()
+                .publish((symbol_short!("dup_flag"), patient.clone()), others);
+        }
+    }
+
+    fn unindex_fingerprint(env: &Env, patient: &Address) {
+        let key = DataKey::Fingerprint(patient.clone());
+        let fingerprint: BytesN<32> = match env.storage().persistent().get(&key) {
+            Some(fingerprint) => fingerprint,
+            None => return,
+        };
+        env.storage().persistent().remove(&key);
+
+        let index_key = DataKey::FingerprintPatients(fingerprint);
+        let mut patients: Vec<Address> = env
+            .storage()
+            .persistent()
+            .get(&index_key)
+            .unwrap_or(Vec::new(env));
+        if let Some(position) = patients.first_index_of(patient) {
+            patients.remove(position);
+        }
+        if patients.is_empty() {
+            env.storage().persistent().remove(&index_key);
+        } else {
+            env.storage().persistent().set(&index_key, &patients);
+        }
+    }
+
+    /// Also refuses the receiving side of a merge whose records are still
+    /// moving, since new record ids would collide with the ones reserved
+    /// for them
+    fn require_not_merged(env: &Env, patient: &Address) {
+        if env
+            .storage()
+            .persistent()
+            .has(&DataKey::MergedInto(patient.clone()))
+        {
+            panic!("Patient merged");
+        }
+        if env
+            .storage()
+            .persistent()
+            .has(&DataKey::IncomingMerge(patient.clone()))
+        {
+            panic!("Merge in progress");
+        }
     }
 }
 #[cfg(test)]
@@ -1,7 +1,10 @@
 #![cfg(test)]
 
 use super::*;
-use soroban_sdk::{testutils::Address as _, Address, Bytes, Env, String};
+use soroban_sdk::{
+    testutils::{Address as _, Events, Ledger, MockAuth, MockAuthInvoke},
+    Address, Bytes, BytesN, Env, IntoVal, String, Symbol, Vec,
+};
 
 /// ------------------------------------------------
 /// PATIENT TESTS
@@ -119,14 +122,114 @@ fn test_register_institution_and_verify_doctor() {
     // Register doctor
     client.register_doctor(&doctor_wallet, &name, &specialization, &cert_hash);
 
-    // Register institution
+    // Register and approve institution
+    let admin = Address::generate(&env);
+    client.initialize(&admin, &None);
     client.register_institution(&institution_wallet);
+    client.approve_institution(&admin, &institution_wallet);
 
     // Verify doctor
     client.verify_doctor(&doctor_wallet, &institution_wallet);
 
     let doctor = client.get_doctor(&doctor_wallet);
     assert_eq!(doctor.verified, true);
+    assert_eq!(doctor.verified_by, Some(institution_wallet));
+    assert!(client.is_doctor_verified(&doctor_wallet));
+}
+
+/// Initialize with an admin and return an institution the admin has approved
+fn approved_institution(env: &Env, client: &MedicalRegistryClient) -> Address {
+    let admin = Address::generate(env);
+    client.initialize(&admin, &None);
+
+    let institution = Address::generate(env);
+    client.register_institution(&institution);
+    client.approve_institution(&admin, &institution);
+    institution
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized institution")]
+fn test_pending_institution_cannot_verify_doctor() {
+    let env = Env::default();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+    env.mock_all_auths();
+
+    let doctor_wallet = Address::generate(&env);
+    let institution_wallet = Address::generate(&env);
+    let cert_hash = Bytes::from_array(&env, &[9]);
+    let name = String::from_str(&env, "Dr. Mallory");
+    client.register_doctor(&doctor_wallet, &name, &name, &cert_hash);
+
+    // Self-registration alone grants nothing
+    client.register_institution(&institution_wallet);
+    client.verify_doctor(&doctor_wallet, &institution_wallet);
+}
+
+#[test]
+#[should_panic(expected = "Only admin")]
+fn test_non_admin_cannot_approve_institution() {
+    let env = Env::default();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+    env.mock_all_auths();
+
+    client.initialize(&Address::generate(&env), &None);
+    let institution_wallet = Address::generate(&env);
+    client.register_institution(&institution_wallet);
+    client.approve_institution(&institution_wallet, &institution_wallet);
+}
+
+#[test]
+fn test_revoked_institution_and_unverify_doctor() {
+    let env = Env::default();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin, &None);
+    let institution = Address::generate(&env);
+    client.register_institution(&institution);
+    client.approve_institution(&admin, &institution);
+
+    let doctor_wallet = Address::generate(&env);
+    let name = String::from_str(&env, "Dr. Carol");
+    client.register_doctor(&doctor_wallet, &name, &name, &Bytes::from_array(&env, &[1]));
+    client.verify_doctor(&doctor_wallet, &institution);
+
+    // Revoking the institution invalidates its verifications
+    let reason = String::from_str(&env, "License expired");
+    client.revoke_institution(&admin, &institution, &reason);
+    let record = client.get_institution(&institution);
+    assert_eq!(record.status, InstitutionStatus::Revoked);
+    assert_eq!(record.reason, Some(reason.clone()));
+    assert!(!client.is_doctor_verified(&doctor_wallet));
+
+    // The admin can clear the stale verification
+    client.unverify_doctor(&doctor_wallet, &admin, &reason);
+    let doctor = client.get_doctor(&doctor_wallet);
+    assert!(!doctor.verified);
+    assert_eq!(doctor.verified_by, None);
+}
+
+#[test]
+#[should_panic(expected = "Unauthorized institution")]
+fn test_other_institution_cannot_unverify_doctor() {
+    let env = Env::default();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+    env.mock_all_auths();
+
+    let institution = approved_institution(&env, &client);
+    let doctor_wallet = Address::generate(&env);
+    let name = String::from_str(&env, "Dr. Dan");
+    client.register_doctor(&doctor_wallet, &name, &name, &Bytes::from_array(&env, &[1]));
+    client.verify_doctor(&doctor_wallet, &institution);
+
+    let other = Address::generate(&env);
+    client.unverify_doctor(&doctor_wallet, &other, &name);
 }
 
 #[test]
@@ -166,9 +269,9 @@ fn test_grant_access_and_add_medical_record() {
     env.mock_all_auths();
 
     client.grant_access(&patient, &doctor);
-    client.add_medical_record(&patient, &doctor, &hash, &desc);
+    client.add_medical_record(&patient, &doctor, &hash, &desc, &RecordCategory::Labs);
 
-    let records = client.get_medical_records(&patient);
+    let records = client.get_medical_records(&patient, &patient, &0, &10);
     assert_eq!(records.len(), 1);
 
     let record = records.get(0).unwrap();
@@ -191,7 +294,7 @@ fn test_unauthorized_doctor_cannot_add_record() {
 
     env.mock_all_auths();
 
-    client.add_medical_record(&patient, &doctor, &hash, &desc);
+    client.add_medical_record(&patient, &doctor, &hash, &desc, &RecordCategory::Labs);
 }
 
 #[test]
@@ -211,3 +314,1783 @@ fn test_revoke_access() {
     let doctors = client.get_authorized_doctors(&patient);
     assert_eq!(doctors.len(), 0);
 }
+
+fn setup_with_access_control(
+    env: &Env,
+) -> (
+    MedicalRegistryClient<'static>,
+    access_control::AccessControlClient<'static>,
+    Address,
+) {
+    let acl_id = env.register(access_control::AccessControl, ());
+    let acl = access_control::AccessControlClient::new(env, &acl_id);
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(env, &contract_id);
+
+    let admin = Address::generate(env);
+    acl.initialize(&admin);
+    client.initialize(&admin, &Some(acl_id));
+
+    (client, acl, admin)
+}
+
+/// ------------------------------------------------
+/// ACCESS-CONTROL INTEGRATION TESTS
+/// ------------------------------------------------
+
+#[test]
+fn test_add_medical_record_with_access_control_grant() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, acl, _) = setup_with_access_control(&env);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+
+    acl.register_entity(
+        &patient,
+        &access_control::EntityType::Patient,
+        &String::from_str(&env, "John Doe"),
+        &String::from_str(&env, "metadata"),
+    );
+    acl.register_entity(
+        &doctor,
+        &access_control::EntityType::Doctor,
+        &String::from_str(&env, "Dr. Alice"),
+        &String::from_str(&env, "metadata"),
+    );
+
+    let resource = client.records_resource_id(&patient);
+    acl.grant_access(
+        &patient,
+        &doctor,
+        &resource,
+        &soroban_sdk::vec![&env, access_control::Action::Write],
+        &0,
+    );
+
+    let hash = Bytes::from_array(&env, &[1, 2, 3]);
+    let desc = String::from_str(&env, "Blood test results");
+    client.add_medical_record(&patient, &doctor, &hash, &desc, &RecordCategory::Labs);
+
+    assert_eq!(
+        client
+            .get_medical_records(&patient, &patient, &0, &10)
+            .len(),
+        1
+    );
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to grant this access")]
+fn test_self_registered_entity_cannot_grant_patient_records() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, acl, _) = setup_with_access_control(&env);
+
+    let patient = Address::generate(&env);
+    let attacker = Address::generate(&env);
+    let accomplice = Address::generate(&env);
+    for (wallet, entity_type) in [
+        (&patient, access_control::EntityType::Patient),
+        (&attacker, access_control::EntityType::Doctor),
+        (&accomplice, access_control::EntityType::Doctor),
+    ] {
+        acl.register_entity(
+            wallet,
+            &entity_type,
+            &String::from_str(&env, "Entity"),
+            &String::from_str(&env, "metadata"),
+        );
+    }
+
+    // Registering is self-service, so registration alone must not let
+    // anyone hand out the patient's records
+    acl.grant_access(
+        &attacker,
+        &accomplice,
+        &client.records_resource_id(&patient),
+        &soroban_sdk::vec![
+            &env,
+            access_control::Action::Read,
+            access_control::Action::Write
+        ],
+        &0,
+    );
+}
+
+#[test]
+fn test_local_grant_ignored_with_access_control() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _, _) = setup_with_access_control(&env);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+
+    // Consent is managed by the access-control contract once configured
+    client.grant_access(&patient, &doctor);
+
+    let hash = Bytes::from_array(&env, &[1, 2, 3]);
+    let desc = String::from_str(&env, "Blood test results");
+    let id = client.add_medical_record(&patient, &doctor, &hash, &desc, &RecordCategory::Labs);
+    assert_eq!(id, None);
+    assert_eq!(client.get_record_count(&patient), 0);
+}
+
+#[test]
+fn test_denied_access_stays_in_audit_log() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, acl, _) = setup_with_access_control(&env);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    acl.register_entity(
+        &doctor,
+        &access_control::EntityType::Doctor,
+        &String::from_str(&env, "Dr. Mallory"),
+        &String::from_str(&env, "metadata"),
+    );
+
+    // Neither the refused write nor the refused read reverts, so both
+    // denials are still in the log once the calls return
+    let hash = Bytes::from_array(&env, &[1, 2, 3]);
+    let desc = String::from_str(&env, "Forged result");
+    assert_eq!(
+        client.add_medical_record(&patient, &doctor, &hash, &desc, &RecordCategory::Labs),
+        None
+    );
+    let labs = EnvelopeScope::Category(RecordCategory::Labs);
+    assert_eq!(client.get_key_envelope(&doctor, &patient, &labs), None);
+
+    let log = acl.get_entity_audit_log(&doctor, &doctor, &0, &10);
+    assert_eq!(log.len(), 2);
+    for entry in log.iter() {
+        assert_eq!(entry.outcome, access_control::AccessOutcome::Denied);
+    }
+    assert_eq!(log.get(0).unwrap().action, access_control::Action::Write);
+    assert_eq!(
+        log.get(0).unwrap().resource_id,
+        client.category_resource_id(&patient, &RecordCategory::Labs)
+    );
+}
+
+#[test]
+#[should_panic(expected = "Only admin")]
+fn test_set_access_control_non_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _, _) = setup_with_access_control(&env);
+
+    let stranger = Address::generate(&env);
+    client.set_access_control(&stranger, &None);
+}
+
+/// ------------------------------------------------
+/// RECORD AMENDMENT TESTS
+/// ------------------------------------------------
+fn setup_record(env: &Env) -> (MedicalRegistryClient<'static>, Address, Address) {
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(env, &contract_id);
+
+    let patient = Address::generate(env);
+    let doctor = Address::generate(env);
+
+    client.grant_access(&patient, &doctor);
+    client.add_medical_record(
+        &patient,
+        &doctor,
+        &Bytes::from_array(env, &[1, 2, 3]),
+        &String::from_str(env, "Potassium 3.1 mmol/L"),
+        &RecordCategory::Labs,
+    );
+
+    (client, patient, doctor)
+}
+
+#[test]
+fn test_amend_medical_record_keeps_original() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+
+    let reason = String::from_str(&env, "Transcription error");
+    let new_id = client.amend_medical_record(
+        &patient,
+        &0,
+        &doctor,
+        &Bytes::from_array(&env, &[4, 5, 6]),
+        &String::from_str(&env, "Potassium 4.1 mmol/L"),
+        &reason,
+    );
+    assert_eq!(new_id, 1);
+
+    let records = client.get_medical_records(&patient, &patient, &0, &10);
+    assert_eq!(records.len(), 2);
+    let original = records.get(0).unwrap();
+    assert_eq!(original.superseded_by, Some(1));
+    assert_eq!(original.record_hash, Bytes::from_array(&env, &[1, 2, 3]));
+
+    let amended = records.get(1).unwrap();
+    assert_eq!(amended.supersedes, Some(0));
+    assert_eq!(amended.amendment_reason, Some(reason));
+    assert_eq!(amended.doctor, doctor);
+    assert_eq!(amended.superseded_by, None);
+}
+
+#[test]
+fn test_get_record_versions() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+
+    // An unrelated record between versions
+    client.add_medical_record(
+        &patient,
+        &doctor,
+        &Bytes::from_array(&env, &[9]),
+        &String::from_str(&env, "Chest X-ray"),
+        &RecordCategory::Imaging,
+    );
+
+    let institution = approved_institution(&env, &client);
+    register_doctor(&env, &client, &doctor);
+    client.verify_doctor(&doctor, &institution);
+    let reviewer = Address::generate(&env);
+    client.add_reviewer(&institution, &reviewer);
+
+    let hash = Bytes::from_array(&env, &[7]);
+    let second = client.amend_medical_record(
+        &patient,
+        &0,
+        &reviewer,
+        &hash,
+        &String::from_str(&env, "v2"),
+        &String::from_str(&env, "Wrong units"),
+    );
+    // The original author can still amend after a reviewer has
+    let third = client.amend_medical_record(
+        &patient,
+        &second,
+        &doctor,
+        &hash,
+        &String::from_str(&env, "v3"),
+        &String::from_str(&env, "Added context"),
+    );
+
+    let versions = client.get_record_versions(&doctor, &patient, &second);
+    assert_eq!(versions.len(), 3);
+    assert_eq!(versions.get(0).unwrap().id, 0);
+    assert_eq!(versions.get(1).unwrap().doctor, reviewer);
+    assert_eq!(versions.get(2).unwrap().id, third);
+    assert_eq!(client.get_record_versions(&doctor, &patient, &1).len(), 1);
+}
+
+#[test]
+fn test_reviewer_amends_after_author_deregistered() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+
+    let institution = approved_institution(&env, &client);
+    register_doctor(&env, &client, &doctor);
+    client.verify_doctor(&doctor, &institution);
+    let reviewer = Address::generate(&env);
+    client.add_reviewer(&institution, &reviewer);
+
+    // The author leaving does not orphan their records
+    client.deregister_doctor(&doctor, &doctor);
+    let amended = client.amend_medical_record(
+        &patient,
+        &0,
+        &reviewer,
+        &Bytes::from_array(&env, &[7]),
+        &String::from_str(&env, "Potassium 3.4 mmol/L"),
+        &String::from_str(&env, "Transcription error"),
+    );
+    assert_eq!(
+        client
+            .get_record_versions(&patient, &patient, &amended)
+            .len(),
+        2
+    );
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to amend record")]
+fn test_other_doctor_cannot_amend() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, _) = setup_record(&env);
+
+    let other_doctor = Address::generate(&env);
+    client.grant_access(&patient, &other_doctor);
+    client.amend_medical_record(
+        &patient,
+        &0,
+        &other_doctor,
+        &Bytes::from_array(&env, &[4]),
+        &String::from_str(&env, "Changed"),
+        &String::from_str(&env, "Disagree"),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to amend record")]
+fn test_reviewer_of_other_institution_cannot_amend() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin, &None);
+    let institution = Address::generate(&env);
+    let other = Address::generate(&env);
+    for wallet in [&institution, &other] {
+        client.register_institution(wallet);
+        client.approve_institution(&admin, wallet);
+    }
+    register_doctor(&env, &client, &doctor);
+    client.verify_doctor(&doctor, &institution);
+
+    let reviewer = Address::generate(&env);
+    client.add_reviewer(&other, &reviewer);
+
+    client.amend_medical_record(
+        &patient,
+        &0,
+        &reviewer,
+        &Bytes::from_array(&env, &[4]),
+        &String::from_str(&env, "Changed"),
+        &String::from_str(&env, "Disagree"),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to amend record")]
+fn test_removed_reviewer_cannot_amend_own_amendment() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+
+    let institution = approved_institution(&env, &client);
+    register_doctor(&env, &client, &doctor);
+    client.verify_doctor(&doctor, &institution);
+    let reviewer = Address::generate(&env);
+    client.add_reviewer(&institution, &reviewer);
+
+    let hash = Bytes::from_array(&env, &[4]);
+    let reason = String::from_str(&env, "Correction");
+    let amended = client.amend_medical_record(&patient, &0, &reviewer, &hash, &reason, &reason);
+
+    // Having written the latest version is no authority on its own
+    client.remove_reviewer(&institution, &reviewer);
+    client.amend_medical_record(&patient, &amended, &reviewer, &hash, &reason, &reason);
+}
+
+#[test]
+#[should_panic(expected = "Record already superseded")]
+fn test_cannot_amend_superseded_version() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+
+    let hash = Bytes::from_array(&env, &[4]);
+    let reason = String::from_str(&env, "Correction");
+    client.amend_medical_record(&patient, &0, &doctor, &hash, &reason, &reason);
+    client.amend_medical_record(&patient, &0, &doctor, &hash, &reason, &reason);
+}
+
+/// ------------------------------------------------
+/// PROXY TESTS
+/// ------------------------------------------------
+fn proxy_terms(env: &Env, permissions: Vec<ProxyPermission>, valid_until: u64) -> ProxyTerms {
+    ProxyTerms {
+        relationship: ProxyRelationship::Guardian,
+        permissions,
+        valid_from: 0,
+        valid_until,
+        document_hash: Some(BytesN::from_array(env, &[5; 32])),
+    }
+}
+
+fn all_proxy_permissions(env: &Env) -> Vec<ProxyPermission> {
+    soroban_sdk::vec![
+        env,
+        ProxyPermission::ManageAccess,
+        ProxyPermission::UpdateProfile,
+        ProxyPermission::ViewRecords
+    ]
+}
+
+#[test]
+fn test_proxy_acts_for_patient() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let patient = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    client.register_patient(
+        &patient,
+        &String::from_str(&env, "Minor"),
+        &1262304000,
+        &String::from_str(&env, "ipfs://initial"),
+    );
+
+    // A court-appointed guardian registered by the institution treating the patient
+    let institution = approved_institution(&env, &client);
+    let paediatrician = Address::generate(&env);
+    register_doctor(&env, &client, &paediatrician);
+    client.verify_doctor(&paediatrician, &institution);
+    client.grant_access(&patient, &paediatrician);
+    client.add_proxy(
+        &institution,
+        &patient,
+        &guardian,
+        &proxy_terms(&env, all_proxy_permissions(&env), 0),
+    );
+    assert!(client.is_active_proxy(&patient, &guardian, &ProxyPermission::ManageAccess));
+
+    client.grant_access_on_behalf(&guardian, &patient, &doctor);
+    assert_eq!(client.get_authorized_doctors(&patient).len(), 2);
+
+    let metadata = String::from_str(&env, "ipfs://guardian-update");
+    client.update_patient_on_behalf(&guardian, &patient, &metadata);
+    assert_eq!(client.get_patient(&patient).metadata, metadata);
+
+    client.add_medical_record(
+        &patient,
+        &doctor,
+        &Bytes::from_array(&env, &[1]),
+        &String::from_str(&env, "Vaccination"),
+        &RecordCategory::General,
+    );
+    assert_eq!(
+        client
+            .get_medical_records_on_behalf(&guardian, &patient, &0, &10)
+            .len(),
+        1
+    );
+
+    let proxies = client.get_proxies(&patient);
+    assert_eq!(proxies.len(), 1);
+    assert_eq!(proxies.get(0).unwrap().appointed_by, institution);
+}
+
+#[test]
+#[should_panic(expected = "Proxy not authorized")]
+fn test_proxy_limited_to_scope() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let patient = Address::generate(&env);
+    let caregiver = Address::generate(&env);
+    register(&env, &client, &patient);
+    client.add_proxy(
+        &patient,
+        &patient,
+        &caregiver,
+        &proxy_terms(
+            &env,
+            soroban_sdk::vec![&env, ProxyPermission::ViewRecords],
+            0,
+        ),
+    );
+
+    client.grant_access_on_behalf(&caregiver, &patient, &Address::generate(&env));
+}
+
+#[test]
+#[should_panic(expected = "Proxy not authorized")]
+fn test_expired_proxy() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let patient = Address::generate(&env);
+    let proxy = Address::generate(&env);
+    register(&env, &client, &patient);
+    client.add_proxy(
+        &patient,
+        &patient,
+        &proxy,
+        &proxy_terms(&env, all_proxy_permissions(&env), 1000),
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 1000);
+    client.get_medical_records_on_behalf(&proxy, &patient, &0, &10);
+}
+
+#[test]
+#[should_panic(expected = "Proxy not authorized")]
+fn test_patient_revokes_proxy() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let patient = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    register(&env, &client, &patient);
+    client.consent_to_proxy(&patient, &guardian);
+    let institution = approved_institution(&env, &client);
+    client.add_proxy(
+        &institution,
+        &patient,
+        &guardian,
+        &proxy_terms(&env, all_proxy_permissions(&env), 0),
+    );
+
+    // The patient has come of age
+    client.revoke_proxy(&patient, &patient, &guardian);
+    assert_eq!(client.get_proxies(&patient).len(), 0);
+    client.get_medical_records_on_behalf(&guardian, &patient, &0, &10);
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to appoint proxy")]
+fn test_stranger_cannot_appoint_proxy() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let patient = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    client.add_proxy(
+        &stranger,
+        &patient,
+        &stranger,
+        &proxy_terms(&env, all_proxy_permissions(&env), 0),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Patient consent or care relationship required")]
+fn test_institution_cannot_appoint_proxy_for_unrelated_patient() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let patient = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    register(&env, &client, &patient);
+    let institution = approved_institution(&env, &client);
+
+    // Consent to a different proxy does not carry over
+    client.consent_to_proxy(&patient, &Address::generate(&env));
+    client.add_proxy(
+        &institution,
+        &patient,
+        &guardian,
+        &proxy_terms(&env, all_proxy_permissions(&env), 0),
+    );
+}
+
+#[test]
+fn test_third_party_proxy_needs_admin_countersignature() {
+    let env = Env::default();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let (patient, guardian, institution) = (
+        Address::generate(&env),
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+    env.mock_all_auths();
+    client.initialize(&admin, &None);
+    client.register_institution(&institution);
+    client.approve_institution(&admin, &institution);
+    register(&env, &client, &patient);
+    client.consent_to_proxy(&patient, &guardian);
+
+    // The institution alone cannot sign the appointment through
+    let terms = proxy_terms(&env, all_proxy_permissions(&env), 0);
+    env.mock_auths(&[MockAuth {
+        address: &institution,
+        invoke: &MockAuthInvoke {
+            contract: &contract_id,
+            fn_name: "add_proxy",
+            args: (&institution, &patient, &guardian, &terms).into_val(&env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert!(client
+        .try_add_proxy(&institution, &patient, &guardian, &terms)
+        .is_err());
+
+    env.mock_all_auths();
+    client.add_proxy(&institution, &patient, &guardian, &terms);
+    assert!(env.auths().iter().any(|(address, _)| *address == admin));
+    assert!(client.is_active_proxy(&patient, &guardian, &ProxyPermission::ViewRecords));
+}
+
+#[test]
+#[should_panic(expected = "Appointment document required")]
+fn test_institution_proxy_needs_document() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let patient = Address::generate(&env);
+    register(&env, &client, &patient);
+    let institution = approved_institution(&env, &client);
+    let mut terms = proxy_terms(&env, all_proxy_permissions(&env), 0);
+    terms.document_hash = None;
+    client.add_proxy(&institution, &patient, &Address::generate(&env), &terms);
+}
+
+#[test]
+#[should_panic(expected = "Cannot appoint yourself as proxy")]
+fn test_institution_cannot_appoint_itself() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let patient = Address::generate(&env);
+    register(&env, &client, &patient);
+    let institution = approved_institution(&env, &client);
+    client.add_proxy(
+        &institution,
+        &patient,
+        &institution,
+        &proxy_terms(&env, all_proxy_permissions(&env), 0),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Invalid validity period")]
+fn test_proxy_validity_already_over() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let patient = Address::generate(&env);
+    register(&env, &client, &patient);
+    env.ledger().with_mut(|li| li.timestamp = 5000);
+    client.add_proxy(
+        &patient,
+        &patient,
+        &Address::generate(&env),
+        &proxy_terms(&env, all_proxy_permissions(&env), 4000),
+    );
+}
+
+/// ------------------------------------------------
+/// RECORD CATEGORY TESTS
+/// ------------------------------------------------
+fn add_record(
+    env: &Env,
+    client: &MedicalRegistryClient,
+    patient: &Address,
+    doctor: &Address,
+    category: RecordCategory,
+) -> u64 {
+    client
+        .add_medical_record(
+            patient,
+            doctor,
+            &Bytes::from_array(env, &[1]),
+            &String::from_str(env, "Record"),
+            &category,
+        )
+        .unwrap()
+}
+
+#[test]
+fn test_paginated_records() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    client.grant_access(&patient, &doctor);
+    for _ in 0..5 {
+        add_record(&env, &client, &patient, &doctor, RecordCategory::Notes);
+    }
+    assert_eq!(client.get_record_count(&patient), 5);
+
+    let page = client.get_medical_records(&patient, &patient, &0, &2);
+    assert_eq!(page.len(), 2);
+    assert_eq!(page.get(1).unwrap().id, 1);
+
+    let page = client.get_medical_records(&doctor, &patient, &4, &2);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get(0).unwrap().id, 4);
+    assert_eq!(
+        client.get_medical_records(&patient, &patient, &5, &2).len(),
+        0
+    );
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to view records")]
+fn test_unauthorized_reader_rejected() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, _) = setup_record(&env);
+
+    let stranger = Address::generate(&env);
+    client.get_medical_records(&stranger, &patient, &0, &10);
+}
+
+#[test]
+fn test_sensitive_categories_hidden_unless_shared() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let patient = Address::generate(&env);
+    let psychiatrist = Address::generate(&env);
+    let gp = Address::generate(&env);
+
+    client.grant_scoped_access(
+        &patient,
+        &psychiatrist,
+        &soroban_sdk::vec![&env, RecordCategory::MentalHealth],
+    );
+    client.grant_access(&patient, &gp);
+    add_record(&env, &client, &patient, &gp, RecordCategory::Labs);
+    add_record(
+        &env,
+        &client,
+        &patient,
+        &psychiatrist,
+        RecordCategory::MentalHealth,
+    );
+    add_record(&env, &client, &patient, &gp, RecordCategory::Imaging);
+
+    // The GP's default grant excludes sensitive categories
+    let seen = client.get_medical_records(&gp, &patient, &0, &10);
+    assert_eq!(seen.len(), 2);
+    assert!(seen.iter().all(|r| !r.category.is_sensitive()));
+
+    // The psychiatrist is scoped to mental health only
+    let seen = client.get_medical_records(&psychiatrist, &patient, &0, &10);
+    assert_eq!(seen.len(), 1);
+    assert_eq!(seen.get(0).unwrap().category, RecordCategory::MentalHealth);
+
+    // The patient sees everything
+    assert_eq!(
+        client
+            .get_medical_records(&patient, &patient, &0, &10)
+            .len(),
+        3
+    );
+}
+
+#[test]
+#[should_panic(expected = "Doctor not authorized")]
+fn test_scoped_doctor_cannot_write_other_category() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let patient = Address::generate(&env);
+    let radiologist = Address::generate(&env);
+    client.grant_scoped_access(
+        &patient,
+        &radiologist,
+        &soroban_sdk::vec![&env, RecordCategory::Imaging],
+    );
+
+    add_record(
+        &env,
+        &client,
+        &patient,
+        &radiologist,
+        RecordCategory::ReproductiveHealth,
+    );
+}
+
+#[test]
+fn test_category_grants_with_access_control() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, acl, _) = setup_with_access_control(&env);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    acl.register_entity(
+        &patient,
+        &access_control::EntityType::Patient,
+        &String::from_str(&env, "John Doe"),
+        &String::from_str(&env, "metadata"),
+    );
+    acl.register_entity(
+        &doctor,
+        &access_control::EntityType::Doctor,
+        &String::from_str(&env, "Dr. Alice"),
+        &String::from_str(&env, "metadata"),
+    );
+
+    let read_write = soroban_sdk::vec![
+        &env,
+        access_control::Action::Read,
+        access_control::Action::Write
+    ];
+    acl.grant_access(
+        &patient,
+        &doctor,
+        &client.records_resource_id(&patient),
+        &read_write,
+        &0,
+    );
+    add_record(&env, &client, &patient, &doctor, RecordCategory::Labs);
+
+    // Writing or reading mental health needs a grant on its own resource
+    let mental_health = client.category_resource_id(&patient, &RecordCategory::MentalHealth);
+    acl.grant_access(&patient, &doctor, &mental_health, &read_write, &0);
+    add_record(
+        &env,
+        &client,
+        &patient,
+        &doctor,
+        RecordCategory::MentalHealth,
+    );
+    assert_eq!(
+        client.get_medical_records(&doctor, &patient, &0, &10).len(),
+        2
+    );
+
+    acl.revoke_access(&patient, &doctor, &mental_health);
+    let seen = client.get_medical_records(&doctor, &patient, &0, &10);
+    assert_eq!(seen.len(), 1);
+    assert_eq!(seen.get(0).unwrap().category, RecordCategory::Labs);
+}
+
+#[test]
+fn test_wildcard_grant_excludes_sensitive_categories() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, acl, _) = setup_with_access_control(&env);
+
+    let patient = Address::generate(&env);
+    let (author, doctor) = (Address::generate(&env), Address::generate(&env));
+    for (wallet, entity_type) in [
+        (&patient, access_control::EntityType::Patient),
+        (&author, access_control::EntityType::Doctor),
+        (&doctor, access_control::EntityType::Doctor),
+    ] {
+        acl.register_entity(
+            wallet,
+            &entity_type,
+            &String::from_str(&env, "Name"),
+            &String::from_str(&env, "metadata"),
+        );
+    }
+    let read_write = soroban_sdk::vec![
+        &env,
+        access_control::Action::Read,
+        access_control::Action::Write
+    ];
+    let mental_health = client.category_resource_id(&patient, &RecordCategory::MentalHealth);
+    for resource in [client.records_resource_id(&patient), mental_health.clone()] {
+        acl.grant_access(&patient, &author, &resource, &read_write, &0);
+    }
+    add_record(&env, &client, &patient, &author, RecordCategory::Labs);
+    let therapy = add_record(
+        &env,
+        &client,
+        &patient,
+        &author,
+        RecordCategory::MentalHealth,
+    );
+
+    // Wildcards over everything the patient has do not reach sensitive categories
+    for scope in ["*", "records/*"] {
+        acl.grant_access(
+            &patient,
+            &doctor,
+            &patient_resource_id(&env, &patient, scope),
+            &read_write,
+            &0,
+        );
+    }
+    let seen = client.get_medical_records(&doctor, &patient, &0, &10);
+    assert_eq!(seen.len(), 1);
+    assert_eq!(seen.get(0).unwrap().category, RecordCategory::Labs);
+    assert_eq!(
+        client
+            .get_record_versions(&doctor, &patient, &therapy)
+            .len(),
+        0
+    );
+    let hash = Bytes::from_array(&env, &[1]);
+    let desc = String::from_str(&env, "Session notes");
+    assert_eq!(
+        client.add_medical_record(
+            &patient,
+            &doctor,
+            &hash,
+            &desc,
+            &RecordCategory::MentalHealth
+        ),
+        None
+    );
+
+    // A grant on the category itself does
+    acl.grant_access(&patient, &doctor, &mental_health, &read_write, &0);
+    assert_eq!(
+        client.get_medical_records(&doctor, &patient, &0, &10).len(),
+        2
+    );
+}
+
+#[test]
+fn test_category_only_grant_can_read_records() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, acl, _) = setup_with_access_control(&env);
+
+    let patient = Address::generate(&env);
+    let (author, doctor) = (Address::generate(&env), Address::generate(&env));
+    for (wallet, entity_type) in [
+        (&patient, access_control::EntityType::Patient),
+        (&author, access_control::EntityType::Doctor),
+        (&doctor, access_control::EntityType::Doctor),
+    ] {
+        acl.register_entity(
+            wallet,
+            &entity_type,
+            &String::from_str(&env, "Name"),
+            &String::from_str(&env, "metadata"),
+        );
+    }
+    let read_write = soroban_sdk::vec![
+        &env,
+        access_control::Action::Read,
+        access_control::Action::Write
+    ];
+    acl.grant_access(
+        &patient,
+        &author,
+        &client.records_resource_id(&patient),
+        &read_write,
+        &0,
+    );
+    add_record(&env, &client, &patient, &author, RecordCategory::Notes);
+    add_record(&env, &client, &patient, &author, RecordCategory::Labs);
+
+    // Only the labs category is shared with the second doctor
+    acl.grant_access(
+        &patient,
+        &doctor,
+        &client.category_resource_id(&patient, &RecordCategory::Labs),
+        &soroban_sdk::vec![&env, access_control::Action::Read],
+        &0,
+    );
+    let seen = client.get_medical_records(&doctor, &patient, &0, &10);
+    assert_eq!(seen.len(), 1);
+    assert_eq!(seen.get(0).unwrap().category, RecordCategory::Labs);
+}
+
+#[test]
+fn test_record_page_audits_only_disclosed_categories() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, acl, _) = setup_with_access_control(&env);
+
+    let patient = Address::generate(&env);
+    let (author, doctor) = (Address::generate(&env), Address::generate(&env));
+    for (wallet, entity_type) in [
+        (&patient, access_control::EntityType::Patient),
+        (&author, access_control::EntityType::Doctor),
+        (&doctor, access_control::EntityType::Doctor),
+    ] {
+        acl.register_entity(
+            wallet,
+            &entity_type,
+            &String::from_str(&env, "Name"),
+            &String::from_str(&env, "metadata"),
+        );
+    }
+    let read_write = soroban_sdk::vec![
+        &env,
+        access_control::Action::Read,
+        access_control::Action::Write
+    ];
+    acl.grant_access(
+        &patient,
+        &author,
+        &client.records_resource_id(&patient),
+        &read_write,
+        &0,
+    );
+    acl.grant_access(
+        &patient,
+        &author,
+        &client.category_resource_id(&patient, &RecordCategory::MentalHealth),
+        &read_write,
+        &0,
+    );
+    for category in [
+        RecordCategory::Labs,
+        RecordCategory::Labs,
+        RecordCategory::Notes,
+        RecordCategory::MentalHealth,
+    ] {
+        add_record(&env, &client, &patient, &author, category);
+    }
+
+    acl.grant_access(
+        &patient,
+        &doctor,
+        &client.records_resource_id(&patient),
+        &soroban_sdk::vec![&env, access_control::Action::Read],
+        &0,
+    );
+    assert_eq!(
+        client.get_medical_records(&doctor, &patient, &0, &10).len(),
+        3
+    );
+
+    // One entry per category returned; nothing for the hidden one
+    let log = acl.get_entity_audit_log(&doctor, &doctor, &0, &10);
+    assert_eq!(log.len(), 2);
+    for entry in log.iter() {
+        assert_eq!(entry.outcome, access_control::AccessOutcome::Granted);
+    }
+}
+
+#[test]
+fn test_legacy_record_vector_migrated() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    client.grant_access(&patient, &doctor);
+
+    // Records written in the old single-vector layout
+    env.as_contract(&contract_id, || {
+        let mut legacy = Vec::new(&env);
+        for description in ["Annual physical", "Flu shot"] {
+            legacy.push_back(LegacyMedicalRecord {
+                doctor: doctor.clone(),
+                record_hash: Bytes::from_array(&env, &[1]),
+                description: String::from_str(&env, description),
+                timestamp: 100,
+            });
+        }
+        env.storage()
+            .persistent()
+            .set(&DataKey::MedicalRecords(patient.clone()), &legacy);
+    });
+
+    let records = client.get_medical_records(&patient, &patient, &0, &10);
+    assert_eq!(records.len(), 2);
+    assert_eq!(records.get(1).unwrap().id, 1);
+    assert_eq!(records.get(1).unwrap().category, RecordCategory::General);
+    assert_eq!(
+        records.get(1).unwrap().description,
+        String::from_str(&env, "Flu shot")
+    );
+    assert_eq!(client.migrate_medical_records(&patient), 0);
+
+    // New records continue after the migrated ones
+    assert_eq!(
+        add_record(&env, &client, &patient, &doctor, RecordCategory::Notes),
+        2
+    );
+}
+
+/// ------------------------------------------------
+/// KEY ENVELOPE TESTS
+/// ------------------------------------------------
+#[test]
+fn test_key_envelopes_deleted_on_revoke() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+
+    let doctor_key = BytesN::from_array(&env, &[3; 32]);
+    let wrapped = Bytes::from_array(&env, &[0xAA, 0xBB, 0xCC]);
+    let labs = EnvelopeScope::Category(RecordCategory::Labs);
+    client.put_key_envelope(&patient, &doctor, &labs, &doctor_key, &wrapped);
+    client.put_key_envelope(
+        &patient,
+        &doctor,
+        &EnvelopeScope::Record(0),
+        &doctor_key,
+        &wrapped,
+    );
+
+    let envelope = client.get_key_envelope(&doctor, &patient, &labs).unwrap();
+    assert_eq!(envelope.wrapped_key, wrapped);
+    assert_eq!(envelope.recipient_key, doctor_key);
+    assert_eq!(client.get_envelope_scopes(&patient, &doctor).len(), 2);
+
+    client.revoke_access(&patient, &doctor);
+    assert_eq!(client.get_envelope_scopes(&patient, &doctor).len(), 0);
+
+    // Re-granting does not bring the old envelopes back
+    client.grant_access(&patient, &doctor);
+    assert!(client.get_key_envelope(&doctor, &patient, &labs).is_none());
+}
+
+#[test]
+#[should_panic(expected = "Doctor not authorized")]
+fn test_no_envelope_for_hidden_category() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+
+    // An unscoped grant does not cover mental health
+    client.put_key_envelope(
+        &patient,
+        &doctor,
+        &EnvelopeScope::Category(RecordCategory::MentalHealth),
+        &BytesN::from_array(&env, &[3; 32]),
+        &Bytes::from_array(&env, &[1]),
+    );
+}
+
+#[test]
+fn test_delete_key_envelopes_emits_rotation() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+
+    client.put_key_envelope(
+        &patient,
+        &doctor,
+        &EnvelopeScope::Record(0),
+        &BytesN::from_array(&env, &[3; 32]),
+        &Bytes::from_array(&env, &[1]),
+    );
+    client.delete_key_envelopes(&patient, &doctor);
+
+    let events = env.events().all();
+    let (_, topics, _) = events.last().unwrap();
+    let topic: Symbol = topics.get(0).unwrap().into_val(&env);
+    assert_eq!(topic, symbol_short!("key_rot"));
+    assert_eq!(client.get_envelope_scopes(&patient, &doctor).len(), 0);
+}
+
+/// ------------------------------------------------
+/// DATA EXPORT TESTS
+/// ------------------------------------------------
+#[test]
+fn test_export_patient_bundle() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let patient = Address::generate(&env);
+    client.register_patient(
+        &patient,
+        &String::from_str(&env, "John Doe"),
+        &631152000,
+        &String::from_str(&env, "ipfs://profile"),
+    );
+
+    let institution = approved_institution(&env, &client);
+    let doctor = Address::generate(&env);
+    let name = String::from_str(&env, "Dr. Alice");
+    client.register_doctor(&doctor, &name, &name, &Bytes::from_array(&env, &[1]));
+    client.verify_doctor(&doctor, &institution);
+
+    let psychiatrist = Address::generate(&env);
+    client.grant_access(&patient, &doctor);
+    client.grant_scoped_access(
+        &patient,
+        &psychiatrist,
+        &soroban_sdk::vec![&env, RecordCategory::MentalHealth],
+    );
+    add_record(&env, &client, &patient, &doctor, RecordCategory::Labs);
+    add_record(
+        &env,
+        &client,
+        &patient,
+        &psychiatrist,
+        RecordCategory::MentalHealth,
+    );
+
+    let exported = client.export_patient_bundle(&patient, &patient, &0, &10);
+    let bundle = exported.bundle.clone();
+    assert_eq!(bundle.version, BUNDLE_VERSION);
+    assert_eq!(bundle.profile.name, String::from_str(&env, "John Doe"));
+    assert_eq!(bundle.records.len(), 2);
+    assert_eq!(bundle.total_records, 2);
+    assert_eq!(bundle.grants.len(), 2);
+    assert_eq!(bundle.access_control, None);
+    assert_eq!(bundle.verifications.len(), 2);
+
+    let doctor_verification = bundle
+        .verifications
+        .iter()
+        .find(|v| v.doctor == doctor)
+        .unwrap();
+    assert!(doctor_verification.verified);
+    assert_eq!(doctor_verification.verified_by, Some(institution));
+
+    let psychiatrist_grant = bundle
+        .grants
+        .iter()
+        .find(|g| g.doctor == psychiatrist)
+        .unwrap();
+    assert!(psychiatrist_grant.scoped);
+
+    // The digest is deterministic and detects tampering
+    assert_eq!(client.compute_bundle_digest(&bundle), exported.digest);
+    assert_eq!(
+        client
+            .export_patient_bundle(&patient, &patient, &0, &10)
+            .digest,
+        exported.digest
+    );
+    let mut tampered = bundle;
+    tampered.records.pop_back();
+    assert_ne!(client.compute_bundle_digest(&tampered), exported.digest);
+}
+
+#[test]
+fn test_export_bundle_points_to_access_control() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, acl, _) = setup_with_access_control(&env);
+
+    let patient = Address::generate(&env);
+    let doctor = Address::generate(&env);
+    register(&env, &client, &patient);
+
+    // A local grant is not in force, so it is not exported as one
+    client.grant_access(&patient, &doctor);
+    let bundle = client
+        .export_patient_bundle(&patient, &patient, &0, &10)
+        .bundle;
+    assert_eq!(bundle.grants.len(), 0);
+    assert_eq!(bundle.access_control, Some(acl.address));
+}
+
+#[test]
+fn test_export_bundle_in_chunks() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+    client.register_patient(
+        &patient,
+        &String::from_str(&env, "Jane Doe"),
+        &631152000,
+        &String::from_str(&env, "ipfs://profile"),
+    );
+    add_record(&env, &client, &patient, &doctor, RecordCategory::Notes);
+    add_record(&env, &client, &patient, &doctor, RecordCategory::Notes);
+
+    let first = client.export_patient_bundle(&patient, &patient, &0, &2);
+    let second = client.export_patient_bundle(&patient, &patient, &2, &2);
+    assert_eq!(first.bundle.records.len(), 2);
+    assert_eq!(second.bundle.records.len(), 1);
+    assert_eq!(second.bundle.record_start, 2);
+    assert_eq!(second.bundle.total_records, 3);
+    assert_ne!(first.digest, second.digest);
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to export")]
+fn test_doctor_cannot_export_bundle() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+
+    client.export_patient_bundle(&doctor, &patient, &0, &10);
+}
+
+/// ------------------------------------------------
+/// PATIENT ERASURE TESTS
+/// ------------------------------------------------
+#[test]
+fn test_deregister_patient_erases_personal_data() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+    client.register_patient(
+        &patient,
+        &String::from_str(&env, "Jane Doe"),
+        &631152000,
+        &String::from_str(&env, "blood_type: O+"),
+    );
+    client.put_key_envelope(
+        &patient,
+        &doctor,
+        &EnvelopeScope::Category(RecordCategory::Labs),
+        &BytesN::from_array(&env, &[7; 32]),
+        &Bytes::from_array(&env, &[9, 9]),
+    );
+
+    client.deregister_patient(&patient, &patient);
+
+    assert!(!client.is_patient_registered(&patient));
+    assert_eq!(client.get_authorized_doctors(&patient).len(), 0);
+    assert_eq!(client.get_envelope_scopes(&patient, &doctor).len(), 0);
+    let state = client.get_erasure_state(&patient).unwrap();
+    assert_eq!(state.total_records, 1);
+    assert_eq!(state.tombstoned, 1);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin, &None);
+    let retained = client.get_retained_records(&admin, &patient, &0, &10);
+    assert_eq!(retained.len(), 1);
+    assert_eq!(
+        retained.get(0).unwrap().record_hash,
+        Bytes::from_array(&env, &[1, 2, 3])
+    );
+}
+
+#[test]
+#[should_panic(expected = "Patient erased")]
+fn test_erased_patient_cannot_grant_access() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+    register(&env, &client, &patient);
+
+    client.deregister_patient(&patient, &patient);
+    client.grant_access(&patient, &doctor);
+}
+
+#[test]
+fn test_retained_records_purged_after_retention() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+    register(&env, &client, &patient);
+    let admin = Address::generate(&env);
+    client.initialize(&admin, &None);
+    client.set_retention_period(&admin, &1000);
+    for _ in 0..59 {
+        add_record(&env, &client, &patient, &doctor, RecordCategory::Notes);
+    }
+
+    client.deregister_patient(&admin, &patient);
+    assert_eq!(client.continue_erasure(&patient, &50), 0);
+    assert!(client.try_purge_retained_records(&patient, &50).is_err());
+
+    env.ledger().with_mut(|li| li.timestamp += 1000);
+    assert_eq!(client.purge_retained_records(&patient, &50), 10);
+    assert_eq!(client.purge_retained_records(&patient, &50), 0);
+    assert!(client.get_erasure_state(&patient).is_none());
+    assert_eq!(client.get_record_count(&patient), 0);
+    assert_eq!(
+        client.get_retained_records(&admin, &patient, &0, &50).len(),
+        0
+    );
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to erase patient")]
+fn test_doctor_cannot_erase_patient() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+    register(&env, &client, &patient);
+
+    client.deregister_patient(&doctor, &patient);
+}
+
+#[test]
+#[should_panic(expected = "Doctor not authorized")]
+fn test_deregistered_doctor_loses_access() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+    register_doctor(&env, &client, &doctor);
+
+    client.deregister_doctor(&doctor, &doctor);
+    add_record(&env, &client, &patient, &doctor, RecordCategory::Labs);
+}
+
+fn register_doctor(env: &Env, client: &MedicalRegistryClient, doctor: &Address) {
+    client.register_doctor(
+        doctor,
+        &String::from_str(env, "Dr. Who"),
+        &String::from_str(env, "Nephrology"),
+        &Bytes::from_array(env, &[3]),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Not authorized to amend record")]
+fn test_deregistered_doctor_cannot_amend() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+    register_doctor(&env, &client, &doctor);
+
+    client.deregister_doctor(&doctor, &doctor);
+    client.amend_medical_record(
+        &patient,
+        &0,
+        &doctor,
+        &Bytes::from_array(&env, &[4]),
+        &String::from_str(&env, "Potassium 3.4 mmol/L"),
+        &String::from_str(&env, "Transcription error"),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Doctor deregistered")]
+fn test_deregistered_doctor_cannot_register_again() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _, doctor) = setup_record(&env);
+    register_doctor(&env, &client, &doctor);
+
+    client.deregister_doctor(&doctor, &doctor);
+    register_doctor(&env, &client, &doctor);
+}
+
+#[test]
+#[should_panic(expected = "Patient not found")]
+fn test_cannot_erase_unregistered_patient() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, _) = setup_record(&env);
+
+    client.deregister_patient(&patient, &patient);
+}
+
+#[test]
+#[should_panic(expected = "Doctor not found")]
+fn test_cannot_deregister_unregistered_doctor() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _, doctor) = setup_record(&env);
+
+    client.deregister_doctor(&doctor, &doctor);
+}
+
+/// ------------------------------------------------
+/// RECORD COMMITMENT TESTS
+/// ------------------------------------------------
+fn hash_pair(env: &Env, left: &BytesN<32>, right: &BytesN<32>) -> BytesN<32> {
+    let mut data = Bytes::from_array(env, &[1]);
+    data.append(&left.clone().into());
+    data.append(&right.clone().into());
+    env.crypto().sha256(&data).to_bytes()
+}
+
+/// Build the proof for `index` from the full list of leaves
+fn build_proof(env: &Env, leaves: &Vec<BytesN<32>>, index: u64) -> MerkleProof {
+    let mut level_nodes = leaves.clone();
+    let mut zero = BytesN::from_array(env, &[0; 32]);
+    let mut position = index as u32;
+    let mut siblings = Vec::new(env);
+    for _ in 0..MERKLE_DEPTH {
+        siblings.push_back(level_nodes.get(position ^ 1).unwrap_or(zero.clone()));
+
+        let mut next = Vec::new(env);
+        let mut i = 0;
+        while i < level_nodes.len() {
+            let left = level_nodes.get(i).unwrap();
+            let right = level_nodes.get(i + 1).unwrap_or(zero.clone());
+            next.push_back(hash_pair(env, &left, &right));
+            i += 2;
+        }
+        level_nodes = next;
+        zero = hash_pair(env, &zero, &zero);
+        position >>= 1;
+    }
+    MerkleProof { index, siblings }
+}
+
+#[test]
+fn test_verify_record_inclusion() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+    for _ in 0..4 {
+        add_record(&env, &client, &patient, &doctor, RecordCategory::Notes);
+    }
+
+    let mut leaves = Vec::new(&env);
+    for record in client
+        .get_medical_records(&patient, &patient, &0, &10)
+        .iter()
+    {
+        leaves.push_back(client.record_leaf(&record));
+    }
+    assert_eq!(leaves.len(), 5);
+
+    for index in 0..5u64 {
+        let proof = build_proof(&env, &leaves, index);
+        assert!(client.verify_record_inclusion(
+            &patient,
+            &leaves.get(index as u32).unwrap(),
+            &proof
+        ));
+    }
+
+    // Right leaf, wrong position
+    let proof = build_proof(&env, &leaves, 1);
+    assert!(!client.verify_record_inclusion(&patient, &leaves.get(2).unwrap(), &proof));
+    // Forged leaf
+    let forged = BytesN::from_array(&env, &[5; 32]);
+    assert!(!client.verify_record_inclusion(&patient, &forged, &proof));
+}
+
+#[test]
+fn test_amendment_keeps_earlier_leaves_provable() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, patient, doctor) = setup_record(&env);
+    let original = client
+        .get_medical_records(&patient, &patient, &0, &1)
+        .get(0)
+        .unwrap();
+    let root_before = client.get_record_root(&patient).unwrap();
+
+    client.amend_medical_record(
+        &patient,
+        &0,
+        &doctor,
+        &Bytes::from_array(&env, &[4, 5, 6]),
+        &String::from_str(&env, "Potassium 3.4 mmol/L"),
+        &String::from_str(&env, "Transcription error"),
+    );
+    assert_ne!(client.get_record_root(&patient).unwrap(), root_before);
+
+    // The original now has superseded_by set but commits to the same leaf
+    let records = client.get_medical_records(&patient, &patient, &0, &2);
+    assert_eq!(records.get(0).unwrap().superseded_by, Some(1));
+    let leaf = client.record_leaf(&records.get(0).unwrap());
+    assert_eq!(leaf, client.record_leaf(&original));
+
+    let leaves = Vec::from_array(
+        &env,
+        [leaf.clone(), client.record_leaf(&records.get(1).unwrap())],
+    );
+    assert!(client.verify_record_inclusion(&patient, &leaf, &build_proof(&env, &leaves, 0)));
+}
+
+/// ------------------------------------------------
+/// DUPLICATE DETECTION / MERGE TESTS
+/// ------------------------------------------------
+fn register(env: &Env, client: &MedicalRegistryClient, wallet: &Address) {
+    client.register_patient(
+        wallet,
+        &String::from_str(env, "Jane Doe"),
+        &631152000,
+        &String::from_str(env, "ipfs://profile"),
+    );
+}
+
+#[test]
+fn test_matching_fingerprints_flag_duplicates() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+
+    let (old, new, other) = (
+        Address::generate(&env),
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+    for wallet in [&old, &new, &other] {
+        register(&env, &client, wallet);
+    }
+    client.set_identity_fingerprint(&old, &BytesN::from_array(&env, &[1; 32]));
+    client.set_identity_fingerprint(&other, &BytesN::from_array(&env, &[2; 32]));
+    client.set_identity_fingerprint(&new, &BytesN::from_array(&env, &[1; 32]));
+
+    let (_, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(
+        topics,
+        (symbol_short!("dup_flag"), new.clone()).into_val(&env)
+    );
+    let flagged: Vec<Address> = data.into_val(&env);
+    assert_eq!(flagged, Vec::from_array(&env, [old.clone()]));
+
+    assert_eq!(
+        client.get_possible_duplicates(&new, &new),
+        Vec::from_array(&env, [old.clone()])
+    );
+    assert_eq!(client.get_possible_duplicates(&other, &other).len(), 0);
+}
+
+#[test]
+fn test_merge_patient_moves_history() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, old, doctor) = setup_record(&env);
+    register(&env, &client, &old);
+    client.amend_medical_record(
+        &old,
+        &0,
+        &doctor,
+        &Bytes::from_array(&env, &[4, 5, 6]),
+        &String::from_str(&env, "Potassium 3.4 mmol/L"),
+        &String::from_str(&env, "Transcription error"),
+    );
+
+    let new = Address::generate(&env);
+    let new_doctor = Address::generate(&env);
+    register(&env, &client, &new);
+    client.grant_access(&new, &new_doctor);
+    add_record(&env, &client, &new, &new_doctor, RecordCategory::Notes);
+
+    client.merge_patient(&old, &new);
+
+    assert_eq!(client.get_forwarding_address(&old), Some(new.clone()));
+    assert!(!client.is_patient_registered(&old));
+    assert_eq!(client.get_record_count(&old), 0);
+    assert_eq!(client.get_authorized_doctors(&old).len(), 0);
+
+    assert_eq!(client.get_record_count(&new), 3);
+    assert!(client.get_authorized_doctors(&new).contains(&doctor));
+    let versions = client.get_record_versions(&doctor, &new, &2);
+    assert_eq!(versions.len(), 2);
+    assert_eq!(versions.get(0).unwrap().id, 1);
+    assert_eq!(versions.get(0).unwrap().superseded_by, Some(2));
+    assert_eq!(versions.get(1).unwrap().supersedes, Some(1));
+}
+
+#[test]
+fn test_large_merge_moves_records_in_batches() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, old, doctor) = setup_record(&env);
+    register(&env, &client, &old);
+    for _ in 0..200 {
+        add_record(&env, &client, &old, &doctor, RecordCategory::Labs);
+    }
+    let new = Address::generate(&env);
+    register(&env, &client, &new);
+    client.grant_access(&new, &doctor);
+    add_record(&env, &client, &new, &doctor, RecordCategory::Notes);
+
+    client.merge_patient(&old, &new);
+    let merge = client.get_merge_record(&old).unwrap();
+    assert_eq!((merge.total_records, merge.moved), (201, 50));
+    assert_eq!(client.get_record_count(&new), 51);
+
+    // The ids still to come are reserved, so `new` takes no records meanwhile
+    assert!(client
+        .try_add_medical_record(
+            &new,
+            &doctor,
+            &Bytes::from_array(&env, &[1]),
+            &String::from_str(&env, "Record"),
+            &RecordCategory::Notes,
+        )
+        .is_err());
+
+    // Batches are capped, and anyone may keep the move going
+    assert_eq!(client.continue_merge(&old, &500), 101);
+    while client.continue_merge(&old, &50) > 0 {}
+    assert_eq!(client.continue_merge(&old, &50), 0);
+
+    assert_eq!(client.get_record_count(&new), 202);
+    assert_eq!(client.get_record_count(&old), 0);
+    let last = client.get_record_versions(&doctor, &new, &201);
+    assert_eq!(last.get(0).unwrap().id, 201);
+    assert_eq!(
+        add_record(&env, &client, &new, &doctor, RecordCategory::Notes),
+        202
+    );
+}
+
+fn setup_lost_wallet(env: &Env) -> (MedicalRegistryClient<'static>, Address, Address, Address) {
+    let (client, old, _) = setup_record(env);
+    let admin = Address::generate(env);
+    client.initialize(&admin, &None);
+    let new = Address::generate(env);
+    for wallet in [&old, &new] {
+        register(env, &client, wallet);
+        client.set_identity_fingerprint(wallet, &BytesN::from_array(env, &[1; 32]));
+    }
+    (client, admin, old, new)
+}
+
+#[test]
+fn test_admin_merges_lost_wallet_after_notice_period() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin, old, new) = setup_lost_wallet(&env);
+    let evidence = BytesN::from_array(&env, &[8; 32]);
+
+    client.request_merge(&admin, &old, &new, &evidence);
+    let pending = client.get_pending_merge(&old).unwrap();
+    assert!(client.try_complete_merge(&old).is_err());
+
+    env.ledger()
+        .with_mut(|li| li.timestamp = pending.executable_at);
+    client.complete_merge(&old);
+
+    assert!(client.get_pending_merge(&old).is_none());
+    assert_eq!(client.get_record_count(&new), 1);
+    assert_eq!(client.get_possible_duplicates(&new, &new).len(), 0);
+    let record = client.get_merge_record(&old).unwrap();
+    assert_eq!(record.into, new);
+    assert_eq!(record.approved_by, admin);
+    assert_eq!(record.evidence_hash, Some(evidence));
+}
+
+#[test]
+#[should_panic(expected = "No pending merge")]
+fn test_old_wallet_cancels_requested_merge() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin, old, new) = setup_lost_wallet(&env);
+
+    client.request_merge(&admin, &old, &new, &BytesN::from_array(&env, &[8; 32]));
+    client.cancel_merge(&old, &old);
+
+    env.ledger()
+        .with_mut(|li| li.timestamp += 30 * 24 * 60 * 60);
+    client.complete_merge(&old);
+}
+
+#[test]
+#[should_panic(expected = "Identity fingerprints do not match")]
+fn test_admin_merge_requires_matching_fingerprint() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, old, _) = setup_record(&env);
+    let admin = Address::generate(&env);
+    client.initialize(&admin, &None);
+    let new = Address::generate(&env);
+    register(&env, &client, &old);
+    register(&env, &client, &new);
+    client.set_identity_fingerprint(&old, &BytesN::from_array(&env, &[1; 32]));
+    client.set_identity_fingerprint(&new, &BytesN::from_array(&env, &[2; 32]));
+
+    client.request_merge(&admin, &old, &new, &BytesN::from_array(&env, &[8; 32]));
+}
+
+#[test]
+#[should_panic(expected = "Patient merged")]
+fn test_merged_address_cannot_register_again() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(MedicalRegistry, ());
+    let client = MedicalRegistryClient::new(&env, &contract_id);
+    let (old, new) = (Address::generate(&env), Address::generate(&env));
+    register(&env, &client, &old);
+    register(&env, &client, &new);
+
+    client.merge_patient(&old, &new);
+    register(&env, &client, &old);
+}
@@ -1,5 +1,6 @@
 use soroban_sdk::{
-    contract, contracterror, contractimpl, contracttype, symbol_short, Address, Env, String, Vec,
+    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, BytesN, Env,
+    String, Symbol, Vec,
 };
 
 #[contracttype]
@@ -8,7 +9,29 @@ pub struct InstitutionData {
     pub name: String,
     pub license_id: String,
     pub metadata: String,
-    pub is_verified: bool,
+    pub is_verified: bool, // Filled in on read from the verification record
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VerificationStatus {
+    Verified,
+    Suspended,
+    Revoked,
+}
+
+/// Who verified an institution, on what evidence, and until when
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerificationRecord {
+    pub verifier: Address,
+    pub verified_at: u64,
+    pub expires_at: u64,
+    pub evidence_hash: BytesN<32>, // Hash of the off-chain accreditation documents
+    pub status: VerificationStatus,
+    pub changed_by: Address, // Last to change the status
+    pub changed_at: u64,
+    pub reason: Option<String>, // Given on suspension or revocation
 }
 
 #[contracttype]
@@ -19,6 +42,7 @@ pub struct Appointment {
     pub doctor: Address,
     pub datetime: u64,
     pub status: AppointmentStatus,
+    pub cancel_reason: Option<String>, // Set when the provider cancels
 }
 
 #[contracttype]
@@ -27,12 +51,124 @@ pub enum AppointmentStatus {
     Scheduled,
     Canceled,
     Completed,
+    CheckedIn,
+    NoShow,
+}
+
+/// A change of time recorded when an appointment is rescheduled
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RescheduleRecord {
+    pub previous_datetime: u64,
+    pub new_datetime: u64,
+    pub changed_by: Address,
+    pub changed_at: u64,
+}
+
+/// A recurring weekly window in which a doctor takes appointments
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AvailabilityWindow {
+    pub weekday: u32, // 0 = Monday ... 6 = Sunday
+    pub start: u32,   // Seconds after midnight UTC
+    pub end: u32,     // Seconds after midnight UTC, exclusive
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AvailabilityTemplate {
+    pub slot_duration: u64, // Seconds
+    pub windows: Vec<AvailabilityWindow>,
+}
+
+/// Criteria for `get_appointments_page`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AppointmentFilter {
+    pub statuses: Vec<AppointmentStatus>, // Empty matches any status
+    pub from: u64,                        // Inclusive
+    pub to: u64,                          // Exclusive; 0 for no upper bound
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AppointmentPage {
+    pub appointments: Vec<Appointment>,
+    pub next_cursor: Option<u32>, // None once the history is exhausted
+    pub scan_limited: bool,       // The scan limit ended the page before `limit` matches were found
+}
+
+/// A patient waiting for a freed slot with a doctor
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WaitlistEntry {
+    pub patient: Address,
+    pub earliest: u64, // Preferred window start, inclusive
+    pub latest: u64,   // Preferred window end, exclusive
+    pub joined_at: u64,
+}
+
+/// A freed slot held for one waitlisted patient until `expires_at`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SlotOffer {
+    pub doctor: Address,
+    pub datetime: u64,
+    pub patient: Address,
+    pub expires_at: u64,
+}
+
+/// Copay a doctor collects at booking, held in escrow until the visit
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CopayPolicy {
+    pub token: Address, // SEP-41 token contract, e.g. a Stellar Asset Contract
+    pub amount: i128,
+    pub payee: Address, // The doctor or the institution they are affiliated with
+    pub cancel_window: u64, // Seconds before the appointment when late cancellation starts
+    pub late_refund_bps: u32, // Share refunded on late cancellation, in basis points
+    pub no_show_refund_bps: u32, // Share refunded when the patient does not turn up
+}
+
+/// Copay locked for one appointment, fixed at booking time
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CopayEscrow {
+    pub token: Address,
+    pub amount: i128,
+    pub payer: Address,
+    pub payee: Address,
+    pub cancel_window: u64,
+    pub late_refund_bps: u32,
+    pub no_show_refund_bps: u32,
 }
 
+const DAY: u64 = 24 * 60 * 60;
+
+const BPS_DENOMINATOR: u32 = 10_000;
+
+/// How long a waitlisted patient has to accept an offered slot
+const OFFER_WINDOW: u64 = 60 * 60;
+
+/// Most patients a doctor's waitlist holds
+const MAX_WAITLIST: u32 = 100;
+
+/// Most appointments returned by one `get_appointments_page` call
+const MAX_APPOINTMENT_PAGE: u32 = 50;
+
+/// Most appointments `get_appointments_page` inspects in one call
+const MAX_APPOINTMENT_SCAN: u32 = 200;
+
+/// Widest range `get_available_slots` will scan
+const MAX_SLOT_QUERY_RANGE: u64 = 31 * DAY;
+
 #[contracttype]
 pub enum DataKey {
     Inst(Address),
-    Admin, // To manage the 'verifier' role
+    Admin,                        // To manage the 'verifier' role
+    Verifier(Address),            // Present while the address may verify institutions
+    Verification(Address),        // Institution -> current VerificationRecord
+    VerificationHistory(Address), // Institution -> Vec<VerificationRecord>, oldest first
 }
 
 #[contracttype]
@@ -40,6 +176,17 @@ pub enum AppointmentKey {
     Appointment(u64),
     AppointmentCounter,
     UserAppointments(Address),
+    Availability(Address),    // Doctor -> AvailabilityTemplate
+    BlockedDates(Address),    // Doctor -> Vec<u64> of blocked day starts
+    BookedSlot(Address, u64), // (Doctor, slot start) -> appointment id
+    RescheduleHistory(u64),   // Appointment id -> Vec<RescheduleRecord>
+    DoctorDay(Address, u64),  // (Doctor, day start) -> Vec<u64> of appointment ids
+    DayIndexStart,            // First appointment id booked after DoctorDay was introduced
+    Waitlist(Address),        // Doctor -> Vec<WaitlistEntry> in joining order
+    Offer(Address, u64),      // (Doctor, slot start) -> SlotOffer
+    CopayPolicy(Address),     // Doctor -> CopayPolicy
+    Escrow(u64),              // Appointment id -> CopayEscrow while funds are held
+    Affiliation(Address),     // Doctor -> institution that accepted them
 }
 
 #[contracterror]
@@ -52,6 +199,19 @@ pub enum Error {
     AppointmentNotFound = 4,
     InvalidAppointmentStatus = 5,
     UnauthorizedAppointmentAction = 6,
+    SlotUnavailable = 7,
+    SlotAlreadyBooked = 8,
+    InvalidAvailability = 9,
+    InvalidTimeRange = 10,
+    AlreadyWaitlisted = 11,
+    NotWaitlisted = 12,
+    WaitlistFull = 13,
+    OfferNotFound = 14,
+    OfferExpired = 15,
+    InvalidCopayPolicy = 16,
+    AppointmentNotStarted = 17,
+    PayeeNotAffiliated = 18,
+    AvailabilityConflict = 19,
 }
 
 #[contract]
@@ -59,8 +219,12 @@ pub struct HealthcareRegistry;
 
 #[contractimpl]
 impl HealthcareRegistry {
-    // Set an admin/verifier during initialization
+    // Set the admin, who manages verifiers, during initialization
     pub fn init(env: Env, admin: Address) {
+        admin.require_auth();
+        if env.storage().instance().has(&DataKey::Admin) {
+            panic!("Already initialized");
+        }
         env.storage().instance().set(&DataKey::Admin, &admin);
     }
 
@@ -93,11 +257,16 @@ impl HealthcareRegistry {
     }
 
     pub fn get_institution(env: Env, wallet: Address) -> InstitutionData {
-        let key = DataKey::Inst(wallet);
-        env.storage()
+        let key = DataKey::Inst(wallet.clone());
+        let mut data: InstitutionData = env
+            .storage()
             .persistent()
             .get(&key)
-            .expect("Institution not found")
+            .expect("Institution not found");
+        // The record expires and changes status on its own, so a stored
+        // flag would go stale
+        data.is_verified = Self::is_institution_in_good_standing(env, wallet);
+        data
     }
 
     pub fn update_institution(env: Env, wallet: Address, metadata: String) {
@@ -110,20 +279,202 @@ impl HealthcareRegistry {
         env.storage().persistent().set(&key, &data);
     }
 
-    pub fn verify_institution(env: Env, verifier: Address, wallet: Address) {
+    pub fn add_verifier(env: Env, admin: Address, verifier: Address) {
+        Self::require_admin(&env, &admin);
+        env.storage()
+            .persistent()
+            .set(&DataKey::Verifier(verifier.clone()), &true);
+        env.events()
+            .publish((symbol_short!("vrf_add"), verifier), admin);
+    }
+
+    pub fn remove_verifier(env: Env, admin: Address, verifier: Address) {
+        Self::require_admin(&env, &admin);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Verifier(verifier.clone()));
+        env.events()
+            .publish((symbol_short!("vrf_rm"), verifier), admin);
+    }
+
+    pub fn is_verifier(env: Env, address: Address) -> bool {
+        env.storage().persistent().has(&DataKey::Verifier(address))
+    }
+
+    // Verify (or re-verify) an institution until `expires_at`. Replaces any
+    // earlier record, including a suspended or revoked one.
+    pub fn verify_institution(
+        env: Env,
+        verifier: Address,
+        wallet: Address,
+        expires_at: u64,
+        evidence_hash: BytesN<32>,
+    ) {
         verifier.require_auth();
 
-        // Access Control: Check if caller is the admin
-        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
-        if verifier != admin {
+        // Access Control: Check if caller holds the verifier role
+        if !Self::is_verifier(env.clone(), verifier.clone()) {
             panic!("Not authorized to verify");
         }
+        let now = env.ledger().timestamp();
+        if expires_at <= now {
+            panic!("Verification must expire in the future");
+        }
 
-        let key = DataKey::Inst(wallet.clone());
-        let mut data: InstitutionData = env.storage().persistent().get(&key).expect("Not found");
+        if !env
+            .storage()
+            .persistent()
+            .has(&DataKey::Inst(wallet.clone()))
+        {
+            panic!("Not found");
+        }
 
-        data.is_verified = true;
-        env.storage().persistent().set(&key, &data);
+        let record = VerificationRecord {
+            verifier: verifier.clone(),
+            verified_at: now,
+            expires_at,
+            evidence_hash,
+            status: VerificationStatus::Verified,
+            changed_by: verifier.clone(),
+            changed_at: now,
+            reason: None,
+        };
+        Self::save_verification(&env, &wallet, &record);
+
+        env.events()
+            .publish((symbol_short!("inst_ver"), wallet), (verifier, expires_at));
+    }
+
+    // Temporarily withdraw a verification; `reinstate_institution` restores it
+    pub fn suspend_institution(env: Env, caller: Address, wallet: Address, reason: String) {
+        Self::change_verification(
+            &env,
+            &caller,
+            &wallet,
+            VerificationStatus::Suspended,
+            Some(reason),
+        );
+        env.events()
+            .publish((symbol_short!("inst_sus"), wallet), caller);
+    }
+
+    pub fn reinstate_institution(env: Env, caller: Address, wallet: Address) {
+        Self::change_verification(&env, &caller, &wallet, VerificationStatus::Verified, None);
+        env.events()
+            .publish((symbol_short!("inst_rst"), wallet), caller);
+    }
+
+    // Permanently withdraw a verification; only a fresh verification undoes it
+    pub fn revoke_verification(env: Env, caller: Address, wallet: Address, reason: String) {
+        Self::change_verification(
+            &env,
+            &caller,
+            &wallet,
+            VerificationStatus::Revoked,
+            Some(reason),
+        );
+        env.events()
+            .publish((symbol_short!("inst_rev"), wallet), caller);
+    }
+
+    pub fn get_verification(env: Env, wallet: Address) -> VerificationRecord {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Verification(wallet))
+            .expect("Not verified")
+    }
+
+    pub fn get_verification_history(env: Env, wallet: Address) -> Vec<VerificationRecord> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::VerificationHistory(wallet))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    // True while the institution holds an unexpired, unsuspended verification
+    pub fn is_institution_in_good_standing(env: Env, wallet: Address) -> bool {
+        let record: Option<VerificationRecord> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Verification(wallet));
+        match record {
+            Some(record) => {
+                record.status == VerificationStatus::Verified
+                    && env.ledger().timestamp() < record.expires_at
+            }
+            None => false,
+        }
+    }
+
+    fn require_admin(env: &Env, admin: &Address) {
+        admin.require_auth();
+        let stored: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .expect("Not initialized");
+        if *admin != stored {
+            panic!("Only admin can manage verifiers");
+        }
+    }
+
+    // Suspend, reinstate or revoke; verifiers and the admin may do this
+    fn change_verification(
+        env: &Env,
+        caller: &Address,
+        wallet: &Address,
+        status: VerificationStatus,
+        reason: Option<String>,
+    ) {
+        caller.require_auth();
+
+        let admin: Option<Address> = env.storage().instance().get(&DataKey::Admin);
+        if admin.as_ref() != Some(caller)
+            && !env
+                .storage()
+                .persistent()
+                .has(&DataKey::Verifier(caller.clone()))
+        {
+            panic!("Not authorized to verify");
+        }
+
+        let mut record: VerificationRecord = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Verification(wallet.clone()))
+            .expect("Not verified");
+        let allowed = matches!(
+            (&record.status, &status),
+            (VerificationStatus::Verified, VerificationStatus::Suspended)
+                | (VerificationStatus::Verified, VerificationStatus::Revoked)
+                | (VerificationStatus::Suspended, VerificationStatus::Verified)
+                | (VerificationStatus::Suspended, VerificationStatus::Revoked)
+        );
+        if !allowed {
+            panic!("Invalid verification status change");
+        }
+
+        record.status = status;
+        record.changed_by = caller.clone();
+        record.changed_at = env.ledger().timestamp();
+        record.reason = reason;
+        Self::save_verification(env, wallet, &record);
+    }
+
+    // Store the current record and append it to the institution's history
+    fn save_verification(env: &Env, wallet: &Address, record: &VerificationRecord) {
+        env.storage()
+            .persistent()
+            .set(&DataKey::Verification(wallet.clone()), record);
+
+        let history_key = DataKey::VerificationHistory(wallet.clone());
+        let mut history: Vec<VerificationRecord> = env
+            .storage()
+            .persistent()
+            .get(&history_key)
+            .unwrap_or(Vec::new(env));
+        history.push_back(record.clone());
+        env.storage().persistent().set(&history_key, &history);
     }
 }
 
@@ -132,9 +483,27 @@ pub struct AppointmentScheduling;
 
 #[contractimpl]
 impl AppointmentScheduling {
-    pub fn create_appointment(env: Env, patient: Address, doctor: Address, datetime: u64) -> u64 {
+    // Book an appointment. The doctor's slot at that time is claimed here;
+    // once the doctor has published availability, the datetime must also be
+    // the start of a slot in the template.
+    pub fn create_appointment(
+        env: Env,
+        patient: Address,
+        doctor: Address,
+        datetime: u64,
+    ) -> Result<u64, Error> {
         patient.require_auth();
 
+        Self::book(&env, patient, doctor, datetime)
+    }
+
+    fn book(env: &Env, patient: Address, doctor: Address, datetime: u64) -> Result<u64, Error> {
+        Self::check_slot(env, &doctor, datetime)?;
+        // Any offer left on this slot has lapsed
+        env.storage()
+            .persistent()
+            .remove(&AppointmentKey::Offer(doctor.clone(), datetime));
+
         // Get next appointment ID
         let counter_key = AppointmentKey::AppointmentCounter;
         let appointment_id = env.storage().persistent().get(&counter_key).unwrap_or(0u64) + 1;
@@ -146,6 +515,7 @@ impl AppointmentScheduling {
             doctor: doctor.clone(),
             datetime,
             status: AppointmentStatus::Scheduled,
+            cancel_reason: None,
         };
 
         // Store appointment
@@ -153,6 +523,9 @@ impl AppointmentScheduling {
         env.storage()
             .persistent()
             .set(&appointment_key, &appointment);
+        Self::claim_slot(env, &doctor, datetime, appointment_id);
+        Self::add_to_day(env, &doctor, datetime, appointment_id);
+        Self::lock_copay(env, &patient, &doctor, appointment_id);
 
         // Update counter
         env.storage()
@@ -165,7 +538,7 @@ impl AppointmentScheduling {
             .storage()
             .persistent()
             .get(&patient_key)
-            .unwrap_or(Vec::new(&env));
+            .unwrap_or(Vec::new(env));
         patient_appointments.push_back(appointment_id);
         env.storage()
             .persistent()
@@ -177,7 +550,7 @@ impl AppointmentScheduling {
             .storage()
             .persistent()
             .get(&doctor_key)
-            .unwrap_or(Vec::new(&env));
+            .unwrap_or(Vec::new(env));
         doctor_appointments.push_back(appointment_id);
         env.storage()
             .persistent()
@@ -189,67 +562,181 @@ impl AppointmentScheduling {
             (patient, doctor),
         );
 
-        appointment_id
+        Ok(appointment_id)
     }
 
-    pub fn cancel_appointment(env: Env, patient: Address, appointment_id: u64) {
+    pub fn cancel_appointment(
+        env: Env,
+        patient: Address,
+        appointment_id: u64,
+    ) -> Result<(), Error> {
         patient.require_auth();
 
-        let appointment_key = AppointmentKey::Appointment(appointment_id);
-        let mut appointment: Appointment = env
-            .storage()
-            .persistent()
-            .get(&appointment_key)
-            .ok_or(Error::AppointmentNotFound)
-            .unwrap();
+        let mut appointment = Self::load_appointment(&env, appointment_id)?;
 
-        // Only patient can cancel, and only if appointment is scheduled
+        // Only the patient can cancel here; providers use `cancel_by_provider`
         if appointment.patient != patient {
-            panic!("Unauthorized to cancel this appointment");
-        }
-
-        if !matches!(appointment.status, AppointmentStatus::Scheduled) {
-            panic!("Can only cancel scheduled appointments");
+            return Err(Error::UnauthorizedAppointmentAction);
         }
-
-        appointment.status = AppointmentStatus::Canceled;
-        env.storage()
-            .persistent()
-            .set(&appointment_key, &appointment);
+        Self::transition(&mut appointment, AppointmentStatus::Canceled)?;
+        Self::save_appointment(&env, &appointment);
+        Self::release_slot(&env, &appointment);
+        Self::remove_from_day(&env, &appointment);
+        Self::offer_to_waitlist(&env, &appointment.doctor, appointment.datetime);
+        Self::refund_copay(&env, &appointment, false);
 
         // Emit event
         env.events()
             .publish((symbol_short!("appt_can"), appointment_id), patient);
+        Ok(())
     }
 
-    pub fn complete_appointment(env: Env, doctor: Address, appointment_id: u64) {
+    // Provider-initiated cancellation; the reason is kept on the appointment
+    pub fn cancel_by_provider(
+        env: Env,
+        doctor: Address,
+        appointment_id: u64,
+        reason: String,
+    ) -> Result<(), Error> {
         doctor.require_auth();
 
-        let appointment_key = AppointmentKey::Appointment(appointment_id);
-        let mut appointment: Appointment = env
-            .storage()
-            .persistent()
-            .get(&appointment_key)
-            .ok_or(Error::AppointmentNotFound)
-            .unwrap();
+        let mut appointment = Self::load_appointment(&env, appointment_id)?;
+        if appointment.doctor != doctor {
+            return Err(Error::UnauthorizedAppointmentAction);
+        }
+        Self::transition(&mut appointment, AppointmentStatus::Canceled)?;
+        appointment.cancel_reason = Some(reason.clone());
+        Self::save_appointment(&env, &appointment);
+        Self::release_slot(&env, &appointment);
+        Self::remove_from_day(&env, &appointment);
+        Self::offer_to_waitlist(&env, &appointment.doctor, appointment.datetime);
+        // The patient is never charged for a provider cancellation
+        Self::refund_copay(&env, &appointment, true);
 
-        // Only doctor can complete, and only if appointment is scheduled
+        // Emit event
+        env.events().publish(
+            (symbol_short!("appt_pcn"), appointment_id),
+            (doctor, reason),
+        );
+        Ok(())
+    }
+
+    pub fn complete_appointment(
+        env: Env,
+        doctor: Address,
+        appointment_id: u64,
+    ) -> Result<(), Error> {
+        doctor.require_auth();
+
+        let mut appointment = Self::load_appointment(&env, appointment_id)?;
         if appointment.doctor != doctor {
-            panic!("Unauthorized to complete this appointment");
+            return Err(Error::UnauthorizedAppointmentAction);
         }
+        Self::transition(&mut appointment, AppointmentStatus::Completed)?;
+        Self::save_appointment(&env, &appointment);
+        Self::release_copay(&env, appointment_id);
 
-        if !matches!(appointment.status, AppointmentStatus::Scheduled) {
-            panic!("Can only complete scheduled appointments");
+        // Emit event
+        env.events()
+            .publish((symbol_short!("appt_cmp"), appointment_id), doctor);
+        Ok(())
+    }
+
+    // Move a scheduled appointment to a new time. Either party may do this;
+    // the previous time is appended to the appointment's reschedule history.
+    pub fn reschedule_appointment(
+        env: Env,
+        caller: Address,
+        appointment_id: u64,
+        new_datetime: u64,
+    ) -> Result<(), Error> {
+        caller.require_auth();
+
+        let mut appointment = Self::load_appointment(&env, appointment_id)?;
+        if appointment.patient != caller && appointment.doctor != caller {
+            return Err(Error::UnauthorizedAppointmentAction);
         }
+        Self::transition(&mut appointment, AppointmentStatus::Scheduled)?;
+        Self::check_slot(&env, &appointment.doctor, new_datetime)?;
+
+        Self::release_slot(&env, &appointment);
+        Self::remove_from_day(&env, &appointment);
+        let previous_datetime = appointment.datetime;
+        appointment.datetime = new_datetime;
+        Self::save_appointment(&env, &appointment);
+        Self::claim_slot(&env, &appointment.doctor, new_datetime, appointment_id);
+        Self::add_to_day(&env, &appointment.doctor, new_datetime, appointment_id);
+        Self::offer_to_waitlist(&env, &appointment.doctor, previous_datetime);
 
-        appointment.status = AppointmentStatus::Completed;
+        let history_key = AppointmentKey::RescheduleHistory(appointment_id);
+        let mut history: Vec<RescheduleRecord> = env
+            .storage()
+            .persistent()
+            .get(&history_key)
+            .unwrap_or(Vec::new(&env));
+        history.push_back(RescheduleRecord {
+            previous_datetime,
+            new_datetime,
+            changed_by: caller.clone(),
+            changed_at: env.ledger().timestamp(),
+        });
+        env.storage().persistent().set(&history_key, &history);
+
+        // Emit event
+        env.events().publish(
+            (symbol_short!("appt_rs"), appointment_id),
+            (caller, previous_datetime, new_datetime),
+        );
+        Ok(())
+    }
+
+    pub fn get_reschedule_history(env: Env, appointment_id: u64) -> Vec<RescheduleRecord> {
         env.storage()
             .persistent()
-            .set(&appointment_key, &appointment);
+            .get(&AppointmentKey::RescheduleHistory(appointment_id))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    // Record the patient's arrival; either party may check in
+    pub fn check_in(env: Env, caller: Address, appointment_id: u64) -> Result<(), Error> {
+        caller.require_auth();
+
+        let mut appointment = Self::load_appointment(&env, appointment_id)?;
+        if appointment.patient != caller && appointment.doctor != caller {
+            return Err(Error::UnauthorizedAppointmentAction);
+        }
+        Self::transition(&mut appointment, AppointmentStatus::CheckedIn)?;
+        Self::save_appointment(&env, &appointment);
 
         // Emit event
         env.events()
-            .publish((symbol_short!("appt_cmp"), appointment_id), doctor);
+            .publish((symbol_short!("appt_in"), appointment_id), caller);
+        Ok(())
+    }
+
+    pub fn mark_no_show(env: Env, doctor: Address, appointment_id: u64) -> Result<(), Error> {
+        doctor.require_auth();
+
+        let mut appointment = Self::load_appointment(&env, appointment_id)?;
+        if appointment.doctor != doctor {
+            return Err(Error::UnauthorizedAppointmentAction);
+        }
+        Self::transition(&mut appointment, AppointmentStatus::NoShow)?;
+        // Only a missed appointment can be a no-show
+        if env.ledger().timestamp() < appointment.datetime {
+            return Err(Error::AppointmentNotStarted);
+        }
+        Self::save_appointment(&env, &appointment);
+        Self::refund_no_show(&env, appointment_id);
+
+        // Emit event
+        env.events()
+            .publish((symbol_short!("appt_ns"), appointment_id), doctor);
+        Ok(())
+    }
+
+    pub fn get_appointment(env: Env, appointment_id: u64) -> Result<Appointment, Error> {
+        Self::load_appointment(&env, appointment_id)
     }
 
     pub fn get_appointments(env: Env, user: Address) -> Vec<Appointment> {
@@ -273,6 +760,827 @@ impl AppointmentScheduling {
 
         appointments
     }
+
+    // Page through a user's appointments in booking order, keeping those that
+    // match the filter. Pass 0 as the first cursor and then each returned
+    // `next_cursor` until it is None. At most MAX_APPOINTMENT_SCAN entries are
+    // inspected per call; a page cut short that way has `scan_limited` set and
+    // may even be empty, but its cursor still continues the scan.
+    pub fn get_appointments_page(
+        env: Env,
+        user: Address,
+        filter: AppointmentFilter,
+        cursor: u32,
+        limit: u32,
+    ) -> Result<AppointmentPage, Error> {
+        if filter.to != 0 && filter.to <= filter.from {
+            return Err(Error::InvalidTimeRange);
+        }
+        let limit = if limit > MAX_APPOINTMENT_PAGE {
+            MAX_APPOINTMENT_PAGE
+        } else {
+            limit
+        };
+
+        let appointment_ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&AppointmentKey::UserAppointments(user))
+            .unwrap_or(Vec::new(&env));
+
+        let mut appointments = Vec::new(&env);
+        let mut position = cursor;
+        let mut scanned = 0;
+        while position < appointment_ids.len()
+            && appointments.len() < limit
+            && scanned < MAX_APPOINTMENT_SCAN
+        {
+            let appointment =
+                Self::load_appointment(&env, appointment_ids.get_unchecked(position))?;
+            if Self::matches_filter(&appointment, &filter) {
+                appointments.push_back(appointment);
+            }
+            position += 1;
+            scanned += 1;
+        }
+
+        let next_cursor = if position < appointment_ids.len() {
+            Some(position)
+        } else {
+            None
+        };
+        Ok(AppointmentPage {
+            scan_limited: next_cursor.is_some() && appointments.len() < limit,
+            appointments,
+            next_cursor,
+        })
+    }
+
+    // A doctor's appointments on the day containing `date`, in time order,
+    // leaving out cancellations. Appointments booked before the day index
+    // existed are found by scanning that older part of the doctor's history.
+    pub fn get_day_sheet(env: Env, doctor: Address, date: u64) -> Vec<Appointment> {
+        let day = date - date % DAY;
+        let mut appointment_ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&AppointmentKey::DoctorDay(doctor.clone(), day))
+            .unwrap_or(Vec::new(&env));
+
+        let index_start: u64 = env
+            .storage()
+            .persistent()
+            .get(&AppointmentKey::DayIndexStart)
+            .unwrap_or(u64::MAX);
+        if index_start > 1 {
+            let history: Vec<u64> = env
+                .storage()
+                .persistent()
+                .get(&AppointmentKey::UserAppointments(doctor.clone()))
+                .unwrap_or(Vec::new(&env));
+            for id in history.iter() {
+                // Ids are in booking order, so the rest are indexed
+                if id >= index_start {
+                    break;
+                }
+                if appointment_ids.contains(id) {
+                    continue;
+                }
+                if let Ok(appointment) = Self::load_appointment(&env, id) {
+                    if appointment.doctor == doctor
+                        && appointment.datetime - appointment.datetime % DAY == day
+                    {
+                        appointment_ids.push_back(id);
+                    }
+                }
+            }
+        }
+
+        // Insertion sort; a single day's list is short
+        let mut sheet: Vec<Appointment> = Vec::new(&env);
+        for id in appointment_ids.iter() {
+            if let Ok(appointment) = Self::load_appointment(&env, id) {
+                if appointment.status == AppointmentStatus::Canceled {
+                    continue;
+                }
+                let mut index = sheet.len();
+                while index > 0 && sheet.get_unchecked(index - 1).datetime > appointment.datetime {
+                    index -= 1;
+                }
+                sheet.insert(index, appointment);
+            }
+        }
+        sheet
+    }
+
+    // Join a doctor's waitlist. When a slot inside [earliest, latest) is freed,
+    // the earliest-joined matching patient is offered it.
+    pub fn join_waitlist(
+        env: Env,
+        patient: Address,
+        doctor: Address,
+        earliest: u64,
+        latest: u64,
+    ) -> Result<(), Error> {
+        patient.require_auth();
+
+        if latest <= earliest || latest <= env.ledger().timestamp() {
+            return Err(Error::InvalidTimeRange);
+        }
+        let key = AppointmentKey::Waitlist(doctor.clone());
+        let mut waitlist: Vec<WaitlistEntry> = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(&env));
+        if waitlist.iter().any(|entry| entry.patient == patient) {
+            return Err(Error::AlreadyWaitlisted);
+        }
+        if waitlist.len() >= MAX_WAITLIST {
+            return Err(Error::WaitlistFull);
+        }
+        waitlist.push_back(WaitlistEntry {
+            patient: patient.clone(),
+            earliest,
+            latest,
+            joined_at: env.ledger().timestamp(),
+        });
+        env.storage().persistent().set(&key, &waitlist);
+
+        // Emit event
+        env.events().publish(
+            (symbol_short!("wl_join"), doctor),
+            (patient, earliest, latest),
+        );
+        Ok(())
+    }
+
+    pub fn leave_waitlist(env: Env, patient: Address, doctor: Address) -> Result<(), Error> {
+        patient.require_auth();
+
+        if !Self::take_from_waitlist(&env, &doctor, &patient) {
+            return Err(Error::NotWaitlisted);
+        }
+        Ok(())
+    }
+
+    pub fn get_waitlist(env: Env, doctor: Address) -> Vec<WaitlistEntry> {
+        env.storage()
+            .persistent()
+            .get(&AppointmentKey::Waitlist(doctor))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    pub fn get_offer(env: Env, doctor: Address, datetime: u64) -> Option<SlotOffer> {
+        env.storage()
+            .persistent()
+            .get(&AppointmentKey::Offer(doctor, datetime))
+    }
+
+    // Book an offered slot before the offer expires
+    pub fn accept_offer(
+        env: Env,
+        patient: Address,
+        doctor: Address,
+        datetime: u64,
+    ) -> Result<u64, Error> {
+        patient.require_auth();
+
+        let offer = Self::load_offer(&env, &doctor, datetime)?;
+        if offer.patient != patient {
+            return Err(Error::UnauthorizedAppointmentAction);
+        }
+        if env.ledger().timestamp() >= offer.expires_at {
+            return Err(Error::OfferExpired);
+        }
+        env.storage()
+            .persistent()
+            .remove(&AppointmentKey::Offer(doctor.clone(), datetime));
+        let appointment_id = Self::book(&env, patient.clone(), doctor.clone(), datetime)?;
+
+        // Emit event
+        env.events().publish(
+            (symbol_short!("wl_acc"), doctor),
+            (patient, datetime, appointment_id),
+        );
+        Ok(appointment_id)
+    }
+
+    // Turn down an offer so it moves on to the next waitlisted patient
+    pub fn decline_offer(
+        env: Env,
+        patient: Address,
+        doctor: Address,
+        datetime: u64,
+    ) -> Result<(), Error> {
+        patient.require_auth();
+
+        let offer = Self::load_offer(&env, &doctor, datetime)?;
+        if offer.patient != patient {
+            return Err(Error::UnauthorizedAppointmentAction);
+        }
+        Self::pass_offer_on(&env, offer, symbol_short!("wl_dec"));
+        Ok(())
+    }
+
+    // Move a lapsed offer on to the next waitlisted patient. Anyone may call
+    // this once the offer has expired, typically the notifier.
+    pub fn advance_offer(env: Env, doctor: Address, datetime: u64) -> Result<(), Error> {
+        let offer = Self::load_offer(&env, &doctor, datetime)?;
+        if env.ledger().timestamp() < offer.expires_at {
+            return Err(Error::InvalidAppointmentStatus);
+        }
+        Self::pass_offer_on(&env, offer, symbol_short!("wl_lapse"));
+        Ok(())
+    }
+
+    // Record that the institution accepts copays on the doctor's behalf,
+    // replacing any earlier affiliation
+    pub fn affiliate_doctor(env: Env, institution: Address, doctor: Address) {
+        institution.require_auth();
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::Affiliation(doctor.clone()), &institution);
+        env.events()
+            .publish((symbol_short!("affil"), doctor), institution);
+    }
+
+    // End an affiliation. A copay policy paying the institution is cleared
+    // with it; appointments already booked keep their escrow.
+    pub fn end_affiliation(env: Env, institution: Address, doctor: Address) -> Result<(), Error> {
+        institution.require_auth();
+        if Self::get_affiliation(env.clone(), doctor.clone()) != Some(institution.clone()) {
+            return Err(Error::NotFound);
+        }
+        env.storage()
+            .persistent()
+            .remove(&AppointmentKey::Affiliation(doctor.clone()));
+
+        let policy_key = AppointmentKey::CopayPolicy(doctor.clone());
+        let policy: Option<CopayPolicy> = env.storage().persistent().get(&policy_key);
+        if policy.is_some_and(|policy| policy.payee == institution) {
+            env.storage().persistent().remove(&policy_key);
+        }
+        env.events()
+            .publish((symbol_short!("affil_end"), doctor), institution);
+        Ok(())
+    }
+
+    pub fn get_affiliation(env: Env, doctor: Address) -> Option<Address> {
+        env.storage()
+            .persistent()
+            .get(&AppointmentKey::Affiliation(doctor))
+    }
+
+    // Require a copay on new bookings with this doctor. Appointments already
+    // booked keep the terms they were booked under. The payee must be the
+    // doctor or the institution they are affiliated with.
+    pub fn set_copay_policy(env: Env, doctor: Address, policy: CopayPolicy) -> Result<(), Error> {
+        doctor.require_auth();
+
+        if policy.amount <= 0
+            || policy.late_refund_bps > BPS_DENOMINATOR
+            || policy.no_show_refund_bps > BPS_DENOMINATOR
+        {
+            return Err(Error::InvalidCopayPolicy);
+        }
+        if policy.payee != doctor
+            && Self::get_affiliation(env.clone(), doctor.clone()) != Some(policy.payee.clone())
+        {
+            return Err(Error::PayeeNotAffiliated);
+        }
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::CopayPolicy(doctor), &policy);
+        Ok(())
+    }
+
+    pub fn clear_copay_policy(env: Env, doctor: Address) {
+        doctor.require_auth();
+        env.storage()
+            .persistent()
+            .remove(&AppointmentKey::CopayPolicy(doctor));
+    }
+
+    pub fn get_copay_policy(env: Env, doctor: Address) -> Option<CopayPolicy> {
+        env.storage()
+            .persistent()
+            .get(&AppointmentKey::CopayPolicy(doctor))
+    }
+
+    pub fn get_escrow(env: Env, appointment_id: u64) -> Option<CopayEscrow> {
+        env.storage()
+            .persistent()
+            .get(&AppointmentKey::Escrow(appointment_id))
+    }
+
+    // Publish or replace the doctor's weekly availability. Every upcoming
+    // scheduled appointment must start on a slot of the new template.
+    pub fn set_availability(
+        env: Env,
+        doctor: Address,
+        slot_duration: u64,
+        windows: Vec<AvailabilityWindow>,
+    ) -> Result<(), Error> {
+        doctor.require_auth();
+
+        if slot_duration == 0 || slot_duration > DAY || windows.is_empty() {
+            return Err(Error::InvalidAvailability);
+        }
+        for window in windows.iter() {
+            if window.weekday > 6
+                || window.start >= window.end
+                || window.end as u64 > DAY
+                || ((window.end - window.start) as u64) < slot_duration
+            {
+                return Err(Error::InvalidAvailability);
+            }
+        }
+
+        let template = AvailabilityTemplate {
+            slot_duration,
+            windows,
+        };
+        let now = env.ledger().timestamp();
+        let appointment_ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&AppointmentKey::UserAppointments(doctor.clone()))
+            .unwrap_or(Vec::new(&env));
+        for id in appointment_ids.iter() {
+            let appointment = Self::load_appointment(&env, id)?;
+            if appointment.doctor == doctor
+                && appointment.status == AppointmentStatus::Scheduled
+                && appointment.datetime >= now
+                && !Self::is_template_slot(&template, appointment.datetime)
+            {
+                return Err(Error::AvailabilityConflict);
+            }
+        }
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::Availability(doctor.clone()), &template);
+
+        env.events()
+            .publish((symbol_short!("avail"), doctor), slot_duration);
+        Ok(())
+    }
+
+    pub fn get_availability(env: Env, doctor: Address) -> Option<AvailabilityTemplate> {
+        env.storage()
+            .persistent()
+            .get(&AppointmentKey::Availability(doctor))
+    }
+
+    // Block the whole day containing `date`
+    pub fn block_date(env: Env, doctor: Address, date: u64) {
+        doctor.require_auth();
+
+        let day = date - date % DAY;
+        let key = AppointmentKey::BlockedDates(doctor);
+        let mut blocked: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(&env));
+        if !blocked.contains(day) {
+            blocked.push_back(day);
+            env.storage().persistent().set(&key, &blocked);
+        }
+    }
+
+    pub fn unblock_date(env: Env, doctor: Address, date: u64) {
+        doctor.require_auth();
+
+        let day = date - date % DAY;
+        let key = AppointmentKey::BlockedDates(doctor);
+        let mut blocked: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(&env));
+        if let Some(index) = blocked.first_index_of(day) {
+            blocked.remove(index);
+            env.storage().persistent().set(&key, &blocked);
+        }
+    }
+
+    pub fn get_blocked_dates(env: Env, doctor: Address) -> Vec<u64> {
+        env.storage()
+            .persistent()
+            .get(&AppointmentKey::BlockedDates(doctor))
+            .unwrap_or(Vec::new(&env))
+    }
+
+    // Start times of the doctor's free slots in [from, to), at most 31 days wide
+    pub fn get_available_slots(
+        env: Env,
+        doctor: Address,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<u64>, Error> {
+        if to <= from || to - from > MAX_SLOT_QUERY_RANGE {
+            return Err(Error::InvalidTimeRange);
+        }
+
+        let mut slots = Vec::new(&env);
+        let template: AvailabilityTemplate = match env
+            .storage()
+            .persistent()
+            .get(&AppointmentKey::Availability(doctor.clone()))
+        {
+            Some(template) => template,
+            None => return Ok(slots),
+        };
+        let now = env.ledger().timestamp();
+
+        let mut day = from - from % DAY;
+        while day < to {
+            if !Self::is_blocked(&env, &doctor, day) {
+                let weekday = Self::weekday(day);
+                for window in template.windows.iter() {
+                    if window.weekday != weekday {
+                        continue;
+                    }
+                    let mut start = day + window.start as u64;
+                    while start + template.slot_duration <= day + window.end as u64 {
+                        if start >= from
+                            && start < to
+                            && start >= now
+                            && !env
+                                .storage()
+                                .persistent()
+                                .has(&AppointmentKey::BookedSlot(doctor.clone(), start))
+                        {
+                            slots.push_back(start);
+                        }
+                        start += template.slot_duration;
+                    }
+                }
+            }
+            day += DAY;
+        }
+
+        Ok(slots)
+    }
+
+    // 1970-01-01 was a Thursday
+    fn weekday(timestamp: u64) -> u32 {
+        ((timestamp / DAY + 3) % 7) as u32
+    }
+
+    fn is_template_slot(template: &AvailabilityTemplate, datetime: u64) -> bool {
+        let weekday = Self::weekday(datetime);
+        let offset = datetime % DAY;
+        template.windows.iter().any(|window| {
+            window.weekday == weekday
+                && offset >= window.start as u64
+                && offset + template.slot_duration <= window.end as u64
+                && (offset - window.start as u64).is_multiple_of(template.slot_duration)
+        })
+    }
+
+    // Allowed status changes:
+    //   Scheduled -> Scheduled (reschedule), CheckedIn, Completed, Canceled, NoShow
+    //   CheckedIn -> Completed, Canceled
+    // Canceled, Completed and NoShow are final.
+    fn transition(appointment: &mut Appointment, to: AppointmentStatus) -> Result<(), Error> {
+        use AppointmentStatus::*;
+
+        let allowed = matches!(
+            (&appointment.status, &to),
+            (Scheduled, Scheduled)
+                | (Scheduled, CheckedIn)
+                | (Scheduled, Completed)
+                | (Scheduled, Canceled)
+                | (Scheduled, NoShow)
+                | (CheckedIn, Completed)
+                | (CheckedIn, Canceled)
+        );
+        if !allowed {
+            return Err(Error::InvalidAppointmentStatus);
+        }
+        appointment.status = to;
+        Ok(())
+    }
+
+    fn load_appointment(env: &Env, appointment_id: u64) -> Result<Appointment, Error> {
+        env.storage()
+            .persistent()
+            .get(&AppointmentKey::Appointment(appointment_id))
+            .ok_or(Error::AppointmentNotFound)
+    }
+
+    fn save_appointment(env: &Env, appointment: &Appointment) {
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::Appointment(appointment.id), appointment);
+    }
+
+    // Check that a doctor's slot can be booked. A doctor without published
+    // availability can take any time that no other appointment holds.
+    fn check_slot(env: &Env, doctor: &Address, datetime: u64) -> Result<(), Error> {
+        // A slot on offer to a waitlisted patient is held until the offer lapses
+        let offer: Option<SlotOffer> = env
+            .storage()
+            .persistent()
+            .get(&AppointmentKey::Offer(doctor.clone(), datetime));
+        if let Some(offer) = offer {
+            if env.ledger().timestamp() < offer.expires_at {
+                return Err(Error::SlotAlreadyBooked);
+            }
+        }
+
+        let template: Option<AvailabilityTemplate> = env
+            .storage()
+            .persistent()
+            .get(&AppointmentKey::Availability(doctor.clone()));
+        if let Some(template) = template {
+            if datetime < env.ledger().timestamp()
+                || !Self::is_template_slot(&template, datetime)
+                || Self::is_blocked(env, doctor, datetime)
+            {
+                return Err(Error::SlotUnavailable);
+            }
+        }
+        if env
+            .storage()
+            .persistent()
+            .has(&AppointmentKey::BookedSlot(doctor.clone(), datetime))
+        {
+            return Err(Error::SlotAlreadyBooked);
+        }
+        Ok(())
+    }
+
+    fn claim_slot(env: &Env, doctor: &Address, datetime: u64, appointment_id: u64) {
+        env.storage().persistent().set(
+            &AppointmentKey::BookedSlot(doctor.clone(), datetime),
+            &appointment_id,
+        );
+    }
+
+    fn is_blocked(env: &Env, doctor: &Address, datetime: u64) -> bool {
+        let blocked: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&AppointmentKey::BlockedDates(doctor.clone()))
+            .unwrap_or(Vec::new(env));
+        blocked.contains(datetime - datetime % DAY)
+    }
+
+    fn matches_filter(appointment: &Appointment, filter: &AppointmentFilter) -> bool {
+        (filter.statuses.is_empty() || filter.statuses.contains(&appointment.status))
+            && appointment.datetime >= filter.from
+            && (filter.to == 0 || appointment.datetime < filter.to)
+    }
+
+    fn add_to_day(env: &Env, doctor: &Address, datetime: u64, appointment_id: u64) {
+        // Mark where the index begins; anything booked earlier is not in it
+        if !env
+            .storage()
+            .persistent()
+            .has(&AppointmentKey::DayIndexStart)
+        {
+            let booked: u64 = env
+                .storage()
+                .persistent()
+                .get(&AppointmentKey::AppointmentCounter)
+                .unwrap_or(0);
+            env.storage()
+                .persistent()
+                .set(&AppointmentKey::DayIndexStart, &(booked + 1));
+        }
+        let key = AppointmentKey::DoctorDay(doctor.clone(), datetime - datetime % DAY);
+        let mut ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(env));
+        ids.push_back(appointment_id);
+        env.storage().persistent().set(&key, &ids);
+    }
+
+    fn remove_from_day(env: &Env, appointment: &Appointment) {
+        let key = AppointmentKey::DoctorDay(
+            appointment.doctor.clone(),
+            appointment.datetime - appointment.datetime % DAY,
+        );
+        let mut ids: Vec<u64> = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(env));
+        if let Some(index) = ids.first_index_of(appointment.id) {
+            ids.remove(index);
+        }
+        if ids.is_empty() {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &ids);
+        }
+    }
+
+    fn load_offer(env: &Env, doctor: &Address, datetime: u64) -> Result<SlotOffer, Error> {
+        env.storage()
+            .persistent()
+            .get(&AppointmentKey::Offer(doctor.clone(), datetime))
+            .ok_or(Error::OfferNotFound)
+    }
+
+    // Offer a freed future slot to the first waitlisted patient whose window
+    // contains it. That patient leaves the waitlist; the offer holds the slot
+    // for OFFER_WINDOW or until the slot starts, whichever comes first.
+    fn offer_to_waitlist(env: &Env, doctor: &Address, datetime: u64) {
+        let now = env.ledger().timestamp();
+        if datetime <= now {
+            return;
+        }
+        let key = AppointmentKey::Waitlist(doctor.clone());
+        let mut waitlist: Vec<WaitlistEntry> = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(env));
+        let index = match waitlist
+            .iter()
+            .position(|entry| entry.earliest <= datetime && datetime < entry.latest)
+        {
+            Some(index) => index as u32,
+            None => return,
+        };
+        let entry = waitlist.get_unchecked(index);
+        waitlist.remove(index);
+        env.storage().persistent().set(&key, &waitlist);
+
+        let expires_at = if now + OFFER_WINDOW < datetime {
+            now + OFFER_WINDOW
+        } else {
+            datetime
+        };
+        let offer = SlotOffer {
+            doctor: doctor.clone(),
+            datetime,
+            patient: entry.patient.clone(),
+            expires_at,
+        };
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::Offer(doctor.clone(), datetime), &offer);
+
+        // Emit event
+        env.events().publish(
+            (symbol_short!("wl_offer"), doctor.clone()),
+            (entry.patient, datetime, expires_at),
+        );
+    }
+
+    // Drop an offer that was declined or lapsed and offer the slot to the next
+    // matching patient
+    fn pass_offer_on(env: &Env, offer: SlotOffer, topic: Symbol) {
+        env.storage()
+            .persistent()
+            .remove(&AppointmentKey::Offer(offer.doctor.clone(), offer.datetime));
+        env.events().publish(
+            (topic, offer.doctor.clone()),
+            (offer.patient, offer.datetime),
+        );
+        Self::offer_to_waitlist(env, &offer.doctor, offer.datetime);
+    }
+
+    fn take_from_waitlist(env: &Env, doctor: &Address, patient: &Address) -> bool {
+        let key = AppointmentKey::Waitlist(doctor.clone());
+        let mut waitlist: Vec<WaitlistEntry> = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(env));
+        match waitlist.iter().position(|entry| entry.patient == *patient) {
+            Some(index) => {
+                waitlist.remove(index as u32);
+                env.storage().persistent().set(&key, &waitlist);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Move the doctor's copay, if any, from the patient into this contract
+    fn lock_copay(env: &Env, patient: &Address, doctor: &Address, appointment_id: u64) {
+        let policy: CopayPolicy = match env
+            .storage()
+            .persistent()
+            .get(&AppointmentKey::CopayPolicy(doctor.clone()))
+        {
+            Some(policy) => policy,
+            None => return,
+        };
+        token::Client::new(env, &policy.token).transfer(
+            patient,
+            env.current_contract_address(),
+            &policy.amount,
+        );
+
+        let escrow = CopayEscrow {
+            token: policy.token,
+            amount: policy.amount,
+            payer: patient.clone(),
+            payee: policy.payee,
+            cancel_window: policy.cancel_window,
+            late_refund_bps: policy.late_refund_bps,
+            no_show_refund_bps: policy.no_show_refund_bps,
+        };
+        env.storage()
+            .persistent()
+            .set(&AppointmentKey::Escrow(appointment_id), &escrow);
+        env.events().publish(
+            (symbol_short!("esc_lock"), appointment_id),
+            (patient.clone(), escrow.amount),
+        );
+    }
+
+    fn take_escrow(env: &Env, appointment_id: u64) -> Option<CopayEscrow> {
+        let key = AppointmentKey::Escrow(appointment_id);
+        let escrow: Option<CopayEscrow> = env.storage().persistent().get(&key);
+        if escrow.is_some() {
+            env.storage().persistent().remove(&key);
+        }
+        escrow
+    }
+
+    // Pay the whole escrow to the payee
+    fn release_copay(env: &Env, appointment_id: u64) {
+        let escrow = match Self::take_escrow(env, appointment_id) {
+            Some(escrow) => escrow,
+            None => return,
+        };
+        token::Client::new(env, &escrow.token).transfer(
+            &env.current_contract_address(),
+            &escrow.payee,
+            &escrow.amount,
+        );
+        env.events().publish(
+            (symbol_short!("esc_rel"), appointment_id),
+            (escrow.payee, escrow.amount),
+        );
+    }
+
+    // Return the escrow on cancellation. Outside the cancellation window, or
+    // when `full` is set, the patient gets everything back; inside it they get
+    // `late_refund_bps` and the institution keeps the rest.
+    fn refund_copay(env: &Env, appointment: &Appointment, full: bool) {
+        let escrow = match Self::take_escrow(env, appointment.id) {
+            Some(escrow) => escrow,
+            None => return,
+        };
+
+        let late = env
+            .ledger()
+            .timestamp()
+            .saturating_add(escrow.cancel_window)
+            >= appointment.datetime;
+        let refund_bps = if full || !late {
+            BPS_DENOMINATOR
+        } else {
+            escrow.late_refund_bps
+        };
+        Self::split_copay(env, appointment.id, &escrow, refund_bps);
+    }
+
+    // Return the policy's no-show share of the escrow; the payee keeps the rest
+    fn refund_no_show(env: &Env, appointment_id: u64) {
+        if let Some(escrow) = Self::take_escrow(env, appointment_id) {
+            Self::split_copay(env, appointment_id, &escrow, escrow.no_show_refund_bps);
+        }
+    }
+
+    // Pay `refund_bps` of an escrow back to the patient and the rest to the payee
+    fn split_copay(env: &Env, appointment_id: u64, escrow: &CopayEscrow, refund_bps: u32) {
+        let refund = escrow.amount * refund_bps as i128 / BPS_DENOMINATOR as i128;
+        let retained = escrow.amount - refund;
+
+        let client = token::Client::new(env, &escrow.token);
+        let contract = env.current_contract_address();
+        if refund > 0 {
+            client.transfer(&contract, &escrow.payer, &refund);
+        }
+        if retained > 0 {
+            client.transfer(&contract, &escrow.payee, &retained);
+        }
+        env.events().publish(
+            (symbol_short!("esc_ref"), appointment_id),
+            (refund, retained),
+        );
+    }
+
+    // Free the slot an appointment holds so it can be booked again
+    fn release_slot(env: &Env, appointment: &Appointment) {
+        let slot_key = AppointmentKey::BookedSlot(appointment.doctor.clone(), appointment.datetime);
+        let holder: Option<u64> = env.storage().persistent().get(&slot_key);
+        if holder == Some(appointment.id) {
+            env.storage().persistent().remove(&slot_key);
+        }
+    }
 }
 
 mod test;
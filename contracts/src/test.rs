@@ -1,11 +1,15 @@
 #[cfg(test)]
 mod test {
     use crate::{
-        AppointmentScheduling, AppointmentSchedulingClient, AppointmentStatus, HealthcareRegistry,
-        HealthcareRegistryClient,
+        AppointmentFilter, AppointmentKey, AppointmentScheduling, AppointmentSchedulingClient,
+        AppointmentStatus, AvailabilityWindow, CopayPolicy, Error, HealthcareRegistry,
+        HealthcareRegistryClient, VerificationStatus,
     };
 
-    use soroban_sdk::{testutils::Address as _, Address, Env, String, Vec};
+    use soroban_sdk::{
+        testutils::{Address as _, Ledger, MockAuth, MockAuthInvoke},
+        token, vec, Address, BytesN, Env, IntoVal, String, Vec,
+    };
 
     fn setup_test(env: &Env) -> (HealthcareRegistryClient<'static>, Address, Address) {
         // Updated from register_contract to register
@@ -15,7 +19,17 @@ mod test {
         let admin = Address::generate(env);
         let institution = Address::generate(env);
 
-        client.init(&admin);
+        client
+            .mock_auths(&[MockAuth {
+                address: &admin,
+                invoke: &MockAuthInvoke {
+                    contract: &contract_id,
+                    fn_name: "init",
+                    args: (&admin,).into_val(env),
+                    sub_invokes: &[],
+                },
+            }])
+            .init(&admin);
 
         (client, admin, institution)
     }
@@ -60,19 +74,41 @@ mod test {
         client.register_institution(&inst_addr, &name, &name, &name);
     }
 
+    const YEAR: u64 = 365 * 24 * 60 * 60;
+
+    // Register an institution and a verifier appointed by the admin
+    fn setup_verifier(
+        env: &Env,
+        client: &HealthcareRegistryClient,
+        admin: &Address,
+        inst_addr: &Address,
+    ) -> Address {
+        let name = String::from_str(env, "Clinic A");
+        client.register_institution(inst_addr, &name, &name, &name);
+
+        let verifier = Address::generate(env);
+        client.add_verifier(admin, &verifier);
+        verifier
+    }
+
     #[test]
-    fn test_verification_by_admin() {
+    fn test_verification_by_verifier() {
         let env = Env::default();
         let (client, admin, inst_addr) = setup_test(&env);
         env.mock_all_auths();
+        let verifier = setup_verifier(&env, &client, &admin, &inst_addr);
 
-        let name = String::from_str(&env, "Clinic A");
-        client.register_institution(&inst_addr, &name, &name, &name);
-
-        client.verify_institution(&admin, &inst_addr);
+        let evidence = BytesN::from_array(&env, &[7; 32]);
+        client.verify_institution(&verifier, &inst_addr, &YEAR, &evidence);
 
         let data = client.get_institution(&inst_addr);
         assert_eq!(data.is_verified, true);
+
+        let record = client.get_verification(&inst_addr);
+        assert_eq!(record.verifier, verifier);
+        assert_eq!(record.expires_at, YEAR);
+        assert_eq!(record.evidence_hash, evidence);
+        assert!(client.is_institution_in_good_standing(&inst_addr));
     }
 
     #[test]
@@ -86,7 +122,156 @@ mod test {
         let name = String::from_str(&env, "Clinic A");
         client.register_institution(&inst_addr, &name, &name, &name);
 
-        client.verify_institution(&fake_admin, &inst_addr);
+        client.verify_institution(
+            &fake_admin,
+            &inst_addr,
+            &YEAR,
+            &BytesN::from_array(&env, &[0; 32]),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Not authorized to verify")]
+    fn test_admin_is_not_a_verifier() {
+        let env = Env::default();
+        let (client, admin, inst_addr) = setup_test(&env);
+        env.mock_all_auths();
+        setup_verifier(&env, &client, &admin, &inst_addr);
+
+        client.verify_institution(
+            &admin,
+            &inst_addr,
+            &YEAR,
+            &BytesN::from_array(&env, &[0; 32]),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Not authorized to verify")]
+    fn test_removed_verifier_cannot_verify() {
+        let env = Env::default();
+        let (client, admin, inst_addr) = setup_test(&env);
+        env.mock_all_auths();
+        let verifier = setup_verifier(&env, &client, &admin, &inst_addr);
+
+        client.remove_verifier(&admin, &verifier);
+        assert!(!client.is_verifier(&verifier));
+        client.verify_institution(
+            &verifier,
+            &inst_addr,
+            &YEAR,
+            &BytesN::from_array(&env, &[0; 32]),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Already initialized")]
+    fn test_init_twice_fails() {
+        let env = Env::default();
+        let (client, _, _) = setup_test(&env);
+        env.mock_all_auths();
+
+        client.init(&Address::generate(&env));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_init_requires_admin_auth() {
+        let env = Env::default();
+        let contract_id = env.register(HealthcareRegistry, ());
+        let client = HealthcareRegistryClient::new(&env, &contract_id);
+
+        client.init(&Address::generate(&env));
+    }
+
+    #[test]
+    fn test_verification_expires() {
+        let env = Env::default();
+        let (client, admin, inst_addr) = setup_test(&env);
+        env.mock_all_auths();
+        let verifier = setup_verifier(&env, &client, &admin, &inst_addr);
+
+        client.verify_institution(
+            &verifier,
+            &inst_addr,
+            &YEAR,
+            &BytesN::from_array(&env, &[1; 32]),
+        );
+        env.ledger().with_mut(|li| li.timestamp = YEAR);
+        assert!(!client.is_institution_in_good_standing(&inst_addr));
+        assert!(!client.get_institution(&inst_addr).is_verified);
+
+        // Renewal restores good standing
+        client.verify_institution(
+            &verifier,
+            &inst_addr,
+            &(2 * YEAR),
+            &BytesN::from_array(&env, &[2; 32]),
+        );
+        assert!(client.is_institution_in_good_standing(&inst_addr));
+        assert!(client.get_institution(&inst_addr).is_verified);
+        assert_eq!(client.get_verification_history(&inst_addr).len(), 2);
+    }
+
+    #[test]
+    fn test_suspend_reinstate_and_revoke() {
+        let env = Env::default();
+        let (client, admin, inst_addr) = setup_test(&env);
+        env.mock_all_auths();
+        let verifier = setup_verifier(&env, &client, &admin, &inst_addr);
+        client.verify_institution(
+            &verifier,
+            &inst_addr,
+            &YEAR,
+            &BytesN::from_array(&env, &[1; 32]),
+        );
+
+        let reason = String::from_str(&env, "Inspection pending");
+        client.suspend_institution(&verifier, &inst_addr, &reason);
+        assert!(!client.is_institution_in_good_standing(&inst_addr));
+        assert!(!client.get_institution(&inst_addr).is_verified);
+        let record = client.get_verification(&inst_addr);
+        assert_eq!(record.status, VerificationStatus::Suspended);
+        assert_eq!(record.reason, Some(reason));
+
+        client.reinstate_institution(&admin, &inst_addr);
+        assert!(client.is_institution_in_good_standing(&inst_addr));
+
+        let reason = String::from_str(&env, "License withdrawn");
+        client.revoke_verification(&admin, &inst_addr, &reason);
+        let record = client.get_verification(&inst_addr);
+        assert_eq!(record.status, VerificationStatus::Revoked);
+        assert_eq!(record.changed_by, admin);
+        assert!(!client.is_institution_in_good_standing(&inst_addr));
+
+        // Suspended, reinstated and revoked on top of the original verification
+        assert_eq!(client.get_verification_history(&inst_addr).len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid verification status change")]
+    fn test_revoked_cannot_be_reinstated() {
+        let env = Env::default();
+        let (client, admin, inst_addr) = setup_test(&env);
+        env.mock_all_auths();
+        let verifier = setup_verifier(&env, &client, &admin, &inst_addr);
+        client.verify_institution(
+            &verifier,
+            &inst_addr,
+            &YEAR,
+            &BytesN::from_array(&env, &[1; 32]),
+        );
+
+        client.revoke_verification(&verifier, &inst_addr, &String::from_str(&env, "Fraud"));
+        client.reinstate_institution(&verifier, &inst_addr);
+    }
+
+    #[test]
+    fn test_unverified_institution_not_in_good_standing() {
+        let env = Env::default();
+        let (client, _, inst_addr) = setup_test(&env);
+
+        assert!(!client.is_institution_in_good_standing(&inst_addr));
     }
 
     #[test]
@@ -148,7 +333,6 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "Unauthorized to cancel this appointment")]
     fn test_unauthorized_cancel_appointment() {
         let env = Env::default();
         let (client, patient, doctor) = setup_appointment_test(&env);
@@ -158,11 +342,13 @@ mod test {
         let datetime = 1640995200;
         let appointment_id = client.create_appointment(&patient, &doctor, &datetime);
 
-        client.cancel_appointment(&unauthorized_user, &appointment_id);
+        assert_eq!(
+            client.try_cancel_appointment(&unauthorized_user, &appointment_id),
+            Err(Ok(Error::UnauthorizedAppointmentAction))
+        );
     }
 
     #[test]
-    #[should_panic(expected = "Can only cancel scheduled appointments")]
     fn test_cancel_completed_appointment_fails() {
         let env = Env::default();
         let (client, patient, doctor) = setup_appointment_test(&env);
@@ -172,7 +358,10 @@ mod test {
         let appointment_id = client.create_appointment(&patient, &doctor, &datetime);
 
         client.complete_appointment(&doctor, &appointment_id);
-        client.cancel_appointment(&patient, &appointment_id);
+        assert_eq!(
+            client.try_cancel_appointment(&patient, &appointment_id),
+            Err(Ok(Error::InvalidAppointmentStatus))
+        );
     }
 
     #[test]
@@ -192,7 +381,6 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "Unauthorized to complete this appointment")]
     fn test_unauthorized_complete_appointment() {
         let env = Env::default();
         let (client, patient, doctor) = setup_appointment_test(&env);
@@ -202,7 +390,10 @@ mod test {
         let datetime = 1640995200;
         let appointment_id = client.create_appointment(&patient, &doctor, &datetime);
 
-        client.complete_appointment(&unauthorized_user, &appointment_id);
+        assert_eq!(
+            client.try_complete_appointment(&unauthorized_user, &appointment_id),
+            Err(Ok(Error::UnauthorizedAppointmentAction))
+        );
     }
 
     #[test]
@@ -219,9 +410,9 @@ mod test {
         let appointment_id1 = client.create_appointment(&patient, &doctor, &datetime1);
         let appointment_id2 = client.create_appointment(&patient, &doctor, &datetime2);
 
-        // Create appointment for patient2 with doctor
+        // Create appointment for patient2 with doctor, an hour after patient's
         env.mock_all_auths();
-        let appointment_id3 = client.create_appointment(&patient2, &doctor, &datetime1);
+        let appointment_id3 = client.create_appointment(&patient2, &doctor, &(datetime1 + 3600));
 
         // Check patient's appointments
         let patient_appointments = client.get_appointments(&patient);
@@ -282,6 +473,7 @@ mod test {
                 AppointmentStatus::Scheduled => scheduled_count += 1,
                 AppointmentStatus::Canceled => canceled_count += 1,
                 AppointmentStatus::Completed => completed_count += 1,
+                _ => {}
             }
         }
 
@@ -289,4 +481,807 @@ mod test {
         assert_eq!(canceled_count, 1); // id2
         assert_eq!(completed_count, 1); // id1
     }
+
+    // Availability and slot booking tests
+    const MONDAY: u64 = 1704067200; // 2024-01-01 00:00:00 UTC
+    const HOUR: u64 = 60 * 60;
+
+    // Mondays 09:00-11:00 in 30 minute slots
+    fn setup_availability(env: &Env, client: &AppointmentSchedulingClient, doctor: &Address) {
+        let windows = vec![
+            env,
+            AvailabilityWindow {
+                weekday: 0,
+                start: 9 * 60 * 60,
+                end: 11 * 60 * 60,
+            },
+        ];
+        client.set_availability(doctor, &(30 * 60), &windows);
+    }
+
+    #[test]
+    fn test_get_available_slots() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        setup_availability(&env, &client, &doctor);
+
+        // Two weeks covers two Mondays
+        let slots = client.get_available_slots(&doctor, &MONDAY, &(MONDAY + 14 * 24 * HOUR));
+        assert_eq!(slots.len(), 8);
+        assert_eq!(slots.get(0).unwrap(), MONDAY + 9 * HOUR);
+        assert_eq!(slots.get(4).unwrap(), MONDAY + 7 * 24 * HOUR + 9 * HOUR);
+
+        client.create_appointment(&patient, &doctor, &(MONDAY + 9 * HOUR));
+        let slots = client.get_available_slots(&doctor, &MONDAY, &(MONDAY + 24 * HOUR));
+        assert_eq!(slots.len(), 3);
+        assert!(!slots.contains(MONDAY + 9 * HOUR));
+    }
+
+    #[test]
+    fn test_double_booking_rejected() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        let patient2 = Address::generate(&env);
+        env.mock_all_auths();
+        setup_availability(&env, &client, &doctor);
+
+        let slot = MONDAY + 9 * HOUR;
+        client.create_appointment(&patient, &doctor, &slot);
+        assert_eq!(
+            client.try_create_appointment(&patient2, &doctor, &slot),
+            Err(Ok(Error::SlotAlreadyBooked))
+        );
+    }
+
+    #[test]
+    fn test_double_booking_rejected_without_availability() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        let patient2 = Address::generate(&env);
+        env.mock_all_auths();
+
+        let datetime = MONDAY + 14 * HOUR;
+        let appointment_id = client.create_appointment(&patient, &doctor, &datetime);
+        assert_eq!(
+            client.try_create_appointment(&patient2, &doctor, &datetime),
+            Err(Ok(Error::SlotAlreadyBooked))
+        );
+
+        // Moving the first appointment frees its time
+        client.reschedule_appointment(&patient, &appointment_id, &(MONDAY + 15 * HOUR));
+        client.create_appointment(&patient2, &doctor, &datetime);
+    }
+
+    #[test]
+    fn test_availability_change_must_keep_bookings() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        setup_availability(&env, &client, &doctor);
+
+        let appointment_id =
+            client.create_appointment(&patient, &doctor, &(MONDAY + 9 * HOUR + 30 * 60));
+
+        // Hour-long slots would leave the 09:30 booking between slots
+        let windows = vec![
+            &env,
+            AvailabilityWindow {
+                weekday: 0,
+                start: 9 * 60 * 60,
+                end: 11 * 60 * 60,
+            },
+        ];
+        assert_eq!(
+            client.try_set_availability(&doctor, &HOUR, &windows),
+            Err(Ok(Error::AvailabilityConflict))
+        );
+
+        // So would giving up Monday mornings
+        let afternoons = vec![
+            &env,
+            AvailabilityWindow {
+                weekday: 0,
+                start: 13 * 60 * 60,
+                end: 15 * 60 * 60,
+            },
+        ];
+        assert_eq!(
+            client.try_set_availability(&doctor, &(30 * 60), &afternoons),
+            Err(Ok(Error::AvailabilityConflict))
+        );
+
+        client.cancel_appointment(&patient, &appointment_id);
+        client.set_availability(&doctor, &HOUR, &windows);
+        assert_eq!(
+            client.get_availability(&doctor).unwrap().slot_duration,
+            HOUR
+        );
+    }
+
+    #[test]
+    fn test_booking_outside_availability_rejected() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        setup_availability(&env, &client, &doctor);
+
+        // Tuesday, and a Monday time that is not a slot boundary
+        for datetime in [MONDAY + 24 * HOUR + 9 * HOUR, MONDAY + 9 * HOUR + 60] {
+            assert_eq!(
+                client.try_create_appointment(&patient, &doctor, &datetime),
+                Err(Ok(Error::SlotUnavailable))
+            );
+        }
+    }
+
+    #[test]
+    fn test_blocked_date() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        setup_availability(&env, &client, &doctor);
+
+        client.block_date(&doctor, &(MONDAY + 12 * HOUR));
+        assert_eq!(client.get_blocked_dates(&doctor), vec![&env, MONDAY]);
+        assert_eq!(
+            client
+                .get_available_slots(&doctor, &MONDAY, &(MONDAY + 24 * HOUR))
+                .len(),
+            0
+        );
+        assert_eq!(
+            client.try_create_appointment(&patient, &doctor, &(MONDAY + 9 * HOUR)),
+            Err(Ok(Error::SlotUnavailable))
+        );
+
+        client.unblock_date(&doctor, &MONDAY);
+        client.create_appointment(&patient, &doctor, &(MONDAY + 9 * HOUR));
+    }
+
+    #[test]
+    fn test_cancel_releases_slot() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        let patient2 = Address::generate(&env);
+        env.mock_all_auths();
+        setup_availability(&env, &client, &doctor);
+
+        let slot = MONDAY + 10 * HOUR;
+        let appointment_id = client.create_appointment(&patient, &doctor, &slot);
+        client.cancel_appointment(&patient, &appointment_id);
+
+        client.create_appointment(&patient2, &doctor, &slot);
+    }
+
+    #[test]
+    fn test_invalid_availability_and_range() {
+        let env = Env::default();
+        let (client, _, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let windows = vec![
+            &env,
+            AvailabilityWindow {
+                weekday: 7,
+                start: 0,
+                end: 3600,
+            },
+        ];
+        assert_eq!(
+            client.try_set_availability(&doctor, &1800, &windows),
+            Err(Ok(Error::InvalidAvailability))
+        );
+        assert_eq!(
+            client.try_get_available_slots(&doctor, &MONDAY, &(MONDAY + 40 * 24 * HOUR)),
+            Err(Ok(Error::InvalidTimeRange))
+        );
+    }
+
+    // Appointment lifecycle tests
+    #[test]
+    fn test_reschedule_keeps_history() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let appointment_id = client.create_appointment(&patient, &doctor, &1640995200);
+        client.reschedule_appointment(&patient, &appointment_id, &1641081600);
+        client.reschedule_appointment(&doctor, &appointment_id, &1641168000);
+
+        let appointment = client.get_appointment(&appointment_id);
+        assert_eq!(appointment.datetime, 1641168000);
+        assert_eq!(appointment.status, AppointmentStatus::Scheduled);
+
+        let history = client.get_reschedule_history(&appointment_id);
+        assert_eq!(history.len(), 2);
+        let first = history.get(0).unwrap();
+        assert_eq!(first.previous_datetime, 1640995200);
+        assert_eq!(first.new_datetime, 1641081600);
+        assert_eq!(first.changed_by, patient);
+        assert_eq!(history.get(1).unwrap().changed_by, doctor);
+
+        let stranger = Address::generate(&env);
+        assert_eq!(
+            client.try_reschedule_appointment(&stranger, &appointment_id, &1641254400),
+            Err(Ok(Error::UnauthorizedAppointmentAction))
+        );
+    }
+
+    #[test]
+    fn test_reschedule_moves_slot() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        setup_availability(&env, &client, &doctor);
+
+        let appointment_id = client.create_appointment(&patient, &doctor, &(MONDAY + 9 * HOUR));
+        let other = client.create_appointment(&patient, &doctor, &(MONDAY + 10 * HOUR));
+
+        assert_eq!(
+            client.try_reschedule_appointment(&patient, &appointment_id, &(MONDAY + 10 * HOUR)),
+            Err(Ok(Error::SlotAlreadyBooked))
+        );
+        assert_eq!(
+            client.try_reschedule_appointment(&patient, &appointment_id, &(MONDAY + 12 * HOUR)),
+            Err(Ok(Error::SlotUnavailable))
+        );
+
+        client.reschedule_appointment(&patient, &appointment_id, &(MONDAY + 9 * HOUR + 30 * 60));
+        let slots = client.get_available_slots(&doctor, &MONDAY, &(MONDAY + 24 * HOUR));
+        assert!(slots.contains(MONDAY + 9 * HOUR));
+        assert!(!slots.contains(MONDAY + 9 * HOUR + 30 * 60));
+        assert!(!slots.contains(MONDAY + 10 * HOUR));
+        assert_eq!(client.get_appointment(&other).datetime, MONDAY + 10 * HOUR);
+    }
+
+    #[test]
+    fn test_check_in_then_complete() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let appointment_id = client.create_appointment(&patient, &doctor, &1640995200);
+        client.check_in(&patient, &appointment_id);
+        assert_eq!(
+            client.get_appointment(&appointment_id).status,
+            AppointmentStatus::CheckedIn
+        );
+
+        // A checked-in appointment can no longer be moved or marked as a no-show
+        assert_eq!(
+            client.try_reschedule_appointment(&patient, &appointment_id, &1641081600),
+            Err(Ok(Error::InvalidAppointmentStatus))
+        );
+        assert_eq!(
+            client.try_mark_no_show(&doctor, &appointment_id),
+            Err(Ok(Error::InvalidAppointmentStatus))
+        );
+
+        client.complete_appointment(&doctor, &appointment_id);
+        assert_eq!(
+            client.get_appointment(&appointment_id).status,
+            AppointmentStatus::Completed
+        );
+        assert_eq!(
+            client.try_check_in(&patient, &appointment_id),
+            Err(Ok(Error::InvalidAppointmentStatus))
+        );
+    }
+
+    #[test]
+    fn test_mark_no_show() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let appointment_id = client.create_appointment(&patient, &doctor, &1640995200);
+        assert_eq!(
+            client.try_mark_no_show(&patient, &appointment_id),
+            Err(Ok(Error::UnauthorizedAppointmentAction))
+        );
+        assert_eq!(
+            client.try_mark_no_show(&doctor, &appointment_id),
+            Err(Ok(Error::AppointmentNotStarted))
+        );
+
+        env.ledger().set_timestamp(1640995200);
+        client.mark_no_show(&doctor, &appointment_id);
+        assert_eq!(
+            client.get_appointment(&appointment_id).status,
+            AppointmentStatus::NoShow
+        );
+        assert_eq!(
+            client.try_complete_appointment(&doctor, &appointment_id),
+            Err(Ok(Error::InvalidAppointmentStatus))
+        );
+    }
+
+    #[test]
+    fn test_cancel_by_provider() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        setup_availability(&env, &client, &doctor);
+
+        let appointment_id = client.create_appointment(&patient, &doctor, &(MONDAY + 9 * HOUR));
+        let reason = String::from_str(&env, "Doctor unavailable");
+        assert_eq!(
+            client.try_cancel_by_provider(&patient, &appointment_id, &reason),
+            Err(Ok(Error::UnauthorizedAppointmentAction))
+        );
+
+        client.cancel_by_provider(&doctor, &appointment_id, &reason);
+        let appointment = client.get_appointment(&appointment_id);
+        assert_eq!(appointment.status, AppointmentStatus::Canceled);
+        assert_eq!(appointment.cancel_reason, Some(reason.clone()));
+
+        // The slot is free again
+        let slots = client.get_available_slots(&doctor, &MONDAY, &(MONDAY + 24 * HOUR));
+        assert!(slots.contains(MONDAY + 9 * HOUR));
+
+        assert_eq!(
+            client.try_cancel_by_provider(&doctor, &appointment_id, &reason),
+            Err(Ok(Error::InvalidAppointmentStatus))
+        );
+        assert_eq!(
+            client.try_get_appointment(&99),
+            Err(Ok(Error::AppointmentNotFound))
+        );
+    }
+
+    // Appointment query tests
+    fn any_appointment(env: &Env) -> AppointmentFilter {
+        AppointmentFilter {
+            statuses: Vec::new(env),
+            from: 0,
+            to: 0,
+        }
+    }
+
+    #[test]
+    fn test_appointments_page_cursor() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        for day in 0..5 {
+            client.create_appointment(&patient, &doctor, &(MONDAY + day * 24 * HOUR));
+        }
+
+        let filter = any_appointment(&env);
+        let page = client.get_appointments_page(&patient, &filter, &0, &2);
+        assert_eq!(page.appointments.len(), 2);
+        assert_eq!(page.appointments.get(0).unwrap().id, 1);
+        assert_eq!(page.next_cursor, Some(2));
+
+        let page = client.get_appointments_page(&patient, &filter, &2, &2);
+        assert_eq!(page.appointments.get(0).unwrap().id, 3);
+        assert_eq!(page.next_cursor, Some(4));
+
+        let page = client.get_appointments_page(&patient, &filter, &4, &2);
+        assert_eq!(page.appointments.len(), 1);
+        assert_eq!(page.next_cursor, None);
+        assert!(!page.scan_limited);
+    }
+
+    #[test]
+    fn test_appointments_page_continues_past_scan_limit() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let mut last = 0;
+        for hour in 0..205 {
+            last = client.create_appointment(&patient, &doctor, &(MONDAY + hour * HOUR));
+        }
+        client.complete_appointment(&doctor, &last);
+
+        let completed = AppointmentFilter {
+            statuses: vec![&env, AppointmentStatus::Completed],
+            from: 0,
+            to: 0,
+        };
+        // The first call gives up after 200 entries but says where to resume
+        let page = client.get_appointments_page(&patient, &completed, &0, &10);
+        assert_eq!(page.appointments.len(), 0);
+        assert!(page.scan_limited);
+        assert_eq!(page.next_cursor, Some(200));
+
+        let page = client.get_appointments_page(&patient, &completed, &200, &10);
+        assert_eq!(page.appointments.len(), 1);
+        assert_eq!(page.appointments.get(0).unwrap().id, last);
+        assert!(!page.scan_limited);
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[test]
+    fn test_appointments_page_filters() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let id1 = client.create_appointment(&patient, &doctor, &MONDAY);
+        let id2 = client.create_appointment(&patient, &doctor, &(MONDAY + 24 * HOUR));
+        let id3 = client.create_appointment(&patient, &doctor, &(MONDAY + 48 * HOUR));
+        client.cancel_appointment(&patient, &id2);
+        client.complete_appointment(&doctor, &id1);
+
+        let scheduled = AppointmentFilter {
+            statuses: vec![&env, AppointmentStatus::Scheduled],
+            from: 0,
+            to: 0,
+        };
+        let page = client.get_appointments_page(&patient, &scheduled, &0, &10);
+        assert_eq!(page.appointments.len(), 1);
+        assert_eq!(page.appointments.get(0).unwrap().id, id3);
+
+        let first_two_days = AppointmentFilter {
+            statuses: Vec::new(&env),
+            from: MONDAY,
+            to: MONDAY + 48 * HOUR,
+        };
+        let page = client.get_appointments_page(&doctor, &first_two_days, &0, &10);
+        assert_eq!(page.appointments.len(), 2);
+        assert_eq!(page.appointments.get(1).unwrap().id, id2);
+        assert_eq!(page.next_cursor, None);
+
+        let backwards = AppointmentFilter {
+            statuses: Vec::new(&env),
+            from: MONDAY + HOUR,
+            to: MONDAY,
+        };
+        assert_eq!(
+            client.try_get_appointments_page(&patient, &backwards, &0, &10),
+            Err(Ok(Error::InvalidTimeRange))
+        );
+    }
+
+    #[test]
+    fn test_day_sheet() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let late = client.create_appointment(&patient, &doctor, &(MONDAY + 15 * HOUR));
+        let early = client.create_appointment(&patient, &doctor, &(MONDAY + 9 * HOUR));
+        let next_day = client.create_appointment(&patient, &doctor, &(MONDAY + 33 * HOUR));
+
+        let sheet = client.get_day_sheet(&doctor, &(MONDAY + 12 * HOUR));
+        assert_eq!(sheet.len(), 2);
+        assert_eq!(sheet.get(0).unwrap().id, early);
+        assert_eq!(sheet.get(1).unwrap().id, late);
+
+        // Rescheduling moves the appointment between day sheets
+        client.reschedule_appointment(&patient, &next_day, &(MONDAY + 12 * HOUR));
+        let sheet = client.get_day_sheet(&doctor, &MONDAY);
+        assert_eq!(sheet.len(), 3);
+        assert_eq!(sheet.get(1).unwrap().id, next_day);
+        assert_eq!(
+            client.get_day_sheet(&doctor, &(MONDAY + 24 * HOUR)).len(),
+            0
+        );
+
+        client.cancel_appointment(&patient, &early);
+        let sheet = client.get_day_sheet(&doctor, &MONDAY);
+        assert_eq!(sheet.len(), 2);
+        assert_eq!(sheet.get(0).unwrap().id, next_day);
+    }
+
+    #[test]
+    fn test_day_sheet_includes_appointments_booked_before_index() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let older = client.create_appointment(&patient, &doctor, &(MONDAY + 10 * HOUR));
+        let cancelled = client.create_appointment(&patient, &doctor, &(MONDAY + 11 * HOUR));
+        // Put storage back the way it was before days were indexed
+        env.as_contract(&client.address, || {
+            let storage = env.storage().persistent();
+            storage.remove(&AppointmentKey::DoctorDay(doctor.clone(), MONDAY));
+            storage.remove(&AppointmentKey::DayIndexStart);
+        });
+        client.cancel_appointment(&patient, &cancelled);
+
+        let newer = client.create_appointment(&patient, &doctor, &(MONDAY + 9 * HOUR));
+        let sheet = client.get_day_sheet(&doctor, &MONDAY);
+        assert_eq!(sheet.len(), 2);
+        assert_eq!(sheet.get(0).unwrap().id, newer);
+        assert_eq!(sheet.get(1).unwrap().id, older);
+
+        // An older appointment rescheduled into the index is listed once
+        client.reschedule_appointment(&patient, &older, &(MONDAY + 12 * HOUR));
+        let sheet = client.get_day_sheet(&doctor, &MONDAY);
+        assert_eq!(sheet.len(), 2);
+        assert_eq!(sheet.get(1).unwrap().id, older);
+    }
+
+    // Waitlist tests
+    #[test]
+    fn test_cancellation_offers_slot_to_waitlist() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        setup_availability(&env, &client, &doctor);
+
+        let slot = MONDAY + 9 * HOUR;
+        let appointment_id = client.create_appointment(&patient, &doctor, &slot);
+
+        // Only the second patient's window contains the slot
+        let evening = Address::generate(&env);
+        let morning = Address::generate(&env);
+        client.join_waitlist(
+            &evening,
+            &doctor,
+            &(MONDAY + 17 * HOUR),
+            &(MONDAY + 20 * HOUR),
+        );
+        client.join_waitlist(&morning, &doctor, &MONDAY, &(MONDAY + 12 * HOUR));
+        assert_eq!(
+            client.try_join_waitlist(&morning, &doctor, &MONDAY, &(MONDAY + 12 * HOUR)),
+            Err(Ok(Error::AlreadyWaitlisted))
+        );
+
+        client.cancel_appointment(&patient, &appointment_id);
+        let offer = client.get_offer(&doctor, &slot).unwrap();
+        assert_eq!(offer.patient, morning);
+        assert_eq!(client.get_waitlist(&doctor).len(), 1);
+
+        // The slot is held for the offered patient
+        let walk_in = Address::generate(&env);
+        assert_eq!(
+            client.try_create_appointment(&walk_in, &doctor, &slot),
+            Err(Ok(Error::SlotAlreadyBooked))
+        );
+        assert_eq!(
+            client.try_accept_offer(&walk_in, &doctor, &slot),
+            Err(Ok(Error::UnauthorizedAppointmentAction))
+        );
+
+        let new_id = client.accept_offer(&morning, &doctor, &slot);
+        let appointment = client.get_appointment(&new_id);
+        assert_eq!(appointment.patient, morning);
+        assert_eq!(appointment.datetime, slot);
+        assert_eq!(client.get_offer(&doctor, &slot), None);
+    }
+
+    #[test]
+    fn test_lapsed_offer_moves_to_next_patient() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        let slot = MONDAY + 9 * HOUR;
+        let appointment_id = client.create_appointment(&patient, &doctor, &slot);
+        let first = Address::generate(&env);
+        let second = Address::generate(&env);
+        client.join_waitlist(&first, &doctor, &MONDAY, &(MONDAY + 24 * HOUR));
+        client.join_waitlist(&second, &doctor, &MONDAY, &(MONDAY + 24 * HOUR));
+
+        client.cancel_by_provider(
+            &doctor,
+            &appointment_id,
+            &String::from_str(&env, "Emergency surgery"),
+        );
+        assert_eq!(client.get_offer(&doctor, &slot).unwrap().patient, first);
+        assert_eq!(
+            client.try_advance_offer(&doctor, &slot),
+            Err(Ok(Error::InvalidAppointmentStatus))
+        );
+
+        env.ledger().with_mut(|li| li.timestamp += 2 * HOUR);
+        assert_eq!(
+            client.try_accept_offer(&first, &doctor, &slot),
+            Err(Ok(Error::OfferExpired))
+        );
+        client.advance_offer(&doctor, &slot);
+        assert_eq!(client.get_offer(&doctor, &slot).unwrap().patient, second);
+
+        // Declining with nobody left on the waitlist frees the slot
+        client.decline_offer(&second, &doctor, &slot);
+        assert_eq!(client.get_offer(&doctor, &slot), None);
+        assert_eq!(client.get_waitlist(&doctor).len(), 0);
+        assert_eq!(
+            client.try_accept_offer(&second, &doctor, &slot),
+            Err(Ok(Error::OfferNotFound))
+        );
+        client.create_appointment(&first, &doctor, &slot);
+    }
+
+    #[test]
+    fn test_leave_waitlist() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+
+        assert_eq!(
+            client.try_join_waitlist(&patient, &doctor, &(MONDAY + HOUR), &MONDAY),
+            Err(Ok(Error::InvalidTimeRange))
+        );
+        client.join_waitlist(&patient, &doctor, &MONDAY, &(MONDAY + HOUR));
+        client.leave_waitlist(&patient, &doctor);
+        assert_eq!(client.get_waitlist(&doctor).len(), 0);
+        assert_eq!(
+            client.try_leave_waitlist(&patient, &doctor),
+            Err(Ok(Error::NotWaitlisted))
+        );
+    }
+
+    // Copay escrow tests
+    const COPAY: i128 = 1_000;
+
+    // A token with the patient funded, and a copay policy on the doctor that
+    // refunds 25% when cancelling within a day of the appointment and 50% on
+    // a no-show
+    fn setup_copay(
+        env: &Env,
+        client: &AppointmentSchedulingClient,
+        patient: &Address,
+        doctor: &Address,
+    ) -> (token::Client<'static>, Address) {
+        let issuer = Address::generate(env);
+        let asset = env.register_stellar_asset_contract_v2(issuer);
+        token::StellarAssetClient::new(env, &asset.address()).mint(patient, &(10 * COPAY));
+
+        let institution = Address::generate(env);
+        client.affiliate_doctor(&institution, doctor);
+        client.set_copay_policy(
+            doctor,
+            &CopayPolicy {
+                token: asset.address(),
+                amount: COPAY,
+                payee: institution.clone(),
+                cancel_window: 24 * HOUR,
+                late_refund_bps: 2_500,
+                no_show_refund_bps: 5_000,
+            },
+        );
+        (token::Client::new(env, &asset.address()), institution)
+    }
+
+    #[test]
+    fn test_copay_released_on_completion() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        let (token, institution) = setup_copay(&env, &client, &patient, &doctor);
+
+        let appointment_id = client.create_appointment(&patient, &doctor, &MONDAY);
+        assert_eq!(token.balance(&patient), 9 * COPAY);
+        assert_eq!(token.balance(&client.address), COPAY);
+        assert_eq!(
+            client.get_escrow(&appointment_id).unwrap().payee,
+            institution
+        );
+
+        client.complete_appointment(&doctor, &appointment_id);
+        assert_eq!(token.balance(&institution), COPAY);
+        assert_eq!(token.balance(&client.address), 0);
+        assert_eq!(client.get_escrow(&appointment_id), None);
+    }
+
+    #[test]
+    fn test_copay_refunds_on_cancellation() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        let (token, institution) = setup_copay(&env, &client, &patient, &doctor);
+
+        // Early cancellation: full refund
+        let early = client.create_appointment(&patient, &doctor, &MONDAY);
+        client.cancel_appointment(&patient, &early);
+        assert_eq!(token.balance(&patient), 10 * COPAY);
+
+        // Late cancellation: 25% back, the institution keeps the rest
+        let late = client.create_appointment(&patient, &doctor, &MONDAY);
+        env.ledger().with_mut(|li| li.timestamp = MONDAY - HOUR);
+        client.cancel_appointment(&patient, &late);
+        assert_eq!(token.balance(&patient), 9 * COPAY + COPAY / 4);
+        assert_eq!(token.balance(&institution), COPAY * 3 / 4);
+
+        // Provider cancellation is always refunded in full
+        let by_provider = client.create_appointment(&patient, &doctor, &(MONDAY - HOUR / 2));
+        client.cancel_by_provider(&doctor, &by_provider, &String::from_str(&env, "Sick"));
+        assert_eq!(token.balance(&patient), 9 * COPAY + COPAY / 4);
+        assert_eq!(token.balance(&client.address), 0);
+    }
+
+    #[test]
+    fn test_copay_split_on_no_show() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        let (token, institution) = setup_copay(&env, &client, &patient, &doctor);
+
+        let appointment_id = client.create_appointment(&patient, &doctor, &MONDAY);
+        env.ledger().with_mut(|li| li.timestamp = MONDAY + HOUR);
+        client.mark_no_show(&doctor, &appointment_id);
+
+        assert_eq!(token.balance(&patient), 9 * COPAY + COPAY / 2);
+        assert_eq!(token.balance(&institution), COPAY / 2);
+        assert_eq!(client.get_escrow(&appointment_id), None);
+    }
+
+    #[test]
+    fn test_copay_cancel_with_unbounded_window() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        let (token, _) = setup_copay(&env, &client, &patient, &doctor);
+        let mut policy = client.get_copay_policy(&doctor).unwrap();
+        policy.cancel_window = u64::MAX;
+        client.set_copay_policy(&doctor, &policy);
+
+        // Every cancellation counts as late rather than overflowing
+        env.ledger().with_mut(|li| li.timestamp = HOUR);
+        let appointment_id = client.create_appointment(&patient, &doctor, &MONDAY);
+        client.cancel_appointment(&patient, &appointment_id);
+        assert_eq!(token.balance(&patient), 9 * COPAY + COPAY / 4);
+    }
+
+    #[test]
+    fn test_copay_policy_validation() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        let (token, _) = setup_copay(&env, &client, &patient, &doctor);
+
+        let mut policy = client.get_copay_policy(&doctor).unwrap();
+        policy.late_refund_bps = 10_001;
+        assert_eq!(
+            client.try_set_copay_policy(&doctor, &policy),
+            Err(Ok(Error::InvalidCopayPolicy))
+        );
+        policy.late_refund_bps = 0;
+        policy.no_show_refund_bps = 10_001;
+        assert_eq!(
+            client.try_set_copay_policy(&doctor, &policy),
+            Err(Ok(Error::InvalidCopayPolicy))
+        );
+
+        // Without a policy no copay is taken
+        client.clear_copay_policy(&doctor);
+        let appointment_id = client.create_appointment(&patient, &doctor, &MONDAY);
+        assert_eq!(client.get_escrow(&appointment_id), None);
+        assert_eq!(token.balance(&patient), 10 * COPAY);
+    }
+
+    #[test]
+    fn test_copay_payee_must_be_affiliated() {
+        let env = Env::default();
+        let (client, patient, doctor) = setup_appointment_test(&env);
+        env.mock_all_auths();
+        let (_, institution) = setup_copay(&env, &client, &patient, &doctor);
+        let mut policy = client.get_copay_policy(&doctor).unwrap();
+
+        // An address that never took the doctor on cannot be paid
+        policy.payee = Address::generate(&env);
+        assert_eq!(
+            client.try_set_copay_policy(&doctor, &policy),
+            Err(Ok(Error::PayeeNotAffiliated))
+        );
+
+        // The doctor may always collect for themselves
+        policy.payee = doctor.clone();
+        client.set_copay_policy(&doctor, &policy);
+
+        // Another institution cannot end someone else's affiliation
+        let stranger = Address::generate(&env);
+        assert_eq!(
+            client.try_end_affiliation(&stranger, &doctor),
+            Err(Ok(Error::NotFound))
+        );
+
+        // Ending the affiliation drops a policy that pays the institution
+        policy.payee = institution.clone();
+        client.set_copay_policy(&doctor, &policy);
+        client.end_affiliation(&institution, &doctor);
+        assert_eq!(client.get_affiliation(&doctor), None);
+        assert_eq!(client.get_copay_policy(&doctor), None);
+        assert_eq!(
+            client.try_set_copay_policy(&doctor, &policy),
+            Err(Ok(Error::PayeeNotAffiliated))
+        );
+    }
 }